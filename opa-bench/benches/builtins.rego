@@ -0,0 +1,9 @@
+package test
+
+allow {
+    names := [upper(trim(item.name, " ")) | item := input.items[_]]
+    parts := [split(name, "-") | name := names[_]]
+    sorted := sort(names)
+    count(sorted) == count(parts)
+    re_match("^ITEM-[0-9]+$", sorted[0])
+}
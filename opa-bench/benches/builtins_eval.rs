@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Item {
+    name: String,
+}
+
+#[derive(Serialize)]
+struct Input {
+    items: Vec<Item>,
+}
+
+fn input(len: usize) -> Input {
+    let items = (0..len)
+        .map(|id| Item {
+            name: format!(" item-{} ", id),
+        })
+        .collect();
+    Input { items }
+}
+
+// Every builtin call transcodes its arguments and result between wasm
+// memory and Value, so this tracks the cost of that path.
+pub fn bench_builtins_eval(c: &mut Criterion) {
+    let query = "data.test.allow";
+    let mut module_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    module_path.push("benches/builtins.rego");
+    let module = std::fs::read_to_string(&module_path).unwrap();
+    let wasm = opa_go::wasm::compile(query, &module_path).unwrap();
+
+    let go = opa_go::Rego::new(query, "test", module.as_str()).unwrap();
+    let mut wasm = opa_wasm::Policy::from_wasm(&wasm).unwrap();
+
+    let mut group = c.benchmark_group("builtins eval");
+
+    for len in &[1, 100] {
+        let input = input(*len);
+
+        group.bench_with_input(BenchmarkId::new("go", len), &input, |b, input| {
+            b.iter(|| {
+                let result = go.eval_bool(black_box(input)).unwrap();
+                assert_eq!(true, result);
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("wasm", len), &input, |b, input| {
+            b.iter(|| {
+                let result = wasm.evaluate(black_box(input));
+                assert!(result.is_ok());
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_builtins_eval);
+criterion_main!(benches);
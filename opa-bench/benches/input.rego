@@ -0,0 +1,5 @@
+package test
+
+allow {
+    count(input.items) > 0
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Item {
+    id: usize,
+    name: String,
+    tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Input {
+    items: Vec<Item>,
+}
+
+fn input(len: usize) -> Input {
+    let items = (0..len)
+        .map(|id| Item {
+            id,
+            name: format!("item-{}", id),
+            tags: vec!["a".to_string(), "b".to_string()],
+        })
+        .collect();
+    Input { items }
+}
+
+pub fn bench_input_eval(c: &mut Criterion) {
+    let mut module_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    module_path.push("benches/input.rego");
+    let wasm = opa_go::wasm::compile("data.test.allow", &module_path).unwrap();
+    let mut policy = opa_wasm::Policy::from_wasm(&wasm).unwrap();
+
+    let mut group = c.benchmark_group("input eval");
+
+    for len in &[1, 100, 1000] {
+        let input = input(*len);
+
+        group.bench_with_input(BenchmarkId::new("to_instance", len), &input, |b, input| {
+            b.iter(|| {
+                let result = policy.evaluate(black_box(input));
+                assert!(result.is_ok());
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("buffered", len), &input, |b, input| {
+            b.iter(|| {
+                let result = policy.evaluate_buffered(black_box(input));
+                assert!(result.is_ok());
+            })
+        });
+
+        let prepared = policy.prepare_input(&input).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("prepared", len),
+            &prepared,
+            |b, prepared| {
+                b.iter(|| {
+                    let result = policy.evaluate_prepared(black_box(prepared));
+                    assert!(result.is_ok());
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+pub fn bench_char_input(c: &mut Criterion) {
+    let mut module_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    module_path.push("benches/input.rego");
+    let wasm = opa_go::wasm::compile("data.test.allow", &module_path).unwrap();
+    let mut policy = opa_wasm::Policy::from_wasm(&wasm).unwrap();
+
+    let mut group = c.benchmark_group("char input eval");
+
+    for len in &[100, 10_000] {
+        let input = "abc☃".chars().cycle().take(*len).collect::<Vec<char>>();
+
+        group.bench_with_input(BenchmarkId::new("to_instance", len), &input, |b, input| {
+            b.iter(|| {
+                let result = policy.evaluate(black_box(input));
+                assert!(result.is_ok());
+            })
+        });
+    }
+
+    group.finish();
+}
+
+pub fn bench_wide_object_input(c: &mut Criterion) {
+    let mut module_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    module_path.push("benches/input.rego");
+    let wasm = opa_go::wasm::compile("data.test.allow", &module_path).unwrap();
+    let mut policy = opa_wasm::Policy::from_wasm(&wasm).unwrap();
+
+    let mut group = c.benchmark_group("wide object input eval");
+
+    for len in &[100, 10_000] {
+        let input = (0..*len)
+            .map(|i| (format!("key-{}", i), i))
+            .collect::<HashMap<String, usize>>();
+
+        group.bench_with_input(BenchmarkId::new("to_instance", len), &input, |b, input| {
+            b.iter(|| {
+                let result = policy.evaluate(black_box(input));
+                assert!(result.is_ok());
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_input_eval,
+    bench_char_input,
+    bench_wide_object_input
+);
+criterion_main!(benches);
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Input {
+    items: Vec<usize>,
+}
+
+pub fn bench_pool_eval(c: &mut Criterion) {
+    let mut module_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    module_path.push("benches/input.rego");
+    let wasm = opa_go::wasm::compile("data.test.allow", &module_path).unwrap();
+    let mut policy = opa_wasm::Policy::from_wasm(&wasm).unwrap();
+    let pool = opa_wasm::PolicyPool::new(wasm, 0).unwrap();
+
+    let mut group = c.benchmark_group("pool eval");
+
+    for len in &[100, 1000] {
+        let inputs = (0..*len)
+            .map(|i| Input {
+                items: (0..i % 10).collect(),
+            })
+            .collect::<Vec<_>>();
+        group.throughput(Throughput::Elements(*len as u64));
+
+        group.bench_with_input(BenchmarkId::new("sequential", len), &inputs, |b, inputs| {
+            b.iter(|| {
+                for input in black_box(inputs) {
+                    let result = policy.evaluate(input);
+                    assert!(result.is_ok());
+                }
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("parallel", len), &inputs, |b, inputs| {
+            b.iter(|| {
+                let result = pool.evaluate_par::<_, opa_wasm::Value>(black_box(inputs));
+                assert!(result.is_ok());
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_pool_eval);
+criterion_main!(benches);
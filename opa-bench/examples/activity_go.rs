@@ -0,0 +1,19 @@
+use std::path::PathBuf;
+
+use opa_bench::activity::{Activity, Operation};
+
+fn main() {
+    let query = "data.test.allow";
+    let mut module_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    module_path.push("examples/activity.rego");
+    let module = std::fs::read_to_string(&module_path).unwrap();
+
+    let go = opa_go::Rego::new(query, "test", module.as_str()).unwrap();
+
+    for _i in 0..1_000_000 {
+        let operation = Operation::new_connect();
+        let activity = Activity::new("auth_id".to_string(), "client_id".to_string(), operation);
+        let result = go.eval_bool(&activity).unwrap();
+        assert_eq!(true, result);
+    }
+}
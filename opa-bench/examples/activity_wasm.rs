@@ -0,0 +1,19 @@
+use std::path::PathBuf;
+
+use opa_bench::activity::{Activity, Operation};
+
+fn main() {
+    let query = "data.test.allow";
+    let mut module_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    module_path.push("examples/activity.rego");
+    let wasm = opa_go::wasm::compile(query, &module_path).unwrap();
+
+    let mut wasm = opa_wasm::Policy::from_wasm(&wasm).unwrap();
+
+    for _i in 0..10_000_000 {
+        let operation = Operation::new_connect();
+        let activity = Activity::new("auth_id".to_string(), "client_id".to_string(), operation);
+        let result: Option<bool> = wasm.evaluate_opt(&activity).unwrap();
+        assert_eq!(Some(true), result);
+    }
+}
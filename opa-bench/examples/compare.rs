@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use opa_bench::activity::{Activity, Operation};
+use opa_bench::harness::{self, Report};
+
+const ITERATIONS: u32 = 100_000;
+
+fn activity() -> Activity {
+    let operation = Operation::new_connect();
+    Activity::new("auth_id".to_string(), "client_id".to_string(), operation)
+}
+
+fn main() {
+    let query = "data.test.allow";
+    let mut module_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    module_path.push("examples/activity.rego");
+    let module = std::fs::read_to_string(&module_path).unwrap();
+    let wasm = opa_go::wasm::compile(query, &module_path).unwrap();
+
+    let go = opa_go::Rego::new(query, "test", module.as_str()).unwrap();
+    let mut wasm = opa_wasm::Policy::from_wasm(&wasm).unwrap();
+    let mut rego = opa_rego::Policy::from_query(query, &[module.as_str()]).unwrap();
+
+    let mut report = Report::new();
+    report.push(harness::time("go", ITERATIONS, || {
+        let result = go.eval_bool(&activity()).unwrap();
+        assert_eq!(true, result);
+    }));
+    report.push(harness::time("wasm", ITERATIONS, || {
+        let result: Option<bool> = wasm.evaluate_opt(&activity()).unwrap();
+        assert_eq!(Some(true), result);
+    }));
+    report.push(harness::time("rust-rego", ITERATIONS, || {
+        let result: bool = rego.evaluate(activity()).unwrap();
+        assert_eq!(true, result);
+    }));
+
+    print!("{}", report);
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// A small timing harness for the examples, which run the same policy
+// through each backend and print a comparison. The criterion benches are
+// the better tool for precise numbers; this is meant for a quick look or
+// for running a single backend under a profiler.
+
+#[derive(Clone, Debug)]
+pub struct Timing {
+    pub backend: &'static str,
+    pub iterations: u32,
+    pub total: Duration,
+}
+
+impl Timing {
+    pub fn per_iter(&self) -> Duration {
+        self.total / self.iterations.max(1)
+    }
+}
+
+/// Runs `f` `iterations` times, after a warmup of a tenth as many runs.
+pub fn time<F: FnMut()>(backend: &'static str, iterations: u32, mut f: F) -> Timing {
+    for _ in 0..iterations / 10 {
+        f();
+    }
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let total = start.elapsed();
+
+    Timing {
+        backend,
+        iterations,
+        total,
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    timings: Vec<Timing>,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, timing: Timing) {
+        self.timings.push(timing);
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fastest = self
+            .timings
+            .iter()
+            .map(Timing::per_iter)
+            .min()
+            .unwrap_or_default();
+
+        writeln!(
+            f,
+            "{:<12} {:>12} {:>14} {:>10}",
+            "backend", "iterations", "per iter", "relative"
+        )?;
+        for timing in &self.timings {
+            let relative = timing.per_iter().as_secs_f64() / fastest.as_secs_f64().max(1e-12);
+            writeln!(
+                f,
+                "{:<12} {:>12} {:>14} {:>9.2}x",
+                timing.backend,
+                timing.iterations,
+                format!("{:?}", timing.per_iter()),
+                relative
+            )?;
+        }
+        Ok(())
+    }
+}
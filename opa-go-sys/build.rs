@@ -13,10 +13,15 @@ fn main() {
         .parse_callbacks(Box::new(bindgen::CargoCallbacks))
         .whitelist_function("Free")
         .whitelist_function("RegoNew")
+        .whitelist_function("RegoNewMulti")
+        .whitelist_function("RegoNewWithData")
         .whitelist_function("RegoDrop")
         .whitelist_function("RegoEval")
         .whitelist_function("RegoEvalBool")
+        .whitelist_function("RegoEvalExplain")
+        .whitelist_function("RegoPartial")
         .whitelist_function("WasmBuild")
+        .whitelist_function("WasmBuildWithData")
         .clang_arg("-I/usr/arm-linux-gnueabihf/include")
         .generate()
         .expect("Unable to generate bindings");
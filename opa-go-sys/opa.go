@@ -6,28 +6,97 @@ import "C"
 import (
 	"context"
 	"encoding/json"
+	"fmt"
 	"os"
+	"sort"
+	"strings"
 	"sync"
 	"unsafe"
 
+	"github.com/open-policy-agent/opa/ast"
 	"github.com/open-policy-agent/opa/loader"
 	"github.com/open-policy-agent/opa/rego"
+	"github.com/open-policy-agent/opa/storage/inmem"
+	"github.com/open-policy-agent/opa/topdown"
 )
 
 var (
 	registry        = make(map[uint64]*rego.PreparedEvalQuery)
+	sources         = make(map[uint64]source)
 	mutex           = &sync.Mutex{}
 	ids      uint64 = 0
 )
 
+// source keeps the arguments a query was prepared with, so it can be
+// recompiled for partial evaluation.
+type source struct {
+	query   string
+	modules []module
+	data    map[string]interface{}
+}
+
+type module struct {
+	name    string
+	content string
+}
+
+func (s source) options() []func(*rego.Rego) {
+	options := []func(*rego.Rego){
+		rego.Query(s.query),
+	}
+	for _, m := range s.modules {
+		options = append(options, rego.Module(m.name, m.content))
+	}
+	if s.data != nil {
+		options = append(options, rego.Store(inmem.NewFromObject(s.data)))
+	}
+	return options
+}
+
+// Strings passed in from rust point to rust owned memory, so they must be
+// copied before being kept past the end of a call.
+func clone(s string) string {
+	return string([]byte(s))
+}
+
+// Ad hoc queries don't need a module, and an empty one fails to parse.
+func single(modulename string, modulecontent string) []module {
+	if modulecontent == "" {
+		return nil
+	}
+	return []module{{clone(modulename), clone(modulecontent)}}
+}
+
 //export RegoNew
 func RegoNew(query string, modulename string, modulecontent string) (uint64, *C.char) {
+	return prepare(source{clone(query), single(modulename, modulecontent), nil})
+}
+
+//export RegoNewMulti
+func RegoNewMulti(query string, modulenames []string, modulecontents []string) (uint64, *C.char) {
+	if len(modulenames) != len(modulecontents) {
+		return 0, C.CString(fmt.Sprintf("got %d module names for %d modules", len(modulenames), len(modulecontents)))
+	}
+	modules := make([]module, len(modulenames))
+	for i := range modulenames {
+		modules[i] = module{clone(modulenames[i]), clone(modulecontents[i])}
+	}
+	return prepare(source{clone(query), modules, nil})
+}
+
+//export RegoNewWithData
+func RegoNewWithData(query string, modulename string, modulecontent string, data string) (uint64, *C.char) {
+	var obj map[string]interface{}
+	if err := json.Unmarshal([]byte(data), &obj); err != nil {
+		return 0, C.CString(err.Error())
+	}
+	return prepare(source{clone(query), single(modulename, modulecontent), obj})
+}
+
+func prepare(src source) (uint64, *C.char) {
 	ctx := context.Background()
 
-	prepared, err := rego.New(
-		rego.Query(query),
-		rego.Module(modulename, modulecontent),
-	).PrepareForEval(ctx)
+	prepared, err := rego.New(src.options()...).PrepareForEval(ctx)
 
 	if err != nil {
 		return 0, C.CString(err.Error())
@@ -37,6 +106,7 @@ func RegoNew(query string, modulename string, modulecontent string) (uint64, *C.
 	ids += 1
 	var id = ids
 	registry[ids] = &prepared
+	sources[ids] = src
 	mutex.Unlock()
 
 	return id, nil
@@ -44,7 +114,10 @@ func RegoNew(query string, modulename string, modulecontent string) (uint64, *C.
 
 //export RegoDrop
 func RegoDrop(id uint64) {
+	mutex.Lock()
 	delete(registry, id)
+	delete(sources, id)
+	mutex.Unlock()
 }
 
 //export RegoEvalBool
@@ -114,6 +187,67 @@ func RegoEval(id uint64, inputstr string) (*C.char, *C.char) {
 	return C.CString(string(jbytes)), nil
 }
 
+//export RegoEvalExplain
+func RegoEvalExplain(id uint64, inputstr string) (*C.char, *C.char, *C.char) {
+	ctx := context.Background()
+
+	mutex.Lock()
+	query, found := registry[id]
+	mutex.Unlock()
+
+	if !found {
+		return nil, nil, C.CString("could not find rego query")
+	}
+
+	var input interface{}
+	err := json.Unmarshal([]byte(inputstr), &input)
+	if err != nil {
+		return nil, nil, C.CString(err.Error())
+	}
+
+	tracer := topdown.NewBufferTracer()
+	results, err := query.Eval(ctx, rego.EvalInput(input), rego.EvalTracer(tracer))
+	if err != nil {
+		return nil, nil, C.CString(err.Error())
+	}
+
+	jbytes, err := json.Marshal(results)
+	if err != nil {
+		return nil, nil, C.CString(err.Error())
+	}
+
+	var trace strings.Builder
+	topdown.PrettyTrace(&trace, *tracer)
+
+	return C.CString(string(jbytes)), C.CString(trace.String()), nil
+}
+
+//export RegoPartial
+func RegoPartial(id uint64, unknowns []string) (*C.char, *C.char) {
+	ctx := context.Background()
+
+	mutex.Lock()
+	src, found := sources[id]
+	mutex.Unlock()
+
+	if !found {
+		return nil, C.CString("could not find rego query")
+	}
+
+	options := append(src.options(), rego.Unknowns(unknowns))
+	pq, err := rego.New(options...).Partial(ctx)
+	if err != nil {
+		return nil, C.CString(err.Error())
+	}
+
+	jbytes, err := json.Marshal(pq)
+	if err != nil {
+		return nil, C.CString(err.Error())
+	}
+
+	return C.CString(string(jbytes)), nil
+}
+
 // Wasm
 
 type loaderFilter struct {
@@ -131,7 +265,78 @@ func (f loaderFilter) Apply(abspath string, info os.FileInfo, depth int) bool {
 
 //export WasmBuild
 func WasmBuild(query string, data, bundles, ignore []string) (unsafe.Pointer, int, *C.char) {
+	bytes, _, err := wasmBuild(query, data, bundles, ignore)
+	if err != nil {
+		return nil, 0, C.CString(err.Error())
+	}
+
+	return C.CBytes(bytes), len(bytes), nil
+}
+
+// WasmBuildWithData is like WasmBuild, but also returns the JSON encoded data
+// document loaded from the data paths, or nil if they held no data, and the
+// compile warnings as a JSON array.
+//export WasmBuildWithData
+func WasmBuildWithData(query string, data, bundles, ignore []string) (unsafe.Pointer, int, *C.char, *C.char, *C.char) {
+	bytes, warnings, err := wasmBuild(query, data, bundles, ignore)
+	if err != nil {
+		return nil, 0, nil, nil, C.CString(err.Error())
+	}
+
+	loaded, err := loader.Filtered(data, loaderFilter{Ignore: ignore}.Apply)
+	if err != nil {
+		return nil, 0, nil, nil, C.CString(err.Error())
+	}
+
+	var documents *C.char
+	if len(loaded.Documents) > 0 {
+		jbytes, err := json.Marshal(loaded.Documents)
+		if err != nil {
+			return nil, 0, nil, nil, C.CString(err.Error())
+		}
+		documents = C.CString(string(jbytes))
+	}
+
+	wbytes, err := json.Marshal(warnings)
+	if err != nil {
+		return nil, 0, nil, nil, C.CString(err.Error())
+	}
+
+	return C.CBytes(bytes), len(bytes), documents, C.CString(string(wbytes)), nil
+}
+
+// deprecated maps the builtins OPA still accepts, but has replaced, to their
+// replacements.
+var deprecated = map[string]string{
+	"net.cidr_overlap": "net.cidr_contains",
+	"re_match":         "regex.match",
+	"set_diff":         "minus",
+}
+
+// compileWarnings lists the calls to deprecated builtins in the compiled
+// modules. They compile fine, so the compiler itself doesn't report them.
+func compileWarnings(compiler *ast.Compiler) []string {
+	warnings := []string{}
+	for _, module := range compiler.Modules {
+		ast.WalkExprs(module, func(expr *ast.Expr) bool {
+			if !expr.IsCall() {
+				return false
+			}
+			name := expr.Operator().String()
+			if replacement, ok := deprecated[name]; ok {
+				warning := fmt.Sprintf("%v: %s is deprecated, use %s instead", expr.Location, name, replacement)
+				warnings = append(warnings, warning)
+			}
+			return false
+		})
+	}
+	sort.Strings(warnings)
+	return warnings
+}
+
+func wasmBuild(query string, data, bundles, ignore []string) ([]byte, []string, error) {
 	ctx := context.Background()
+	compiler := ast.NewCompiler()
 
 	f := loaderFilter{
 		Ignore: ignore,
@@ -139,6 +344,7 @@ func WasmBuild(query string, data, bundles, ignore []string) (unsafe.Pointer, in
 
 	regoArgs := []func(*rego.Rego){
 		rego.Query(query),
+		rego.Compiler(compiler),
 	}
 
 	if len(data) > 0 {
@@ -154,10 +360,10 @@ func WasmBuild(query string, data, bundles, ignore []string) (unsafe.Pointer, in
 	r := rego.New(regoArgs...)
 	cr, err := r.Compile(ctx, rego.CompilePartial(false))
 	if err != nil {
-		return nil, 0, C.CString(err.Error())
+		return nil, nil, err
 	}
 
-	return C.CBytes(cr.Bytes), len(cr.Bytes), nil
+	return cr.Bytes, compileWarnings(compiler), nil
 }
 
 //export Free
@@ -4,8 +4,11 @@ use std::{error, fmt};
 
 use opa_go_sys::*;
 use serde::Serialize;
+use serde_json::Value;
 
 pub mod wasm;
+#[cfg(feature = "watch")]
+mod watch;
 
 #[derive(Debug)]
 pub struct Error {
@@ -49,6 +52,26 @@ impl From<GoError> for Error {
     }
 }
 
+// Copies a string returned from Go and frees the original.
+unsafe fn take_string(ptr: *mut c_char) -> String {
+    let s = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+    Free(ptr as *mut c_void);
+    s
+}
+
+/// Compiles and evaluates a one-off query, such as `input.x + 1`, returning
+/// the JSON encoded result set. Use `Rego` to evaluate a query repeatedly.
+pub fn eval_query<T: Serialize>(query: &str, input: &T) -> Result<Value, Error> {
+    let rego = Rego::new(query, "", "")?;
+    rego.eval(input)
+}
+
+/// A query prepared by OPA's Go library, identified by an id into a registry
+/// on the Go side.
+///
+/// `Rego` is `Send` and `Sync`: the registry is guarded by a mutex and OPA's
+/// prepared queries are safe to evaluate from several goroutines at once, so
+/// one handle can be shared between threads, e.g. in an `Arc`.
 pub struct Rego {
     id: u64,
 }
@@ -82,6 +105,90 @@ impl Rego {
         Ok(rego)
     }
 
+    // Like new, but compiles several modules together, as (name, contents)
+    // pairs, so rules in one can refer to another's package.
+    pub fn new_multi(query: &str, modules: &[(&str, &str)]) -> Result<Self, Error> {
+        let query = GoString {
+            p: query.as_ptr() as *const c_char,
+            n: query.len() as isize,
+        };
+
+        let (mut names, mut contents): (Vec<_>, Vec<_>) = modules
+            .iter()
+            .map(|(name, contents)| {
+                let name = GoString {
+                    p: name.as_ptr() as *const c_char,
+                    n: name.len() as isize,
+                };
+                let contents = GoString {
+                    p: contents.as_ptr() as *const c_char,
+                    n: contents.len() as isize,
+                };
+                (name, contents)
+            })
+            .unzip();
+        let names = GoSlice {
+            data: names.as_mut_ptr() as *mut c_void,
+            len: names.len() as GoInt,
+            cap: names.len() as GoInt,
+        };
+        let contents = GoSlice {
+            data: contents.as_mut_ptr() as *mut c_void,
+            len: contents.len() as GoInt,
+            cap: contents.len() as GoInt,
+        };
+
+        let result = unsafe { RegoNewMulti(query, names, contents) };
+        if !result.r1.is_null() {
+            let e = GoError {
+                ptr: result.r1 as *const c_char,
+            };
+            return Err(Error::from(e));
+        }
+
+        let rego = Self { id: result.r0 };
+        Ok(rego)
+    }
+
+    // Like new, but evaluates against the given data document (a JSON object).
+    pub fn new_with_data(
+        query: &str,
+        module_name: &str,
+        module_contents: &str,
+        data: &str,
+    ) -> Result<Self, Error> {
+        let query = GoString {
+            p: query.as_ptr() as *const c_char,
+            n: query.len() as isize,
+        };
+
+        let module_name = GoString {
+            p: module_name.as_ptr() as *const c_char,
+            n: module_name.len() as isize,
+        };
+
+        let module_contents = GoString {
+            p: module_contents.as_ptr() as *const c_char,
+            n: module_contents.len() as isize,
+        };
+
+        let data = GoString {
+            p: data.as_ptr() as *const c_char,
+            n: data.len() as isize,
+        };
+
+        let result = unsafe { RegoNewWithData(query, module_name, module_contents, data) };
+        if !result.r1.is_null() {
+            let e = GoError {
+                ptr: result.r1 as *const c_char,
+            };
+            return Err(Error::from(e));
+        }
+
+        let rego = Self { id: result.r0 };
+        Ok(rego)
+    }
+
     pub fn eval_bool<T: Serialize>(&self, input: &T) -> Result<bool, Error> {
         let serialized = serde_json::to_string(input).map_err(|e| Error::new(e.to_string()))?;
         let input = GoString {
@@ -97,6 +204,107 @@ impl Rego {
         }
         Ok(result.r0 != 0)
     }
+
+    // Evaluates the query, returning the JSON encoded result set.
+    pub fn eval<T: Serialize>(&self, input: &T) -> Result<Value, Error> {
+        let serialized = serde_json::to_string(input).map_err(|e| Error::new(e.to_string()))?;
+        let input = GoString {
+            p: serialized.as_ptr() as *const c_char,
+            n: serialized.len() as isize,
+        };
+        let result = unsafe { RegoEval(self.id, input) };
+        if !result.r1.is_null() {
+            let e = GoError {
+                ptr: result.r1 as *const c_char,
+            };
+            return Err(Error::from(e));
+        }
+
+        let json = unsafe { take_string(result.r0) };
+        serde_json::from_str(&json).map_err(|e| Error::new(e.to_string()))
+    }
+
+    // Evaluates the query, also returning OPA's trace of the evaluation, one
+    // line per event.
+    pub fn eval_explain<T: Serialize>(&self, input: &T) -> Result<(Value, Vec<String>), Error> {
+        let serialized = serde_json::to_string(input).map_err(|e| Error::new(e.to_string()))?;
+        let input = GoString {
+            p: serialized.as_ptr() as *const c_char,
+            n: serialized.len() as isize,
+        };
+        let result = unsafe { RegoEvalExplain(self.id, input) };
+        if !result.r2.is_null() {
+            let e = GoError {
+                ptr: result.r2 as *const c_char,
+            };
+            return Err(Error::from(e));
+        }
+
+        let (json, trace) = unsafe { (take_string(result.r0), take_string(result.r1)) };
+        let value = serde_json::from_str(&json).map_err(|e| Error::new(e.to_string()))?;
+        let trace = trace.lines().map(ToString::to_string).collect();
+        Ok((value, trace))
+    }
+
+    // Partially evaluates the query, treating the given references (e.g.
+    // "input") as unknown.
+    pub fn partial(&self, unknowns: &[&str]) -> Result<PartialResult, Error> {
+        let mut unknowns = unknowns
+            .iter()
+            .map(|unknown| GoString {
+                p: unknown.as_ptr() as *const c_char,
+                n: unknown.len() as isize,
+            })
+            .collect::<Vec<_>>();
+        let unknowns = GoSlice {
+            data: unknowns.as_mut_ptr() as *mut c_void,
+            len: unknowns.len() as GoInt,
+            cap: unknowns.len() as GoInt,
+        };
+
+        let result = unsafe { RegoPartial(self.id, unknowns) };
+        if !result.r1.is_null() {
+            let e = GoError {
+                ptr: result.r1 as *const c_char,
+            };
+            return Err(Error::from(e));
+        }
+
+        let json = unsafe { take_string(result.r0) };
+        let value = serde_json::from_str(&json).map_err(|e| Error::new(e.to_string()))?;
+        Ok(PartialResult(value))
+    }
+}
+
+/// The residual queries and support modules of a partial evaluation, as the
+/// JSON encoding of the Go AST.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartialResult(Value);
+
+impl PartialResult {
+    pub fn queries(&self) -> &[Value] {
+        self.array("queries")
+    }
+
+    pub fn support(&self) -> &[Value] {
+        self.array("modules")
+    }
+
+    pub fn as_json(&self) -> &Value {
+        &self.0
+    }
+
+    pub fn into_json(self) -> Value {
+        self.0
+    }
+
+    fn array(&self, key: &str) -> &[Value] {
+        self.0
+            .get(key)
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
 }
 
 impl Drop for Rego {
@@ -2,9 +2,13 @@ use std::os::raw::{c_char, c_void};
 use std::path::Path;
 use std::{slice, str};
 
-use opa_go_sys::{Free, GoInt, GoSlice, GoString, WasmBuild};
+use opa_go_sys::{Free, GoInt, GoSlice, GoString, WasmBuild, WasmBuildWithData};
+use serde_json::Value;
 
-use crate::{Error, GoError};
+use crate::{take_string, Error, GoError};
+
+#[cfg(feature = "watch")]
+pub use crate::watch::{watch, Watch};
 
 struct WasmBuildReturn {
     ptr: *const u8,
@@ -33,18 +37,113 @@ impl Drop for WasmBuildReturn {
     }
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct CompileOptions {
+    pub ignore: Vec<String>,
+}
+
 pub fn compile<P: AsRef<Path>>(query: &str, data: P) -> Result<Vec<u8>, Error> {
+    compile_with_options(query, data, &CompileOptions::default())
+}
+
+pub fn compile_with_options<P: AsRef<Path>>(
+    query: &str,
+    data: P,
+    options: &CompileOptions,
+) -> Result<Vec<u8>, Error> {
+    compile_paths(query, &[data], options)
+}
+
+/// The output of `compile_output`: the wasm module, the data document that
+/// was loaded alongside the policies and any compile warnings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompileOutput {
+    pub wasm: Vec<u8>,
+    /// `None` if the data paths held no data files.
+    pub data: Option<Value>,
+    /// Non-fatal issues, like calls to deprecated builtins, prefixed with
+    /// their location.
+    pub warnings: Vec<String>,
+}
+
+// Like compile_with_options, but also returns the merged data loaded from
+// the data path, to check what got bundled with the policy, and the compile
+// warnings.
+pub fn compile_output<P: AsRef<Path>>(
+    query: &str,
+    data: P,
+    options: &CompileOptions,
+) -> Result<CompileOutput, Error> {
+    with_args(query, &[data], options, |query, data, bundles, ignore| {
+        let result = unsafe { WasmBuildWithData(query, data, bundles, ignore) };
+        if !result.r4.is_null() {
+            let e = GoError {
+                ptr: result.r4 as *const c_char,
+            };
+            return Err(Error::from(e));
+        }
+
+        let wasm = WasmBuildReturn {
+            ptr: result.r0 as *const u8,
+            len: result.r1 as usize,
+        }
+        .into_bytes();
+        let data = if result.r2.is_null() {
+            None
+        } else {
+            Some(unsafe { take_string(result.r2) })
+        };
+        let warnings = unsafe { take_string(result.r3) };
+
+        let data = match data {
+            Some(json) => Some(serde_json::from_str(&json).map_err(|e| Error::new(e.to_string()))?),
+            None => None,
+        };
+        let warnings = serde_json::from_str(&warnings).map_err(|e| Error::new(e.to_string()))?;
+        Ok(CompileOutput {
+            wasm,
+            data,
+            warnings,
+        })
+    })
+}
+
+pub(crate) fn compile_paths<P: AsRef<Path>>(
+    query: &str,
+    paths: &[P],
+    options: &CompileOptions,
+) -> Result<Vec<u8>, Error> {
+    with_args(query, paths, options, |query, data, bundles, ignore| {
+        let bytes = build(query, data, bundles, ignore)?.into_bytes();
+        Ok(bytes)
+    })
+}
+
+// Converts the arguments to their Go representations, which borrow from the
+// arguments and so are only valid for the duration of f.
+fn with_args<P, F, T>(query: &str, paths: &[P], options: &CompileOptions, f: F) -> Result<T, Error>
+where
+    P: AsRef<Path>,
+    F: FnOnce(GoString, GoSlice, GoSlice, GoSlice) -> Result<T, Error>,
+{
     let query = GoString {
         p: query.as_ptr() as *const c_char,
         n: query.len() as isize,
     };
 
-    let data = data.as_ref().to_str().unwrap();
-    let mut data = GoString {
-        p: data.as_ptr() as *const c_char,
-        n: data.len() as isize,
-    };
-    let data = slice::from_mut(&mut data);
+    let mut data = paths
+        .iter()
+        .map(|path| {
+            let path = path
+                .as_ref()
+                .to_str()
+                .ok_or_else(|| Error::new(format!("invalid path {}", path.as_ref().display())))?;
+            Ok(GoString {
+                p: path.as_ptr() as *const c_char,
+                n: path.len() as isize,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
     let data = GoSlice {
         data: data.as_mut_ptr() as *mut c_void,
         len: data.len() as GoInt,
@@ -57,14 +156,21 @@ pub fn compile<P: AsRef<Path>>(query: &str, data: P) -> Result<Vec<u8>, Error> {
         cap: 0,
     };
 
+    let mut ignore = options
+        .ignore
+        .iter()
+        .map(|pattern| GoString {
+            p: pattern.as_ptr() as *const c_char,
+            n: pattern.len() as isize,
+        })
+        .collect::<Vec<_>>();
     let ignore = GoSlice {
-        data: std::ptr::null_mut() as *mut c_void,
-        len: 0,
-        cap: 0,
+        data: ignore.as_mut_ptr() as *mut c_void,
+        len: ignore.len() as GoInt,
+        cap: ignore.len() as GoInt,
     };
 
-    let bytes = build(query, data, bundles, ignore)?.into_bytes();
-    Ok(bytes)
+    f(query, data, bundles, ignore)
 }
 
 fn build(
@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::wasm::{compile_paths, CompileOptions};
+use crate::Error;
+
+const DEBOUNCE: Duration = Duration::from_millis(100);
+const POLL: Duration = Duration::from_millis(100);
+
+/// Handle to a running watch. Dropping it stops watching.
+pub struct Watch {
+    _watcher: RecommendedWatcher,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Watches the rego files under `paths` and recompiles `query` whenever
+/// one of them changes, passing the fresh wasm (or the error) to `on_change`.
+pub fn watch<P, F>(paths: &[P], query: &str, mut on_change: F) -> Result<Watch, Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(Result<Vec<u8>, Error>) + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::watcher(tx, DEBOUNCE).map_err(|e| Error::new(e.to_string()))?;
+    for path in paths {
+        watcher
+            .watch(path, RecursiveMode::Recursive)
+            .map_err(|e| Error::new(e.to_string()))?;
+    }
+
+    let paths = paths
+        .iter()
+        .map(|p| p.as_ref().to_path_buf())
+        .collect::<Vec<PathBuf>>();
+    let query = query.to_string();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+
+    let thread = thread::spawn(move || {
+        while !stopped.load(Ordering::SeqCst) {
+            match rx.recv_timeout(POLL) {
+                Ok(DebouncedEvent::Create(_))
+                | Ok(DebouncedEvent::Write(_))
+                | Ok(DebouncedEvent::Remove(_))
+                | Ok(DebouncedEvent::Rename(_, _)) => {
+                    on_change(compile_paths(&query, &paths, &CompileOptions::default()))
+                }
+                Ok(DebouncedEvent::Error(e, _)) => on_change(Err(Error::new(e.to_string()))),
+                Ok(_) | Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+
+    let watch = Watch {
+        _watcher: watcher,
+        stop,
+        thread: Some(thread),
+    };
+    Ok(watch)
+}
@@ -1,6 +1,9 @@
 use std::fs;
 use std::path::PathBuf;
 
+use opa_go::wasm::CompileOptions;
+use serde_json::json;
+
 #[test]
 fn test_opa_compiler_compile() {
     let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -9,3 +12,52 @@ fn test_opa_compiler_compile() {
     let expected = fs::read(&root.join("tests/empty.wasm")).unwrap();
     assert_eq!(expected, bytes);
 }
+
+#[test]
+fn test_opa_compiler_compile_ignore() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let dir = root.join("tests/ignore");
+
+    let result = opa_go::wasm::compile("data.tests.allow", &dir);
+    assert!(result.is_err());
+
+    let options = CompileOptions {
+        ignore: vec!["*_test.rego".to_string()],
+    };
+    let bytes = opa_go::wasm::compile_with_options("data.tests.allow", &dir, &options).unwrap();
+    assert!(!bytes.is_empty());
+}
+
+#[test]
+fn test_opa_compiler_compile_output() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let options = CompileOptions::default();
+
+    let output =
+        opa_go::wasm::compile_output("data.tests.allow", root.join("tests/data"), &options)
+            .unwrap();
+    assert!(!output.wasm.is_empty());
+    assert_eq!(Some(json!({"users": {"admins": ["alice"]}})), output.data);
+
+    let output =
+        opa_go::wasm::compile_output("data.tests.allow", root.join("tests/empty.rego"), &options)
+            .unwrap();
+    let expected = fs::read(root.join("tests/empty.wasm")).unwrap();
+    assert_eq!(expected, output.wasm);
+    assert_eq!(None, output.data);
+    assert!(output.warnings.is_empty());
+}
+
+#[test]
+fn test_opa_compiler_compile_warnings() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let options = CompileOptions::default();
+
+    let output =
+        opa_go::wasm::compile_output("data.tests.allow", root.join("tests/deprecated"), &options)
+            .unwrap();
+    assert!(!output.wasm.is_empty());
+    assert_eq!(1, output.warnings.len());
+    assert!(output.warnings[0].contains("policy.rego:4"));
+    assert!(output.warnings[0].contains("re_match is deprecated, use regex.match instead"));
+}
@@ -0,0 +1,5 @@
+package tests
+
+allow {
+    data.users.admins[_] == input.user
+}
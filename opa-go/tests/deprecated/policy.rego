@@ -0,0 +1,5 @@
+package tests
+
+allow {
+    re_match("^admin-", input.user)
+}
@@ -0,0 +1,5 @@
+package tests
+
+allow[x] {
+    x := "conflict"
+}
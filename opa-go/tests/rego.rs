@@ -0,0 +1,129 @@
+use std::sync::Arc;
+use std::thread;
+
+use opa_go::Rego;
+use serde_json::json;
+
+const MODULE: &str = r#"
+package test
+
+allow {
+    input.user == "alice"
+}
+"#;
+
+#[test]
+fn test_partial() {
+    let rego = Rego::new("data.test.allow == true", "test.rego", MODULE).unwrap();
+    let result = rego.partial(&["input"]).unwrap();
+    assert_eq!(1, result.queries().len());
+    assert!(result.as_json().to_string().contains("alice"));
+}
+
+const DATA_MODULE: &str = r#"
+package test
+
+allow {
+    data.users[input.user].admin
+}
+"#;
+
+#[test]
+fn test_new_with_data() {
+    let data = r#"{"users": {"alice": {"admin": true}, "bob": {"admin": false}}}"#;
+    let rego = Rego::new_with_data("data.test.allow", "test.rego", DATA_MODULE, data).unwrap();
+
+    assert!(rego.eval_bool(&json!({"user": "alice"})).unwrap());
+    assert!(!rego.eval_bool(&json!({"user": "bob"})).unwrap());
+}
+
+#[test]
+fn test_new_with_invalid_data() {
+    let result = Rego::new_with_data("data.test.allow", "test.rego", DATA_MODULE, "[1, 2");
+    assert!(result.is_err());
+}
+
+const EXPLAIN_MODULE: &str = r#"
+package test
+
+allow {
+    input.user == "alice"
+    input.method == "GET"
+    startswith(input.path, "/public")
+}
+"#;
+
+#[test]
+fn test_eval_explain() {
+    let rego = Rego::new("data.test.allow", "test.rego", EXPLAIN_MODULE).unwrap();
+    let input = json!({"user": "alice", "method": "GET", "path": "/public/index.html"});
+    let (result, trace) = rego.eval_explain(&input).unwrap();
+
+    assert_eq!(json!(true), result[0]["expressions"][0]["value"]);
+    assert!(!trace.is_empty());
+    assert!(trace.iter().any(|line| line.contains("startswith")));
+}
+
+#[test]
+fn test_eval_query() {
+    let result = opa_go::eval_query("input.x + 1", &json!({"x": 1})).unwrap();
+    assert_eq!(json!(2), result[0]["expressions"][0]["value"]);
+
+    assert!(opa_go::eval_query("input.x +", &json!({})).is_err());
+}
+
+#[test]
+fn test_concurrent_eval() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Rego>();
+
+    let rego = Arc::new(Rego::new("data.test.allow", "test.rego", MODULE).unwrap());
+    let threads = (0..8)
+        .map(|i| {
+            let rego = rego.clone();
+            thread::spawn(move || {
+                // Handles created and dropped alongside the shared one
+                let own = Rego::new("data.test.allow", "test.rego", MODULE).unwrap();
+                for j in 0..50 {
+                    let user = if (i + j) % 2 == 0 { "alice" } else { "bob" };
+                    let input = json!({ "user": user });
+                    assert_eq!(user == "alice", rego.eval_bool(&input).unwrap());
+                    assert_eq!(user == "alice", own.eval_bool(&input).unwrap());
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+const USERS_MODULE: &str = r#"
+package users
+
+admins = {"alice"}
+"#;
+
+const AUTHZ_MODULE: &str = r#"
+package authz
+
+import data.users
+
+allow {
+    users.admins[input.user]
+}
+"#;
+
+#[test]
+fn test_new_multi() {
+    let modules = [("users.rego", USERS_MODULE), ("authz.rego", AUTHZ_MODULE)];
+    let rego = Rego::new_multi("data.authz.allow", &modules).unwrap();
+
+    assert!(rego.eval_bool(&json!({"user": "alice"})).unwrap());
+    assert!(!rego.eval_bool(&json!({"user": "bob"})).unwrap());
+
+    // A syntax error in any one module fails the whole set
+    let broken = [("users.rego", "package users\n\nadmins = {"), modules[1]];
+    assert!(Rego::new_multi("data.authz.allow", &broken).is_err());
+}
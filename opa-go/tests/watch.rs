@@ -0,0 +1,28 @@
+#![cfg(feature = "watch")]
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[test]
+fn test_opa_compiler_watch() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let dir = std::env::temp_dir().join(format!("opa-go-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let policy = dir.join("policy.rego");
+    fs::copy(root.join("tests/empty.rego"), &policy).unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let watch = opa_go::wasm::watch(&[&dir], "data.tests.allow", move |result| {
+        let _ = tx.send(result);
+    })
+    .unwrap();
+
+    fs::write(&policy, "package tests\n\ndefault allow = true\n").unwrap();
+    let bytes = rx.recv_timeout(Duration::from_secs(10)).unwrap().unwrap();
+    assert!(!bytes.is_empty());
+
+    drop(watch);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -2,10 +2,21 @@ use std::fmt;
 
 use rego::{CompiledQuery, ValueRef};
 use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[cfg(feature = "opa-wasm")]
+mod wasm;
+
+#[cfg(feature = "opa-wasm")]
+pub use wasm::FromWasm;
 
 #[derive(Debug)]
 pub enum Error {
     Compile(String),
+    Data(String),
+    DataKey(String),
+    Number(String),
+    Undefined,
     Runtime(rego::Error<'static>),
     Serialize(rego::Error<'static>),
     Deserialize(rego::Error<'static>),
@@ -15,6 +26,14 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::Compile(s) => write!(f, "Policy failed to compile: {}", s),
+            Self::Data(s) => write!(f, "Invalid data document: {}", s),
+            Self::DataKey(s) => write!(
+                f,
+                "Data key {:?} can't be loaded: top level values must be objects, and keys at the top two levels must be Rego identifiers.",
+                s
+            ),
+            Self::Number(s) => write!(f, "Number {} has no Rego equivalent.", s),
+            Self::Undefined => write!(f, "The policy result is undefined."),
             Self::Runtime(_) => write!(f, "An error occurred while evaluating the policy."),
             Self::Serialize(_) => write!(f, "An error occurred while serializing the input."),
             Self::Deserialize(_) => write!(f, "An error occurred while deserializing the result."),
@@ -45,13 +64,82 @@ impl Policy {
         Ok(policy)
     }
 
+    // The compiler only takes modules, so the data document is loaded as
+    // generated modules: each top level key becomes a package and each of
+    // its entries a rule holding the entry as a JSON literal. Packages and
+    // rules need plain names, so top level scalars, and keys at the top two
+    // levels that aren't Rego identifiers, fail with Error::DataKey. Deeper
+    // keys are part of the literals and can be anything.
+    pub fn from_query_with_data<D: Serialize>(
+        query: &str,
+        modules: &[&str],
+        data: &D,
+    ) -> Result<Self, Error> {
+        let data_modules = data_modules(data)?;
+        let mut all = modules.to_vec();
+        all.extend(data_modules.iter().map(String::as_str));
+        Self::from_query(query, &all)
+    }
+
     pub fn evaluate<T: ValueRef, V: DeserializeOwned>(&mut self, input: T) -> Result<V, Error> {
+        self.evaluate_opt(input)?.ok_or(Error::Undefined)
+    }
+
+    // Returns None when the query is undefined, rather than failing to
+    // deserialize the undefined value.
+    pub fn evaluate_opt<T: ValueRef, V: DeserializeOwned>(
+        &mut self,
+        input: T,
+    ) -> Result<Option<V>, Error> {
         let result = self.query.eval(&input).map_err(Error::Runtime)?;
+        if let rego::Value::Undefined = result {
+            return Ok(None);
+        }
         let result = rego::from_value(result).map_err(Error::Deserialize)?;
-        Ok(result)
+        Ok(Some(result))
     }
 }
 
+const KEYWORDS: &[&str] = &[
+    "as", "default", "else", "false", "import", "not", "null", "package", "some", "true", "with",
+];
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    let starts_ok = match chars.next() {
+        Some(c) => c.is_ascii_alphabetic() || c == '_',
+        None => false,
+    };
+    starts_ok && chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && !KEYWORDS.contains(&s)
+}
+
+fn data_modules<D: Serialize>(data: &D) -> Result<Vec<String>, Error> {
+    let data = serde_json::to_value(data).map_err(|e| Error::Data(e.to_string()))?;
+    let packages = match data {
+        serde_json::Value::Object(packages) => packages,
+        serde_json::Value::Null => return Ok(Vec::new()),
+        _ => return Err(Error::Data("expected an object".to_string())),
+    };
+
+    let mut modules = Vec::with_capacity(packages.len());
+    for (package, rules) in packages {
+        let rules = match rules {
+            serde_json::Value::Object(rules) if is_ident(&package) => rules,
+            _ => return Err(Error::DataKey(package)),
+        };
+
+        let mut module = format!("package {}\n", package);
+        for (name, value) in rules {
+            if !is_ident(&name) {
+                return Err(Error::DataKey(format!("{}.{}", package, name)));
+            }
+            module.push_str(&format!("\n{} = {}\n", name, value));
+        }
+        modules.push(module);
+    }
+    Ok(modules)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,4 +156,76 @@ mod tests {
         let result = policy.evaluate(()).unwrap();
         assert_eq!(true, result);
     }
+
+    #[test]
+    fn test_evaluate_undefined() {
+        let module = r###"
+        package test
+
+        allow {
+            input.user == "alice"
+        }
+        "###;
+        let query = "data.test.allow";
+        let mut policy = Policy::from_query(query, &[module]).unwrap();
+
+        let result: Option<bool> = policy.evaluate_opt(()).unwrap();
+        assert_eq!(None, result);
+
+        let err = policy.evaluate::<_, bool>(()).unwrap_err();
+        assert!(matches!(err, Error::Undefined));
+    }
+
+    #[test]
+    fn test_from_query_with_data() {
+        let module = r###"
+        package test
+
+        default allow = false
+
+        allow {
+            data.config.enabled
+        }
+        "###;
+        let query = "data.test.allow";
+
+        let data = serde_json::json!({"config": {"enabled": true, "tags": ["a", "b"]}});
+        let mut policy = Policy::from_query_with_data(query, &[module], &data).unwrap();
+        let result: bool = policy.evaluate(()).unwrap();
+        assert_eq!(true, result);
+
+        let data = serde_json::json!({"config": {"enabled": false}});
+        let mut policy = Policy::from_query_with_data(query, &[module], &data).unwrap();
+        let result: bool = policy.evaluate(()).unwrap();
+        assert_eq!(false, result);
+    }
+
+    #[test]
+    fn test_data_modules_invalid() {
+        let err = data_modules(&serde_json::json!([1, 2])).unwrap_err();
+        assert!(matches!(err, Error::Data(_)));
+
+        let err = data_modules(&serde_json::json!({"limit": 5})).unwrap_err();
+        assert!(matches!(err, Error::DataKey(ref key) if key == "limit"));
+
+        let err = data_modules(&serde_json::json!({"my-app": {}})).unwrap_err();
+        assert!(matches!(err, Error::DataKey(ref key) if key == "my-app"));
+
+        let err = data_modules(&serde_json::json!({"users": {"alice-1": {}}})).unwrap_err();
+        assert!(matches!(err, Error::DataKey(ref key) if key == "users.alice-1"));
+
+        let modules = data_modules(&serde_json::json!({"config": {"enabled": true}})).unwrap();
+        assert_eq!(
+            vec!["package config\n\nenabled = true\n".to_string()],
+            modules
+        );
+
+        // Keys below the rules are part of the JSON literal
+        let data = serde_json::json!({"users": {"roles": {"alice-1": "admin"}}});
+        let modules = data_modules(&data).unwrap();
+        assert_eq!(
+            vec!["package users\n\nroles = {\"alice-1\":\"admin\"}\n".to_string()],
+            modules
+        );
+    }
 }
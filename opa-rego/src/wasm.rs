@@ -0,0 +1,160 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+use opa_wasm::value::{Number, Value as WasmValue};
+use rego::{Index, Map, Set, ToValue, Value};
+
+use crate::Error;
+
+// Wraps an owned opa-wasm value so it can be used as input to a rego policy.
+// Sets map to rego sets, everything else maps to its structural equivalent.
+// Numbers become rego integers when they fit in an i64 and floats otherwise,
+// so number refs beyond an f64's precision are rounded. Refs that aren't a
+// finite f64 at all fail with Error::Number when the value is wrapped, since
+// the conversion to rego can't fail.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FromWasm(WasmValue);
+
+impl TryFrom<WasmValue> for FromWasm {
+    type Error = Error;
+
+    fn try_from(value: WasmValue) -> Result<Self, Self::Error> {
+        check_numbers(&value)?;
+        Ok(Self(value))
+    }
+}
+
+impl FromWasm {
+    pub fn into_inner(self) -> WasmValue {
+        self.0
+    }
+}
+
+fn check_numbers(value: &WasmValue) -> Result<(), Error> {
+    match value {
+        WasmValue::Number(n) => to_rego_number(n).map(|_| ()),
+        WasmValue::Array(v) => v.iter().try_for_each(check_numbers),
+        WasmValue::Object(m) => m.values().try_for_each(check_numbers),
+        WasmValue::Set(s) => s.iter().try_for_each(check_numbers),
+        _ => Ok(()),
+    }
+}
+
+fn to_rego_number(n: &Number) -> Result<Value<'static>, Error> {
+    if let Some(i) = n.as_i64() {
+        return Ok(Value::from(i));
+    }
+    match n.as_f64() {
+        Some(f) if f.is_finite() => Ok(Value::from(f)),
+        _ => Err(Error::Number(n.to_string())),
+    }
+}
+
+fn to_rego(value: &WasmValue) -> Value<'_> {
+    match value {
+        WasmValue::Null => Value::Null,
+        WasmValue::Bool(b) => Value::from(*b),
+        // Checked by check_numbers when the value was wrapped
+        WasmValue::Number(n) => to_rego_number(n).unwrap_or(Value::Null),
+        WasmValue::String(s) => Value::String(Cow::Borrowed(s.as_str())),
+        WasmValue::Array(v) => Value::Array(v.iter().map(to_rego).collect()),
+        WasmValue::Object(m) => Value::Object(
+            m.iter()
+                .map(|(k, v)| (Value::String(Cow::Borrowed(k.as_str())), to_rego(v)))
+                .collect::<Map<_, _>>(),
+        ),
+        WasmValue::Set(s) => Value::Set(s.iter().map(to_rego).collect::<Set<_>>()),
+    }
+}
+
+impl Index for FromWasm {
+    fn index(&self, field: &Value<'_>) -> Option<Value<'_>> {
+        match (&self.0, field) {
+            (WasmValue::Object(m), Value::String(field)) => m.get(field.as_ref()).map(to_rego),
+            _ => None,
+        }
+    }
+}
+
+impl ToValue for FromWasm {
+    fn to_value(&self) -> Value<'_> {
+        to_rego(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opa_wasm::value::{Map as WasmMap, Set as WasmSet};
+
+    use super::*;
+    use crate::Policy;
+
+    fn nested() -> WasmValue {
+        let mut roles = WasmSet::new();
+        roles.insert(WasmValue::String("admin".to_string()));
+        let mut user = WasmMap::new();
+        user.insert("name".to_string(), WasmValue::String("alice".to_string()));
+        user.insert("roles".to_string(), WasmValue::Set(roles));
+        user.insert(
+            "ids".to_string(),
+            WasmValue::Array(vec![WasmValue::from(1), WasmValue::Bool(true)]),
+        );
+        let mut input = WasmMap::new();
+        input.insert("user".to_string(), WasmValue::Object(user));
+        WasmValue::Object(input)
+    }
+
+    #[test]
+    fn test_to_value() {
+        let mut roles = Set::new();
+        roles.insert(Value::from("admin"));
+        let mut user = Map::new();
+        user.insert(Value::from("name"), Value::from("alice"));
+        user.insert(Value::from("roles"), Value::Set(roles));
+        user.insert(
+            Value::from("ids"),
+            Value::Array(vec![Value::from(1i64), Value::from(true)]),
+        );
+        let mut expected = Map::new();
+        expected.insert(Value::from("user"), Value::Object(user));
+
+        let input = FromWasm::try_from(nested()).unwrap();
+        assert_eq!(Value::Object(expected), input.to_value());
+    }
+
+    #[test]
+    fn test_numbers() {
+        let number = |s: &str| WasmValue::Number(Number::from(s.to_string()));
+
+        let input = FromWasm::try_from(number("10")).unwrap();
+        assert_eq!(Value::from(10i64), input.to_value());
+        let input = FromWasm::try_from(number("1.5")).unwrap();
+        assert_eq!(Value::from(1.5), input.to_value());
+        // Too big for an i64, so rounded to the nearest f64
+        let input = FromWasm::try_from(number("12345678901234567890")).unwrap();
+        assert_eq!(Value::from(12345678901234567890.0), input.to_value());
+
+        let err = FromWasm::try_from(WasmValue::Array(vec![number("1e400")])).unwrap_err();
+        assert!(matches!(err, Error::Number(ref n) if n == "1e400"));
+        assert!(FromWasm::try_from(number("abc")).is_err());
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let module = r###"
+        package test
+
+        default allow = false
+
+        allow {
+            input.user.name == "alice"
+            input.user.roles["admin"]
+        }
+        "###;
+        let query = "data.test.allow";
+        let mut policy = Policy::from_query(query, &[module]).unwrap();
+        let input = FromWasm::try_from(nested()).unwrap();
+        let result: bool = policy.evaluate(input).unwrap();
+        assert!(result);
+    }
+}
@@ -0,0 +1,33 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use proptest::strategy::{Strategy, ValueTree};
+use proptest::test_runner::{Config, RngAlgorithm, TestRng, TestRunner};
+
+use opa_wasm::fuzzing::corrupt_roundtrip;
+use opa_wasm::value::arb::arb_value;
+
+const SEED_LEN: usize = 32;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < SEED_LEN {
+        return;
+    }
+
+    // The first bytes seed the value generator, the rest are (offset, byte)
+    // corruptions applied to the serialized value.
+    let (seed, rest) = data.split_at(SEED_LEN);
+    let rng = TestRng::from_seed(RngAlgorithm::ChaCha, seed);
+    let mut runner = TestRunner::new_with_rng(Config::default(), rng);
+    let value = match arb_value().new_tree(&mut runner) {
+        Ok(tree) => tree.current(),
+        Err(_) => return,
+    };
+
+    let corruptions = rest
+        .chunks_exact(5)
+        .map(|c| (u32::from_le_bytes([c[0], c[1], c[2], c[3]]), c[4]))
+        .collect::<Vec<_>>();
+
+    // Errors are expected; panics and out of bounds reads are not.
+    let _ = corrupt_roundtrip(&value, &corruptions);
+});
@@ -34,11 +34,34 @@ macro_rules! binary_loop {
 
 binary_loop!(sum, 0, numbers::plus);
 binary_loop!(product, 1, numbers::mul);
-binary_loop!(min, std::f64::MAX, numbers::min);
-binary_loop!(max, std::f64::MIN, numbers::max);
 binary_loop!(all, true, for_all);
 binary_loop!(any, false, for_any);
 
+fn collection(name: &'static str, val: Value) -> Result<Vec<Value>, Error> {
+    match val {
+        Value::Array(v) => Ok(v),
+        Value::Set(v) => Ok(v.into_iter().collect()),
+        val => Err(Error::InvalidType("array or set", val)),
+    }
+    .and_then(|v| {
+        if v.is_empty() {
+            Err(Error::BuiltinUndefined(name))
+        } else {
+            Ok(v)
+        }
+    })
+}
+
+pub fn max(val: Value) -> Result<Value, Error> {
+    let v = collection("max", val)?;
+    Ok(v.into_iter().max().unwrap_or_default())
+}
+
+pub fn min(val: Value) -> Result<Value, Error> {
+    let v = collection("min", val)?;
+    Ok(v.into_iter().min().unwrap_or_default())
+}
+
 fn for_all(left: Value, right: Value) -> Result<Value, Error> {
     if let (Some(l), Some(r)) = (left.as_bool(), right.as_bool()) {
         Ok(Value::Bool(l && r))
@@ -55,6 +78,7 @@ fn for_any(left: Value, right: Value) -> Result<Value, Error> {
     }
 }
 
+// Value's Ord is OPA's order, see Value.
 pub fn sort(val: Value) -> Result<Value, Error> {
     let v = match val {
         Value::Array(mut v) => {
@@ -73,6 +97,8 @@ pub fn sort(val: Value) -> Result<Value, Error> {
 
 #[cfg(test)]
 mod tests {
+    use crate::value::Map;
+
     use super::*;
 
     #[test]
@@ -98,4 +124,84 @@ mod tests {
         let out = product(v.into());
         assert!(out.is_err());
     }
+
+    #[test]
+    fn test_max_min_numbers() {
+        let v = Value::Array(vec![Value::from(2), Value::from(10.5), Value::from(-3)]);
+        assert_eq!(Value::from(10.5), max(v.clone()).unwrap());
+        assert_eq!(Value::from(-3), min(v).unwrap());
+
+        let v = Value::Array(vec![
+            Value::from(1.5),
+            Value::Number("12345678901234567890".parse().unwrap()),
+            Value::from(7),
+        ]);
+        assert_eq!(
+            Value::Number("12345678901234567890".parse().unwrap()),
+            max(v).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_max_min_strings() {
+        let v = Value::from(vec!["pear", "apple", "zucchini", "fig"]);
+        assert_eq!(Value::from("zucchini"), max(v.clone()).unwrap());
+        assert_eq!(Value::from("apple"), min(v).unwrap());
+    }
+
+    #[test]
+    fn test_max_min_mixed() {
+        let v = Value::Array(vec![
+            Value::from("a"),
+            Value::Null,
+            Value::from(100),
+            Value::from(vec![1]),
+            Value::Bool(true),
+        ]);
+        assert_eq!(Value::from(vec![1]), max(v.clone()).unwrap());
+        assert_eq!(Value::Null, min(v).unwrap());
+
+        let set = Value::Set(vec![Value::from(1), Value::from("b")].into_iter().collect());
+        assert_eq!(Value::from("b"), max(set.clone()).unwrap());
+        assert_eq!(Value::from(1), min(set).unwrap());
+    }
+
+    #[test]
+    fn test_max_min_empty() {
+        let err = max(Value::Array(vec![])).unwrap_err();
+        assert!(matches!(err, Error::BuiltinUndefined("max")));
+        let err = min(Value::Set(Default::default())).unwrap_err();
+        assert!(matches!(err, Error::BuiltinUndefined("min")));
+
+        assert!(max(Value::from("abc")).is_err());
+    }
+
+    #[test]
+    fn test_sort_objects() {
+        let object = |entries: &[(&str, i64)]| {
+            let map = entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), Value::from(*v)))
+                .collect::<Map<String, Value>>();
+            Value::Object(map)
+        };
+
+        let v = Value::Array(vec![
+            object(&[("b", 0)]),
+            object(&[("a", 2)]),
+            object(&[("a", 1), ("b", 0)]),
+            object(&[]),
+            object(&[("a", 1)]),
+            object(&[("a", 1), ("c", 0)]),
+        ]);
+        let expected = Value::Array(vec![
+            object(&[]),
+            object(&[("a", 1)]),
+            object(&[("a", 1), ("b", 0)]),
+            object(&[("a", 1), ("c", 0)]),
+            object(&[("a", 2)]),
+            object(&[("b", 0)]),
+        ]);
+        assert_eq!(expected, sort(v).unwrap());
+    }
 }
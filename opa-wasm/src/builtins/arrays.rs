@@ -9,19 +9,98 @@ pub fn concat(left: Value, right: Value) -> Result<Value, Error> {
     Ok(Value::Array(left))
 }
 
+// Clamps the bounds the same way OPA does: stop is clamped to [0, len], then
+// start to [0, stop], so negative or reversed bounds give an empty array
+// instead of an error.
 pub fn slice(val: Value, start: Value, end: Value) -> Result<Value, Error> {
     let array = val.try_into_array()?;
     let start = start.try_into_i64()?;
     let end = end.try_into_i64()?;
 
-    let v = if start >= end || (start < 0 && end < 0) {
-        Value::Array(vec![])
-    } else {
-        let len = array.len();
-        let start = cmp::min(cmp::max(start, 0) as usize, len);
-        let end = cmp::min(cmp::max(end, 0) as usize, len);
-        Value::Array(array[start..end].into())
-    };
+    let end = cmp::min(cmp::max(end, 0) as u64, array.len() as u64) as usize;
+    let start = cmp::min(cmp::max(start, 0) as u64, end as u64) as usize;
+    Ok(Value::Array(array[start..end].into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concat() {
+        let result = concat(Value::from(vec![1, 2]), Value::from(vec!["a"])).unwrap();
+        assert_eq!(
+            Value::Array(vec![Value::from(1), Value::from(2), Value::from("a")]),
+            result
+        );
+    }
+
+    #[test]
+    fn test_concat_empty() {
+        let empty = || Value::Array(vec![]);
+        assert_eq!(
+            Value::from(vec![1]),
+            concat(empty(), Value::from(vec![1])).unwrap()
+        );
+        assert_eq!(
+            Value::from(vec![1]),
+            concat(Value::from(vec![1]), empty()).unwrap()
+        );
+        assert_eq!(empty(), concat(empty(), empty()).unwrap());
+    }
+
+    #[test]
+    fn test_concat_not_array() {
+        let array = || Value::from(vec![1]);
+        let set = Value::from_set_iter(vec![1]);
+        for (left, right) in vec![
+            (Value::from("a"), array()),
+            (array(), Value::Null),
+            (array(), set),
+        ] {
+            let err = concat(left, right).unwrap_err();
+            assert!(matches!(err, Error::InvalidType("array", _)), "{:?}", err);
+        }
+    }
+
+    fn slice_of(start: i64, end: i64) -> Value {
+        let array = Value::from(vec![1, 2, 3, 4]);
+        slice(array, start.into(), end.into()).unwrap()
+    }
+
+    #[test]
+    fn test_slice() {
+        assert_eq!(Value::from(vec![2, 3]), slice_of(1, 3));
+        assert_eq!(Value::from(vec![1, 2, 3, 4]), slice_of(0, 4));
+        assert_eq!(Value::Array(vec![]), slice_of(2, 2));
+    }
+
+    #[test]
+    fn test_slice_negative() {
+        assert_eq!(Value::from(vec![1, 2]), slice_of(-2, 2));
+        assert_eq!(Value::Array(vec![]), slice_of(-3, -1));
+        assert_eq!(Value::Array(vec![]), slice_of(1, -1));
+        assert_eq!(Value::from(vec![1, 2, 3, 4]), slice_of(i64::min_value(), 4));
+    }
+
+    #[test]
+    fn test_slice_reversed() {
+        assert_eq!(Value::Array(vec![]), slice_of(3, 1));
+        assert_eq!(Value::Array(vec![]), slice_of(10, 2));
+    }
+
+    #[test]
+    fn test_slice_out_of_range() {
+        assert_eq!(Value::from(vec![3, 4]), slice_of(2, 10));
+        assert_eq!(Value::Array(vec![]), slice_of(4, 10));
+        assert_eq!(Value::Array(vec![]), slice_of(10, 20));
+        assert_eq!(Value::from(vec![1, 2, 3, 4]), slice_of(0, i64::max_value()));
+    }
 
-    Ok(v)
+    #[test]
+    fn test_slice_invalid() {
+        let array = Value::from(vec![1, 2]);
+        assert!(slice(array.clone(), Value::from("0"), Value::from(1)).is_err());
+        assert!(slice(Value::from("ab"), Value::from(0), Value::from(1)).is_err());
+    }
 }
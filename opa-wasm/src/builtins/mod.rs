@@ -1,4 +1,4 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
@@ -6,6 +6,7 @@ use lazy_static::lazy_static;
 use tracing::{debug, error};
 
 use crate::runtime::Instance;
+use crate::value::Map;
 use crate::{opa_serde, Error, Value, ValueAddr};
 
 mod aggregates;
@@ -13,6 +14,8 @@ mod arrays;
 mod net;
 mod numbers;
 mod objects;
+mod print;
+mod random;
 mod regex;
 mod sets;
 mod strings;
@@ -36,92 +39,154 @@ type Arity1 = fn(Value) -> Result<Value, Error>;
 type Arity2 = fn(Value, Value) -> Result<Value, Error>;
 type Arity3 = fn(Value, Value, Value) -> Result<Value, Error>;
 type Arity4 = fn(Value, Value, Value, Value) -> Result<Value, Error>;
+type StateArity2 = fn(&mut EvalState, Value, Value) -> Result<Value, Error>;
+
+enum Builtin {
+    Arity0(Arity0),
+    Arity1(Arity1),
+    Arity2(Arity2),
+    Arity3(Arity3),
+    // No four argument builtins are implemented yet
+    #[allow(dead_code)]
+    Arity4(Arity4),
+    StateArity2(StateArity2),
+}
 
-lazy_static! {
-    static ref BUILTIN0: HashMap<&'static str, Arity0> = {
-        let mut b: HashMap<&'static str, Arity0> = HashMap::new();
-        b.insert("time.now_ns", time::now_ns);
-        b
-    };
-    static ref BUILTIN1: HashMap<&'static str, Arity1> = {
-        let mut b: HashMap<&'static str, Arity1> = HashMap::new();
-        b.insert("trace", trace);
-
-        b.insert("all", aggregates::all);
-        b.insert("any", aggregates::any);
-        b.insert("count", aggregates::count);
-        b.insert("max", aggregates::max);
-        b.insert("min", aggregates::min);
-        b.insert("product", aggregates::product);
-        b.insert("sort", aggregates::sort);
-        b.insert("sum", aggregates::sum);
-
-        b.insert("abs", numbers::abs);
-        b.insert("round", numbers::round);
-
-        b.insert("net.cidr_expand", net::cidr_expand);
-
-        b.insert("upper", strings::upper);
-
-        b.insert("time.clock", time::clock);
-        b.insert("time.date", time::date);
-        b.insert("time.parse_rfc3339_ns", time::parse_rfc3339_ns);
-        b.insert("time.weekday", time::weekday);
-
-        b.insert("is_array", types::is_array);
-        b.insert("is_boolean", types::is_boolean);
-        b.insert("is_null", types::is_null);
-        b.insert("is_number", types::is_number);
-        b.insert("is_object", types::is_object);
-        b.insert("is_set", types::is_set);
-        b.insert("is_string", types::is_string);
-        b.insert("type_name", types::type_name);
+// State kept for the length of one evaluation, for builtins whose results
+// depend on earlier calls, like rand.intn returning the same number for the
+// same arguments.
+#[derive(Debug, Default)]
+pub struct EvalState {
+    random: random::Random,
+}
+
+impl EvalState {
+    // Starts a new evaluation. Deterministic evaluations start from the same
+    // state every time.
+    fn reset(&mut self, deterministic: bool) {
+        self.random.reset(deterministic);
+    }
+}
+
+// Builds the name to function table. The arity is part of each entry, so a
+// function registered at the wrong arity doesn't compile.
+macro_rules! registry {
+    ($($(#[$attr:meta])* $name:literal => $arity:ident($func:path),)*) => {{
+        let mut b: HashMap<&'static str, Builtin> = HashMap::new();
+        $(
+            $(#[$attr])*
+            b.insert($name, Builtin::$arity($func));
+        )*
         b
-    };
-    static ref BUILTIN2: HashMap<&'static str, Arity2> = {
-        let mut b: HashMap<&'static str, Arity2> = HashMap::new();
-        b.insert("array.concat", arrays::concat);
+    }};
+}
+
+// What time.now_ns returns in deterministic mode: the Unix epoch.
+const DETERMINISTIC_NOW_NS: i64 = 0;
 
-        b.insert("plus", numbers::plus);
-        b.insert("minus", numbers::minus);
-        b.insert("mul", numbers::mul);
-        b.insert("div", numbers::div);
-        b.insert("rem", numbers::rem);
+// Builtins whose results can change between evaluations of the same input.
+// In deterministic mode they return a fixed value or fail.
+const NONDETERMINISTIC: &[&str] = &["time.now_ns", "net.lookup_ip_addr"];
 
-        b.insert("net.cidr_contains", net::cidr_contains);
-        b.insert("net.cidr_intersects", net::cidr_intersects);
+lazy_static! {
+    static ref BUILTINS: HashMap<&'static str, Builtin> = registry! {
+        "time.now_ns" => Arity0(time::now_ns),
 
-        b.insert("object.remove", objects::remove);
+        "trace" => Arity1(trace),
 
-        b.insert("re_match", regex::re_match);
+        "all" => Arity1(aggregates::all),
+        "any" => Arity1(aggregates::any),
+        "count" => Arity1(aggregates::count),
+        "max" => Arity1(aggregates::max),
+        "min" => Arity1(aggregates::min),
+        "product" => Arity1(aggregates::product),
+        "sort" => Arity1(aggregates::sort),
+        "sum" => Arity1(aggregates::sum),
 
-        b.insert("and", sets::and);
-        b.insert("or", sets::or);
-        b
-    };
-    static ref BUILTIN3: HashMap<&'static str, Arity3> = {
-        let mut b: HashMap<&'static str, Arity3> = HashMap::new();
-        b.insert("array.slice", arrays::slice);
+        "abs" => Arity1(numbers::abs),
+        "round" => Arity1(numbers::round),
 
-        b.insert("object.get", objects::get);
-        b
-    };
-    static ref BUILTIN4: HashMap<&'static str, Arity4> = {
-        let b: HashMap<&'static str, Arity4> = HashMap::new();
-        b
+        "net.cidr_expand" => Arity1(net::cidr_expand),
+        #[cfg(feature = "dns")]
+        "net.lookup_ip_addr" => Arity1(net::lookup_ip_addr),
+
+        "object.union_n" => Arity1(objects::union_n),
+
+        "strings.to_string" => Arity1(strings::to_string),
+        "upper" => Arity1(strings::upper),
+
+        "time.clock" => Arity1(time::clock),
+        "time.date" => Arity1(time::date),
+        "time.parse_rfc3339_ns" => Arity1(time::parse_rfc3339_ns),
+        "time.weekday" => Arity1(time::weekday),
+
+        "is_array" => Arity1(types::is_array),
+        "is_boolean" => Arity1(types::is_boolean),
+        "is_null" => Arity1(types::is_null),
+        "is_number" => Arity1(types::is_number),
+        "is_object" => Arity1(types::is_object),
+        "is_set" => Arity1(types::is_set),
+        "is_string" => Arity1(types::is_string),
+        "type_name" => Arity1(types::type_name),
+
+        "array.concat" => Arity2(arrays::concat),
+
+        "plus" => Arity2(numbers::plus),
+        "minus" => Arity2(numbers::minus),
+        "mul" => Arity2(numbers::mul),
+        "div" => Arity2(numbers::div),
+        "rem" => Arity2(numbers::rem),
+
+        "net.cidr_contains" => Arity2(net::cidr_contains),
+        "net.cidr_intersects" => Arity2(net::cidr_intersects),
+
+        "object.remove" => Arity2(objects::remove),
+        "object.union" => Arity2(objects::union),
+
+        "rand.intn" => StateArity2(random::intn),
+
+        "re_match" => Arity2(regex::re_match),
+        "regex.match" => Arity2(regex::re_match),
+
+        "and" => Arity2(sets::and),
+        "intersection" => Arity1(sets::intersection),
+        "or" => Arity2(sets::or),
+        "union" => Arity1(sets::union),
+
+        "format_int" => Arity2(strings::format_int),
+        "split" => Arity2(strings::split),
+        "strings.count" => Arity2(strings::count),
+        "trim" => Arity2(strings::trim),
+        "trim_left" => Arity2(strings::trim_left),
+        "trim_right" => Arity2(strings::trim_right),
+
+        "array.slice" => Arity3(arrays::slice),
+
+        "object.get" => Arity3(objects::get),
+
+        "strings.split_n" => Arity3(strings::split_n),
     };
     static ref BUILTIN_NAMES: HashSet<&'static str> = {
-        BUILTIN0
+        BUILTINS
             .keys()
-            .chain(BUILTIN1.keys())
-            .chain(BUILTIN2.keys())
-            .chain(BUILTIN3.keys())
-            .chain(BUILTIN4.keys())
+            .chain(std::iter::once(&print::PRINT))
             .map(|k| *k)
             .collect::<HashSet<&'static str>>()
     };
 }
 
+/// A builtin function called during evaluation, recorded when tracing with
+/// `PolicyConfig::trace_builtins`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuiltinCall {
+    /// The builtin's name, e.g. `upper`.
+    pub name: String,
+    pub args: Vec<Value>,
+    /// The result, or `None` when the call failed or was undefined, which
+    /// leaves the calling expression undefined.
+    pub result: Option<Value>,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Builtins {
     inner: Arc<RefCell<Option<Inner>>>,
@@ -134,49 +199,98 @@ impl Builtins {
         Ok(())
     }
 
-    pub fn builtin0(&self, id: i32, ctx_addr: ValueAddr) -> ValueAddr {
+    pub fn builtin(&self, id: i32, ctx_addr: ValueAddr, args: &[ValueAddr]) -> ValueAddr {
         let maybe_inner = self.inner.borrow();
         let inner = btry!(maybe_inner.as_ref().ok_or(Error::Initialization));
-        inner.builtin0(id, ctx_addr)
+        match inner.call(id, ctx_addr, args) {
+            Ok(addr) => addr,
+            Err(Error::UnknownBuiltinId(id)) => {
+                error!(msg = "unknown builtin function id", id = id);
+                inner
+                    .errors
+                    .borrow_mut()
+                    .push(Error::UnknownBuiltinId(id).to_string());
+                inner.unknown_ids.borrow_mut().push(id);
+                ValueAddr(0)
+            }
+            // Not a failure, e.g. max of an empty array
+            Err(Error::BuiltinUndefined(_)) => ValueAddr(0),
+            Err(err) => {
+                error!(msg = "error processing builtin function", error = %err);
+                let message = match inner.lookup.get(&id) {
+                    Some(name) => format!("{}: {}", name, err),
+                    None => err.to_string(),
+                };
+                inner.errors.borrow_mut().push(message);
+                // The policy sees a failed builtin as undefined
+                ValueAddr(0)
+            }
+        }
     }
 
-    pub fn builtin1(&self, id: i32, ctx_addr: ValueAddr, value: ValueAddr) -> ValueAddr {
-        let maybe_inner = self.inner.borrow();
-        let inner = btry!(maybe_inner.as_ref().ok_or(Error::Initialization));
-        inner.builtin1(id, ctx_addr, value)
+    // Drops the prints and failures of earlier evaluations, which would
+    // otherwise pile up for as long as nothing takes them, and forgets the
+    // numbers rand.intn returned.
+    pub fn clear(&self) {
+        if let Some(inner) = self.inner.borrow().as_ref() {
+            inner.prints.borrow_mut().clear();
+            inner.errors.borrow_mut().clear();
+            inner.unknown_ids.borrow_mut().clear();
+            inner.state.borrow_mut().reset(inner.deterministic.get());
+        }
     }
 
-    pub fn builtin2(&self, id: i32, ctx_addr: ValueAddr, a: ValueAddr, b: ValueAddr) -> ValueAddr {
-        let maybe_inner = self.inner.borrow();
-        let inner = btry!(maybe_inner.as_ref().ok_or(Error::Initialization));
-        inner.builtin2(id, ctx_addr, a, b)
+    pub fn println(&self, message: String) {
+        debug!(message = %message, "println");
+        if let Some(inner) = self.inner.borrow().as_ref() {
+            inner.prints.borrow_mut().push(message);
+        }
     }
 
-    pub fn builtin3(
-        &self,
-        id: i32,
-        ctx_addr: ValueAddr,
-        a: ValueAddr,
-        b: ValueAddr,
-        c: ValueAddr,
-    ) -> ValueAddr {
-        let maybe_inner = self.inner.borrow();
-        let inner = btry!(maybe_inner.as_ref().ok_or(Error::Initialization));
-        inner.builtin3(id, ctx_addr, a, b, c)
+    pub fn take_prints(&self) -> Vec<String> {
+        self.inner
+            .borrow()
+            .as_ref()
+            .map(|inner| inner.prints.replace(Vec::new()))
+            .unwrap_or_default()
     }
 
-    pub fn builtin4(
-        &self,
-        id: i32,
-        ctx_addr: ValueAddr,
-        a: ValueAddr,
-        b: ValueAddr,
-        c: ValueAddr,
-        d: ValueAddr,
-    ) -> ValueAddr {
-        let maybe_inner = self.inner.borrow();
-        let inner = btry!(maybe_inner.as_ref().ok_or(Error::Initialization));
-        inner.builtin4(id, ctx_addr, a, b, c, d)
+    pub fn set_tracing(&self, tracing: bool) {
+        if let Some(inner) = self.inner.borrow().as_ref() {
+            inner.tracing.set(tracing);
+        }
+    }
+
+    pub fn set_deterministic(&self, deterministic: bool) {
+        if let Some(inner) = self.inner.borrow().as_ref() {
+            inner.deterministic.set(deterministic);
+        }
+    }
+
+    pub fn take_trace(&self) -> Vec<BuiltinCall> {
+        self.inner
+            .borrow()
+            .as_ref()
+            .map(|inner| inner.trace.replace(Vec::new()))
+            .unwrap_or_default()
+    }
+
+    pub fn take_errors(&self) -> Vec<String> {
+        self.inner
+            .borrow()
+            .as_ref()
+            .map(|inner| inner.errors.replace(Vec::new()))
+            .unwrap_or_default()
+    }
+
+    // Ids the module called that aren't in its builtin table, which points
+    // at an ABI mismatch rather than a failed builtin.
+    pub fn take_unknown_ids(&self) -> Vec<i32> {
+        self.inner
+            .borrow()
+            .as_ref()
+            .map(|inner| inner.unknown_ids.replace(Vec::new()))
+            .unwrap_or_default()
     }
 }
 
@@ -184,12 +298,21 @@ impl Builtins {
 struct Inner {
     instance: Instance,
     lookup: HashMap<i32, String>,
+    prints: RefCell<Vec<String>>,
+    errors: RefCell<Vec<String>>,
+    unknown_ids: RefCell<Vec<i32>>,
+    tracing: Cell<bool>,
+    trace: RefCell<Vec<BuiltinCall>>,
+    deterministic: Cell<bool>,
+    state: RefCell<EvalState>,
 }
 
 impl Inner {
     fn new(instance: Instance) -> Result<Self, Error> {
-        let builtins_addr = instance.functions().builtins()?;
-        let val: Value = opa_serde::from_instance(&instance, builtins_addr)?;
+        let val: Value = match instance.functions().builtins()? {
+            Some(addr) => opa_serde::from_instance(&instance, addr)?,
+            None => Value::Object(Map::new()),
+        };
 
         let mut lookup = HashMap::new();
         for (k, v) in val.try_into_object()?.into_iter() {
@@ -200,120 +323,147 @@ impl Inner {
             lookup.insert(v as i32, k);
         }
 
-        let inner = Inner { instance, lookup };
+        let inner = Inner {
+            instance,
+            lookup,
+            prints: RefCell::new(Vec::new()),
+            errors: RefCell::new(Vec::new()),
+            unknown_ids: RefCell::new(Vec::new()),
+            tracing: Cell::new(false),
+            trace: RefCell::new(Vec::new()),
+            deterministic: Cell::new(false),
+            state: RefCell::new(EvalState::default()),
+        };
         Ok(inner)
     }
 
-    fn builtin0(&self, id: i32, _ctx_addr: ValueAddr) -> ValueAddr {
-        let name = btry!(self
+    fn call(&self, id: i32, _ctx_addr: ValueAddr, args: &[ValueAddr]) -> Result<ValueAddr, Error> {
+        let name = self
             .lookup
             .get(&id)
-            .ok_or_else(|| Error::UnknownBuiltinId(id)));
-        let func = btry!(BUILTIN0
-            .get(name.as_str())
-            .ok_or_else(|| Error::UnknownBuiltin(name.to_string())));
-        debug!(name = %name, arity = 0, "calling builtin function...");
-        let result = btry!(func());
-        debug!(name = %name, arity = 0, result = ?result, "called builtin function.");
-
-        btry!(opa_serde::to_instance(&self.instance, &result))
-    }
-
-    fn builtin1(&self, id: i32, _ctx_addr: ValueAddr, value: ValueAddr) -> ValueAddr {
-        let name = btry!(self
-            .lookup
-            .get(&id)
-            .ok_or_else(|| Error::UnknownBuiltinId(id)));
-        let func = btry!(BUILTIN1
-            .get(name.as_str())
-            .ok_or_else(|| Error::UnknownBuiltin(name.to_string())));
-
-        let val = btry!(opa_serde::from_instance(&self.instance, value));
-
-        debug!(name = %name, arity = 1, arg0 = ?val, "calling builtin function...");
-        let result = btry!(func(val));
-        debug!(name = %name, arity = 1, result = ?result, "called builtin function.");
+            .ok_or_else(|| Error::UnknownBuiltinId(id))?;
+
+        let args = args
+            .iter()
+            .map(|addr| opa_serde::from_instance(&self.instance, *addr))
+            .collect::<Result<Vec<Value>, _>>()?;
+        let arity = args.len();
+
+        let traced = if self.tracing.get() {
+            Some(args.clone())
+        } else {
+            None
+        };
+
+        if name == print::PRINT && arity == 1 {
+            let message = print::render(args.into_iter().next().unwrap_or_default())?;
+            debug!(message = %message, "print");
+            self.prints.borrow_mut().push(message);
+            self.record(name, traced, Some(&true.into()));
+            let addr = opa_serde::to_instance(&self.instance, &true)?;
+            return Ok(addr);
+        }
 
-        btry!(opa_serde::to_instance(&self.instance, &result))
+        debug!(name = %name, arity = arity, args = ?args, "calling builtin function...");
+        let mut args = args.into_iter();
+        let mut arg = || args.next().unwrap_or_default();
+        let result = match (BUILTINS.get(name.as_str()), arity) {
+            _ if self.deterministic.get() && NONDETERMINISTIC.contains(&name.as_str()) => {
+                fixed(name)
+            }
+            (Some(Builtin::Arity0(func)), 0) => func(),
+            (Some(Builtin::Arity1(func)), 1) => func(arg()),
+            (Some(Builtin::Arity2(func)), 2) => func(arg(), arg()),
+            (Some(Builtin::Arity3(func)), 3) => func(arg(), arg(), arg()),
+            (Some(Builtin::Arity4(func)), 4) => func(arg(), arg(), arg(), arg()),
+            (Some(Builtin::StateArity2(func)), 2) => {
+                func(&mut self.state.borrow_mut(), arg(), arg())
+            }
+            _ => Err(Error::UnknownBuiltin(name.to_string())),
+        };
+        self.record(name, traced, result.as_ref().ok());
+        let result = result?;
+        debug!(name = %name, arity = arity, result = ?result, "called builtin function.");
+
+        let addr = opa_serde::to_instance(&self.instance, &result)?;
+        Ok(addr)
     }
 
-    fn builtin2(&self, id: i32, _ctx_addr: ValueAddr, a: ValueAddr, b: ValueAddr) -> ValueAddr {
-        let name = btry!(self
-            .lookup
-            .get(&id)
-            .ok_or_else(|| Error::UnknownBuiltinId(id)));
-        let func = btry!(BUILTIN2
-            .get(name.as_str())
-            .ok_or_else(|| Error::UnknownBuiltin(name.to_string())));
-
-        let val1 = btry!(opa_serde::from_instance(&self.instance, a));
-        let val2 = btry!(opa_serde::from_instance(&self.instance, b));
-
-        debug!(name = %name, arity = 2, arg0 = ?val1, arg1 = ?val2, "calling builtin function...");
-        let result = btry!(func(val1, val2));
-        debug!(name = %name, arity = 2, result = ?result, "called builtin function.");
+    // Adds a call to the trace. The arguments are only copied when tracing.
+    fn record(&self, name: &str, args: Option<Vec<Value>>, result: Option<&Value>) {
+        if let Some(args) = args {
+            self.trace.borrow_mut().push(BuiltinCall {
+                name: name.to_string(),
+                args,
+                result: result.cloned(),
+            });
+        }
+    }
+}
 
-        btry!(opa_serde::to_instance(&self.instance, &result))
+// The result of a nondeterministic builtin in deterministic mode.
+fn fixed(name: &str) -> Result<Value, Error> {
+    match name {
+        "time.now_ns" => Ok(DETERMINISTIC_NOW_NS.into()),
+        name => Err(Error::Nondeterministic(name.to_string())),
     }
+}
 
-    fn builtin3(
-        &self,
-        id: i32,
-        _ctx_addr: ValueAddr,
-        a: ValueAddr,
-        b: ValueAddr,
-        c: ValueAddr,
-    ) -> ValueAddr {
-        let name = btry!(self
-            .lookup
-            .get(&id)
-            .ok_or_else(|| Error::UnknownBuiltinId(id)));
-        let func = btry!(BUILTIN3
-            .get(name.as_str())
-            .ok_or_else(|| Error::UnknownBuiltin(name.to_string())));
+fn trace(value: Value) -> Result<Value, Error> {
+    debug!("TRACE: {:?}", value);
+    value.try_into_string().map(|_| true.into())
+}
 
-        let val1 = btry!(opa_serde::from_instance(&self.instance, a));
-        let val2 = btry!(opa_serde::from_instance(&self.instance, b));
-        let val3 = btry!(opa_serde::from_instance(&self.instance, c));
+#[cfg(test)]
+mod tests {
+    use std::fs;
 
-        debug!(name = %name, arity = 3, arg0 = ?val1, arg1 = ?val2, arg2 = ?val3, "calling builtin function...");
-        let result = btry!(func(val1, val2, val3));
-        debug!(name = %name, arity = 3, result = ?result, "called builtin function.");
+    use crate::runtime::{Instance, Memory, Module};
 
-        btry!(opa_serde::to_instance(&self.instance, &result))
-    }
+    use super::*;
 
-    fn builtin4(
-        &self,
-        id: i32,
-        _ctx_addr: ValueAddr,
-        a: ValueAddr,
-        b: ValueAddr,
-        c: ValueAddr,
-        d: ValueAddr,
-    ) -> ValueAddr {
-        let name = btry!(self
-            .lookup
-            .get(&id)
-            .ok_or_else(|| Error::UnknownBuiltinId(id)));
-        let func = btry!(BUILTIN4
-            .get(name.as_str())
-            .ok_or_else(|| Error::UnknownBuiltin(name.to_string())));
+    #[test]
+    fn test_unknown_builtin_id() {
+        let bytes = fs::read("tests/empty.wasm").unwrap();
+        let module = Module::from_bytes(bytes).unwrap();
+        let memory = Memory::from_module(&module);
+        let instance = Instance::new(&module, memory).unwrap();
 
-        let val1 = btry!(opa_serde::from_instance(&self.instance, a));
-        let val2 = btry!(opa_serde::from_instance(&self.instance, b));
-        let val3 = btry!(opa_serde::from_instance(&self.instance, c));
-        let val4 = btry!(opa_serde::from_instance(&self.instance, d));
+        let args = [ValueAddr(0); 6];
+        for arity in 0..args.len() {
+            let addr = instance
+                .builtins()
+                .builtin(99, ValueAddr(0), &args[..arity]);
+            assert_eq!(ValueAddr(0), addr);
+        }
 
-        debug!(name = %name, arity = 4, arg0 = ?val1, arg1 = ?val2, arg2 = ?val3, arg3 = ?val4, "calling builtin function...");
-        let result = btry!(func(val1, val2, val3, val4));
-        debug!(name = %name, arity = 4, result = ?result, "called builtin function.");
+        let errors = instance.builtins().take_errors();
+        assert_eq!(args.len(), errors.len());
+        assert_eq!("Unknown builtin id: 99", errors[0]);
+        assert!(instance.builtins().take_errors().is_empty());
+        assert_eq!(vec![99; args.len()], instance.builtins().take_unknown_ids());
+        assert!(instance.builtins().take_unknown_ids().is_empty());
+
+        instance.builtins().builtin(99, ValueAddr(0), &[]);
+        instance.builtins().clear();
+        assert!(instance.builtins().take_errors().is_empty());
+        assert!(instance.builtins().take_unknown_ids().is_empty());
+    }
 
-        btry!(opa_serde::to_instance(&self.instance, &result))
+    #[test]
+    fn test_builtin_names() {
+        assert!(!BUILTINS.is_empty());
+        for name in BUILTINS.keys() {
+            assert!(BUILTIN_NAMES.contains(name), "{} is not listed", name);
+        }
+        assert!(BUILTIN_NAMES.contains(print::PRINT));
+        assert_eq!(BUILTINS.len() + 1, BUILTIN_NAMES.len());
     }
-}
 
-fn trace(value: Value) -> Result<Value, Error> {
-    debug!("TRACE: {:?}", value);
-    value.try_into_string().map(|_| true.into())
+    #[test]
+    fn test_fixed() {
+        assert_eq!(Value::from(0), fixed("time.now_ns").unwrap());
+        let err = fixed("net.lookup_ip_addr").unwrap_err();
+        assert!(matches!(err, Error::Nondeterministic(_)));
+    }
 }
@@ -1,4 +1,6 @@
 use std::net::IpAddr;
+#[cfg(feature = "dns")]
+use std::net::ToSocketAddrs;
 
 use ipnetwork::IpNetwork;
 
@@ -64,6 +66,17 @@ pub fn cidr_expand(cidr: Value) -> Result<Value, Error> {
     Ok(v.into())
 }
 
+#[cfg(feature = "dns")]
+pub fn lookup_ip_addr(name: Value) -> Result<Value, Error> {
+    let name = name.try_into_string()?;
+    let addrs = (name.as_str(), 0)
+        .to_socket_addrs()
+        .map_err(|e| Error::LookupHost(name.clone(), e))?
+        .map(|addr| Value::String(addr.ip().to_string()))
+        .collect::<Set<Value>>();
+    Ok(addrs.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -111,4 +124,20 @@ mod tests {
                 .unwrap()
         );
     }
+
+    #[cfg(feature = "dns")]
+    #[test]
+    fn test_net_lookup_ip_addr() {
+        let addrs = lookup_ip_addr("localhost".into())
+            .unwrap()
+            .try_into_set()
+            .unwrap();
+        assert!(!addrs.is_empty());
+        assert!(addrs.iter().all(|addr| addr
+            .as_str()
+            .and_then(|s| s.parse::<IpAddr>().ok())
+            .map_or(false, |ip| ip.is_loopback())));
+
+        assert!(lookup_ip_addr(Value::from(1)).is_err());
+    }
 }
@@ -44,53 +44,21 @@ macro_rules! binary_op {
     );
 }
 
-macro_rules! binary_op_func {
-    ($name:ident, $op:tt) => {
-        pub fn $name(left: Value, right: Value) -> Result<Value, Error> {
-            let v = match (left, right) {
-                (left, right) if left.is_i64() && right.is_i64() => {
-                    let left = left.try_into_i64()?;
-                    let right = right.try_into_i64()?;
-                    let result = left.$op(right);
-                    Value::Number(result.into())
-                }
-                (Value::Number(left), Value::Number(right)) => {
-                    let left = left.try_into_f64()?;
-                    let right = right.try_into_f64()?;
-                    let result = left.$op(right);
-                    Value::Number(result.into())
-                }
-                (a, _) => return Err(Error::InvalidType("number", a)),
-            };
-            Ok(v)
-        }
-    };
-}
-
 unary_op!(abs, abs);
 
-binary_op!(plus, +);
-binary_op!(mul, *);
 binary_op!(div, /);
 binary_op!(rem, %);
 
-binary_op_func!(min, min);
-binary_op_func!(max, max);
+pub fn plus(left: Value, right: Value) -> Result<Value, Error> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => Ok(left.checked_add(&right)?.into()),
+        (a, _) => Err(Error::InvalidType("number", a)),
+    }
+}
 
 pub fn minus(left: Value, right: Value) -> Result<Value, Error> {
     let v = match (left, right) {
-        (left, right) if left.is_i64() && right.is_i64() => {
-            let left = left.try_into_i64()?;
-            let right = right.try_into_i64()?;
-            let result = left - right;
-            Value::Number(result.into())
-        }
-        (Value::Number(left), Value::Number(right)) => {
-            let left = left.try_into_f64()?;
-            let right = right.try_into_f64()?;
-            let result = left - right;
-            Value::Number(result.into())
-        }
+        (Value::Number(left), Value::Number(right)) => left.checked_sub(&right)?.into(),
         (Value::Set(left), Value::Set(right)) => {
             Value::Set(left.difference(&right).cloned().collect())
         }
@@ -99,6 +67,13 @@ pub fn minus(left: Value, right: Value) -> Result<Value, Error> {
     Ok(v)
 }
 
+pub fn mul(left: Value, right: Value) -> Result<Value, Error> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => Ok(left.checked_mul(&right)?.into()),
+        (a, _) => Err(Error::InvalidType("number", a)),
+    }
+}
+
 pub fn round(val: Value) -> Result<Value, Error> {
     let v = match val {
         val if val.is_i64() => {
@@ -18,6 +18,33 @@ pub fn remove(object: Value, keys: Value) -> Result<Value, Error> {
     }
 }
 
+pub fn union(a: Value, b: Value) -> Result<Value, Error> {
+    let mut a = Value::Object(a.try_into_object()?);
+    let b = Value::Object(b.try_into_object()?);
+    a.merge(b);
+    Ok(a)
+}
+
+// Sets are merged in their sorted order, arrays in index order.
+pub fn union_n(objects: Value) -> Result<Value, Error> {
+    match objects {
+        Value::Array(v) => union_all(v.into_iter()),
+        Value::Set(v) => union_all(v.into_iter()),
+        v => Err(Error::InvalidType("array or set of objects", v)),
+    }
+}
+
+fn union_all<I>(iter: I) -> Result<Value, Error>
+where
+    I: Iterator<Item = Value>,
+{
+    let mut merged = Value::Object(Map::new());
+    for object in iter {
+        merged.merge(Value::Object(object.try_into_object()?));
+    }
+    Ok(merged)
+}
+
 fn remove_all<I>(mut map: Map<String, Value>, iter: I) -> Result<Value, Error>
 where
     I: Iterator<Item = Value>,
@@ -27,3 +54,51 @@ where
     }
     Ok(map.into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(entries: Vec<(&str, Value)>) -> Value {
+        Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect::<Map<String, Value>>(),
+        )
+    }
+
+    #[test]
+    fn test_union() {
+        let a = object(vec![("a", 1.into()), ("b", object(vec![("c", 2.into())]))]);
+        let b = object(vec![("b", object(vec![("d", 3.into())]))]);
+        let expected = object(vec![
+            ("a", 1.into()),
+            ("b", object(vec![("c", 2.into()), ("d", 3.into())])),
+        ]);
+        assert_eq!(expected, union(a, b).unwrap());
+
+        assert!(union(object(vec![]), Value::from(1)).is_err());
+    }
+
+    #[test]
+    fn test_union_n() {
+        let objects = Value::Array(vec![
+            object(vec![("a", 1.into()), ("b", object(vec![("x", 1.into())]))]),
+            object(vec![("a", 2.into()), ("b", object(vec![("y", 2.into())]))]),
+            object(vec![("a", 3.into()), ("c", 3.into())]),
+        ]);
+        let expected = object(vec![
+            ("a", 3.into()),
+            ("b", object(vec![("x", 1.into()), ("y", 2.into())])),
+            ("c", 3.into()),
+        ]);
+        assert_eq!(expected, union_n(objects).unwrap());
+
+        assert_eq!(object(vec![]), union_n(Value::Array(vec![])).unwrap());
+
+        let invalid = Value::Array(vec![object(vec![]), Value::from("a")]);
+        assert!(union_n(invalid).is_err());
+        assert!(union_n(object(vec![])).is_err());
+    }
+}
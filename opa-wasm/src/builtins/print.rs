@@ -0,0 +1,59 @@
+use crate::{Error, Value};
+
+pub const PRINT: &str = "internal.print";
+
+// Each operand is a collection holding the operand's values, which is empty
+// when the operand is undefined.
+pub fn render(operands: Value) -> Result<String, Error> {
+    let operands = operands.try_into_array()?;
+    let mut rendered = Vec::with_capacity(operands.len());
+    for operand in operands {
+        let values = match operand {
+            Value::Array(values) => values,
+            Value::Set(values) => values.into_iter().collect(),
+            v => return Err(Error::InvalidType("array", v)),
+        };
+
+        if values.is_empty() {
+            rendered.push("<undefined>".to_string());
+        } else {
+            let values = values
+                .into_iter()
+                .map(|v| match v {
+                    Value::String(s) => s,
+                    v => v.to_string(),
+                })
+                .collect::<Vec<String>>();
+            rendered.push(values.join(", "));
+        }
+    }
+    Ok(rendered.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value;
+
+    #[test]
+    fn test_render() {
+        let mut set = value::Set::new();
+        set.insert(Value::from(1));
+        let operands = Value::Array(vec![
+            Value::Array(vec!["hello".into()]),
+            Value::Set(set),
+            Value::Array(vec![]),
+        ]);
+        let out = render(operands).unwrap();
+        assert_eq!("hello 1 <undefined>", out);
+
+        let operands = Value::Array(vec![Value::Array(vec![Value::Array(vec![
+            "a".into(),
+            true.into(),
+        ])])]);
+        let out = render(operands).unwrap();
+        assert_eq!("[\"a\",true]", out);
+
+        assert!(render(Value::from("hello")).is_err());
+    }
+}
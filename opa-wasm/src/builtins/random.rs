@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::EvalState;
+use crate::{Error, Value};
+
+// What rand.intn's generator starts each evaluation with in deterministic
+// mode.
+const DETERMINISTIC_SEED: u64 = 0;
+
+// The state behind rand.intn. Like OPA, a call returns the same number as
+// any earlier call in the evaluation with the same arguments.
+#[derive(Debug, Default)]
+pub struct Random {
+    seeded: Option<StdRng>,
+    drawn: HashMap<(String, i64), i64>,
+}
+
+impl Random {
+    // Starts a new evaluation. Deterministic evaluations restart from a
+    // fixed seed, so the same calls return the same numbers.
+    pub fn reset(&mut self, deterministic: bool) {
+        self.drawn.clear();
+        self.seeded = if deterministic {
+            Some(StdRng::seed_from_u64(DETERMINISTIC_SEED))
+        } else {
+            None
+        };
+    }
+
+    // A number in [0, |n|), or 0 when n is 0.
+    pub fn intn(&mut self, key: Value, n: Value) -> Result<Value, Error> {
+        let key = key.try_into_string()?;
+        let n = n.try_into_i64()?;
+        if n == 0 {
+            return Ok(0.into());
+        }
+        let n = n
+            .checked_abs()
+            .ok_or_else(|| Error::InvalidNumber(n.to_string()))?;
+
+        let seeded = &mut self.seeded;
+        let drawn = self.drawn.entry((key, n)).or_insert_with(|| match seeded {
+            Some(rng) => rng.gen_range(0, n),
+            None => rand::thread_rng().gen_range(0, n),
+        });
+        Ok((*drawn).into())
+    }
+}
+
+pub fn intn(state: &mut EvalState, key: Value, n: Value) -> Result<Value, Error> {
+    state.random.intn(key, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(random: &mut Random, key: &str, n: i64) -> Value {
+        random.intn(key.into(), n.into()).unwrap()
+    }
+
+    #[test]
+    fn test_intn() {
+        let mut random = Random::default();
+        let first = draw(&mut random, "a", 1000);
+        assert!(first.clone().try_into_i64().unwrap() < 1000);
+        assert_eq!(first, draw(&mut random, "a", 1000));
+        assert!(draw(&mut random, "b", -10).try_into_i64().unwrap() < 10);
+        assert_eq!(Value::from(0), draw(&mut random, "a", 0));
+        assert!(random.intn(1.into(), 10.into()).is_err());
+        assert!(random.intn("a".into(), i64::min_value().into()).is_err());
+    }
+
+    #[test]
+    fn test_intn_deterministic() {
+        let mut random = Random::default();
+        random.reset(true);
+        let drawn = (0..10)
+            .map(|i| draw(&mut random, &i.to_string(), 1 << 40))
+            .collect::<Vec<Value>>();
+
+        random.reset(true);
+        for (i, value) in drawn.into_iter().enumerate() {
+            assert_eq!(value, draw(&mut random, &i.to_string(), 1 << 40));
+        }
+    }
+}
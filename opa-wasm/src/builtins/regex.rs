@@ -1,32 +1,216 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
 use regex::Regex;
 
 use crate::{Error, Value};
 
-// TODO - memoize the compilation of the regex
+const CACHE_SIZE: usize = 64;
+
+lazy_static! {
+    static ref CACHE: Mutex<HashMap<String, Regex>> = Mutex::new(HashMap::new());
+}
+
+// OPA evaluates re_match with Go's regexp.MatchString, which reports whether
+// the pattern matches anywhere in the value. Patterns are not anchored; use
+// ^ and $ to match the whole value.
 pub fn re_match(pattern: Value, value: Value) -> Result<Value, Error> {
-    let pattern = format!("^{}$", pattern.try_into_string()?);
-    let regex = Regex::new(&pattern).map_err(Error::InvalidRegex)?;
+    let pattern = pattern.try_into_string()?;
+    let regex = compile(&pattern)?;
     let value = value.try_into_string()?;
     let b = regex.is_match(&value);
     Ok(b.into())
 }
 
+fn compile(pattern: &str) -> Result<Regex, Error> {
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(regex) = cache.get(pattern) {
+        return Ok(regex.clone());
+    }
+
+    let regex = Regex::new(&translate(pattern)?).map_err(Error::InvalidRegex)?;
+    if cache.len() >= CACHE_SIZE {
+        cache.clear();
+    }
+    cache.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+// Go's RE2 syntax is close to the regex crate's, but not identical:
+//
+// - \d, \w and \s are ASCII only in Go and Unicode aware in rust. They are
+//   rewritten to explicit ASCII classes.
+// - \Q...\E quotes a literal in Go and is not supported in rust. The quoted
+//   text is escaped instead.
+// - Go only understands the i, m, s and U flags. Other flags, like rust's x
+//   for verbose mode, are rejected instead of changing the meaning of the
+//   pattern.
+//
+// Named groups, (?P<name>...), are the same in both. Anything else the
+// regex crate can't parse is reported as an invalid regex.
+fn translate(pattern: &str) -> Result<String, Error> {
+    let unsupported = |construct: &str| {
+        Error::UnsupportedRegex(format!("{} in pattern {:?}", construct, pattern))
+    };
+
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let mut out = String::with_capacity(pattern.len());
+    let mut in_class = false;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                let c = chars[i + 1];
+                i += 2;
+                match (c, in_class) {
+                    ('Q', false) => {
+                        let mut literal = String::new();
+                        while i < chars.len() {
+                            if chars[i] == '\\' && chars.get(i + 1) == Some(&'E') {
+                                i += 2;
+                                break;
+                            }
+                            literal.push(chars[i]);
+                            i += 1;
+                        }
+                        out.push_str(&regex::escape(&literal));
+                    }
+                    ('d', false) => out.push_str("[0-9]"),
+                    ('D', false) => out.push_str("[^0-9]"),
+                    ('w', false) => out.push_str("[0-9A-Za-z_]"),
+                    ('W', false) => out.push_str("[^0-9A-Za-z_]"),
+                    ('s', false) => out.push_str(r"[\t\n\f\r ]"),
+                    ('S', false) => out.push_str(r"[^\t\n\f\r ]"),
+                    ('d', true) => out.push_str("0-9"),
+                    ('w', true) => out.push_str("0-9A-Za-z_"),
+                    ('s', true) => out.push_str(r"\t\n\f\r "),
+                    ('D', true) | ('W', true) | ('S', true) => {
+                        return Err(unsupported(&format!("\\{} in a character class", c)));
+                    }
+                    (c, _) => {
+                        out.push('\\');
+                        out.push(c);
+                    }
+                }
+                continue;
+            }
+            '[' if !in_class => {
+                in_class = true;
+                out.push('[');
+                i += 1;
+                if chars.get(i) == Some(&'^') {
+                    out.push('^');
+                    i += 1;
+                }
+                // A ] right after the opening bracket is a literal
+                if chars.get(i) == Some(&']') {
+                    out.push_str(r"\]");
+                    i += 1;
+                }
+                continue;
+            }
+            '[' if chars.get(i + 1) == Some(&':') => {
+                // ASCII class such as [:alpha:], same syntax in both
+                while i < chars.len() {
+                    out.push(chars[i]);
+                    i += 1;
+                    if chars[i - 1] == ']' && chars[i - 2] == ':' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            ']' if in_class => in_class = false,
+            '(' if !in_class && chars.get(i + 1) == Some(&'?') => {
+                let flags = chars[i + 2..]
+                    .iter()
+                    .take_while(|c| **c != ')' && **c != ':')
+                    .collect::<String>();
+                let is_group = flags.starts_with('P') || flags.starts_with('<');
+                if !is_group {
+                    if let Some(flag) = flags.chars().find(|c| !"imsU-".contains(*c)) {
+                        return Err(unsupported(&format!("flag {:?}", flag)));
+                    }
+                }
+            }
+            _ => (),
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+    Ok(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_re_match() {
-        let result = re_match("[a-z]*".into(), "hello".into())
+    fn is_match(pattern: &str, value: &str) -> bool {
+        re_match(pattern.into(), value.into())
             .unwrap()
             .as_bool()
-            .unwrap();
-        assert_eq!(true, result);
-
-        let result = re_match("[a-z]*".into(), "Hello".into())
             .unwrap()
-            .as_bool()
-            .unwrap();
-        assert_eq!(false, result);
+    }
+
+    #[test]
+    fn test_re_match() {
+        assert_eq!(true, is_match("^[a-z]*$", "hello"));
+        assert_eq!(false, is_match("^[a-z]*$", "Hello"));
+    }
+
+    #[test]
+    fn test_re_match_unanchored() {
+        assert_eq!(true, is_match("[a-z]+", "Hello"));
+        assert_eq!(true, is_match("ell", "Hello"));
+        assert_eq!(false, is_match("^ell", "Hello"));
+        assert_eq!(false, is_match("ell$", "Hello"));
+    }
+
+    #[test]
+    fn test_re_match_named_groups() {
+        assert_eq!(
+            true,
+            is_match(r"^(?P<user>[a-z]+)@(?P<host>[a-z.]+)$", "alice@example.com")
+        );
+        assert_eq!(
+            false,
+            is_match(r"^(?P<user>[a-z]+)@(?P<host>[a-z.]+)$", "alice")
+        );
+    }
+
+    #[test]
+    fn test_re_match_go_syntax() {
+        // ASCII only perl classes
+        assert_eq!(false, is_match(r"^\d+$", "١٢٣"));
+        assert_eq!(true, is_match(r"^[\d-]+$", "12-34"));
+        assert_eq!(false, is_match(r"^\w+$", "héllo"));
+
+        assert_eq!(true, is_match(r"^\Qa.b*c\E$", "a.b*c"));
+        assert_eq!(false, is_match(r"^\Qa.b*c\E$", "axbbc"));
+
+        assert_eq!(true, is_match(r"(?i)^HELLO$", "hello"));
+        assert_eq!(true, is_match(r"^[]a]+$", "a]"));
+        assert_eq!(true, is_match(r"^[[:alpha:]]+$", "abc"));
+    }
+
+    #[test]
+    fn test_re_match_unsupported() {
+        let err = re_match(r"(?x) a b".into(), "ab".into()).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedRegex(_)));
+
+        let err = re_match(r"[\D]".into(), "a".into()).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedRegex(_)));
+
+        let err = re_match(r"\C".into(), "a".into()).unwrap_err();
+        assert!(matches!(err, Error::InvalidRegex(_)));
+    }
+
+    #[test]
+    fn test_compile_cached() {
+        let first = compile("^cached$").unwrap();
+        let second = compile("^cached$").unwrap();
+        assert_eq!(first.as_str(), second.as_str());
+        assert!(CACHE.lock().unwrap().contains_key("^cached$"));
     }
 }
@@ -1,3 +1,4 @@
+use crate::value::Set;
 use crate::{Error, Value};
 
 pub fn and(left: Value, right: Value) -> Result<Value, Error> {
@@ -11,3 +12,88 @@ pub fn or(left: Value, right: Value) -> Result<Value, Error> {
     let right = right.try_into_set()?;
     Ok(Value::Set(left.union(&right).cloned().collect()))
 }
+
+// The intersection of a set of sets. Like OPA, the intersection of no sets is
+// the empty set.
+pub fn intersection(sets: Value) -> Result<Value, Error> {
+    let mut sets = sets.try_into_set()?.into_iter().map(Value::try_into_set);
+    let first = match sets.next() {
+        Some(first) => first?,
+        None => return Ok(Value::Set(Set::new())),
+    };
+    let result = sets.try_fold(first, |result, set| {
+        let set = set?;
+        Ok::<_, Error>(result.into_iter().filter(|v| set.contains(v)).collect())
+    })?;
+    Ok(Value::Set(result))
+}
+
+// The union of a set of sets.
+pub fn union(sets: Value) -> Result<Value, Error> {
+    let mut result = Set::new();
+    for set in sets.try_into_set()? {
+        result.extend(set.try_into_set()?);
+    }
+    Ok(Value::Set(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::numbers;
+    use super::*;
+
+    fn set(values: Vec<i64>) -> Value {
+        Value::from_set_iter(values)
+    }
+
+    fn sets(sets: Vec<Vec<i64>>) -> Value {
+        Value::from_set_iter(sets.into_iter().map(set))
+    }
+
+    #[test]
+    fn test_and_or() {
+        assert_eq!(set(vec![2]), and(set(vec![1, 2]), set(vec![2, 3])).unwrap());
+        assert_eq!(
+            set(vec![1, 2, 3]),
+            or(set(vec![1, 2]), set(vec![2, 3])).unwrap()
+        );
+        assert!(and(set(vec![1]), Value::from(vec![1])).is_err());
+    }
+
+    #[test]
+    fn test_difference() {
+        let diff = numbers::minus(set(vec![1, 2, 3]), set(vec![2, 4])).unwrap();
+        assert_eq!(set(vec![1, 3]), diff);
+        let diff = numbers::minus(set(vec![1, 2]), set(vec![])).unwrap();
+        assert_eq!(set(vec![1, 2]), diff);
+        let diff = numbers::minus(set(vec![]), set(vec![1])).unwrap();
+        assert_eq!(set(vec![]), diff);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let result = intersection(sets(vec![vec![1, 2, 3], vec![2, 3], vec![3, 2, 5]])).unwrap();
+        assert_eq!(set(vec![2, 3]), result);
+        assert_eq!(set(vec![1]), intersection(sets(vec![vec![1]])).unwrap());
+        assert_eq!(
+            set(vec![]),
+            intersection(sets(vec![vec![1, 2], vec![]])).unwrap()
+        );
+        assert_eq!(set(vec![]), intersection(sets(vec![])).unwrap());
+    }
+
+    #[test]
+    fn test_union() {
+        let result = union(sets(vec![vec![1, 2], vec![2, 3], vec![]])).unwrap();
+        assert_eq!(set(vec![1, 2, 3]), result);
+        assert_eq!(set(vec![]), union(sets(vec![])).unwrap());
+        assert_eq!(set(vec![]), union(sets(vec![vec![]])).unwrap());
+    }
+
+    #[test]
+    fn test_not_sets() {
+        assert!(union(Value::from(vec![set(vec![1])])).is_err());
+        assert!(union(Value::from_set_iter(vec![Value::from(1)])).is_err());
+        assert!(intersection(Value::from_set_iter(vec![Value::from(vec![1])])).is_err());
+    }
+}
@@ -1,6 +1,314 @@
+use crate::value::Number;
 use crate::{Error, Value};
 
+pub fn count(search: Value, substring: Value) -> Result<Value, Error> {
+    let search = search.try_into_string()?;
+    let substring = substring.try_into_string()?;
+
+    // Matches go's strings.Count, where an empty substring matches
+    // before and after every rune.
+    let count = if substring.is_empty() {
+        search.chars().count() + 1
+    } else {
+        search.matches(substring.as_str()).count()
+    };
+    Ok(count.into())
+}
+
+pub fn split(string: Value, delimiter: Value) -> Result<Value, Error> {
+    split_n(string, delimiter, Value::from(-1))
+}
+
+pub fn split_n(string: Value, delimiter: Value, n: Value) -> Result<Value, Error> {
+    let s = string.try_into_string()?;
+    let delimiter = delimiter.try_into_string()?;
+    let n = n.try_into_i64()?;
+
+    // Follows go's strings.SplitN: n == 0 returns no substrings and
+    // n < 0 returns all of them.
+    let parts: Vec<&str> = match n {
+        0 => Vec::new(),
+        n if delimiter.is_empty() => {
+            let mut parts = s
+                .char_indices()
+                .map(|(i, c)| &s[i..i + c.len_utf8()])
+                .collect::<Vec<&str>>();
+            if n > 0 && parts.len() > n as usize {
+                let start = parts[..n as usize - 1]
+                    .iter()
+                    .map(|p| p.len())
+                    .sum::<usize>();
+                parts.truncate(n as usize - 1);
+                parts.push(&s[start..]);
+            }
+            parts
+        }
+        n if n < 0 => s.split(delimiter.as_str()).collect(),
+        n => s.splitn(n as usize, delimiter.as_str()).collect(),
+    };
+    Ok(parts.into())
+}
+
+// The cutset is matched per char, like go's strings.Trim, so multi-byte
+// characters are never split.
+pub fn trim(string: Value, cutset: Value) -> Result<Value, Error> {
+    let s = string.try_into_string()?;
+    let cutset = cutset.try_into_string()?;
+    let trimmed = s.trim_matches(|c| cutset.contains(c));
+    Ok(trimmed.into())
+}
+
+pub fn trim_left(string: Value, cutset: Value) -> Result<Value, Error> {
+    let s = string.try_into_string()?;
+    let cutset = cutset.try_into_string()?;
+    let trimmed = s.trim_start_matches(|c| cutset.contains(c));
+    Ok(trimmed.into())
+}
+
+pub fn trim_right(string: Value, cutset: Value) -> Result<Value, Error> {
+    let s = string.try_into_string()?;
+    let cutset = cutset.try_into_string()?;
+    let trimmed = s.trim_end_matches(|c| cutset.contains(c));
+    Ok(trimmed.into())
+}
+
 pub fn upper(string: Value) -> Result<Value, Error> {
     let s = string.try_into_string()?;
     Ok(Value::String(s.to_uppercase()))
 }
+
+// Like go's strconv.FormatInt, after truncating the number toward zero, which
+// is what OPA does for floats.
+pub fn format_int(number: Value, base: Value) -> Result<Value, Error> {
+    let n = match number {
+        Value::Number(ref n) => match n.as_i64() {
+            Some(i) => i as i128,
+            None => n
+                .as_f64()
+                .map(f64::trunc)
+                .filter(|f| f.abs() < i128::max_value() as f64)
+                .ok_or_else(|| Error::InvalidNumber(n.to_string()))? as i128,
+        },
+        v => return Err(Error::InvalidType("number", v)),
+    };
+    let base = match base.as_i64() {
+        Some(b @ 2) | Some(b @ 8) | Some(b @ 10) | Some(b @ 16) => b as u32,
+        _ => return Err(Error::InvalidType("base of 2, 8, 10 or 16", base)),
+    };
+
+    let mut digits = Vec::new();
+    let mut rest = n.abs() as u128;
+    loop {
+        let digit = (rest % base as u128) as u32;
+        digits.push(std::char::from_digit(digit, base).unwrap_or('0'));
+        rest /= base as u128;
+        if rest == 0 {
+            break;
+        }
+    }
+    if n < 0 {
+        digits.push('-');
+    }
+    Ok(digits.into_iter().rev().collect::<String>().into())
+}
+
+// Renders a value the way it would appear in a message: strings as is,
+// numbers without trailing zeros, and everything else as canonical JSON,
+// with sets as arrays and the numbers inside trimmed the same way.
+pub fn to_string(value: Value) -> Result<Value, Error> {
+    let s = match value {
+        Value::String(s) => s,
+        Value::Number(n) => canonical_number(&n),
+        v => canonical_numbers(v).to_opa_json(),
+    };
+    Ok(s.into())
+}
+
+// NaN and the infinities are left for to_opa_json to write as null.
+fn canonical_numbers(value: Value) -> Value {
+    match value {
+        Value::Number(ref n) if n.as_f64().map_or(false, |f| !f.is_finite()) => value,
+        Value::Number(n) => Value::Number(Number::from(canonical_number(&n))),
+        Value::Array(v) => Value::Array(v.into_iter().map(canonical_numbers).collect()),
+        Value::Object(m) => Value::Object(
+            m.into_iter()
+                .map(|(k, v)| (k, canonical_numbers(v)))
+                .collect(),
+        ),
+        Value::Set(s) => Value::Set(s.into_iter().map(canonical_numbers).collect()),
+        v => v,
+    }
+}
+
+// Number refs keep the text they were parsed from, so "1.50" is trimmed to
+// "1.5" and "2.0" to "2". Floats are already printed in their shortest form.
+fn canonical_number(n: &Number) -> String {
+    let s = n.to_string();
+    let (mantissa, exponent) = match s.find(|c| c == 'e' || c == 'E') {
+        Some(i) => s.split_at(i),
+        None => (s.as_str(), ""),
+    };
+    if !mantissa.contains('.') {
+        return s;
+    }
+    let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+    format!("{}{}", mantissa, exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count() {
+        let out = count("cheese".into(), "e".into()).unwrap();
+        assert_eq!(Value::from(3), out);
+
+        // non-overlapping
+        let out = count("aaaa".into(), "aa".into()).unwrap();
+        assert_eq!(Value::from(2), out);
+
+        let out = count("ababab".into(), "aba".into()).unwrap();
+        assert_eq!(Value::from(1), out);
+
+        // empty substring counts runes + 1
+        let out = count("five".into(), "".into()).unwrap();
+        assert_eq!(Value::from(5), out);
+
+        let out = count("héllo".into(), "".into()).unwrap();
+        assert_eq!(Value::from(6), out);
+
+        let out = count(Value::from(1), "".into());
+        assert!(out.is_err());
+    }
+
+    #[test]
+    fn test_split_n() {
+        let out = split("a,b,c".into(), ",".into()).unwrap();
+        assert_eq!(Value::from(vec!["a", "b", "c"]), out);
+
+        let out = split_n("a,b,c".into(), ",".into(), 2.into()).unwrap();
+        assert_eq!(Value::from(vec!["a", "b,c"]), out);
+
+        let out = split_n("a,b,c".into(), ",".into(), 0.into()).unwrap();
+        assert_eq!(Value::from(Vec::<&str>::new()), out);
+
+        let out = split_n("a,b,c".into(), ",".into(), Value::from(-1)).unwrap();
+        assert_eq!(Value::from(vec!["a", "b", "c"]), out);
+
+        let out = split_n("héllo".into(), "".into(), 3.into()).unwrap();
+        assert_eq!(Value::from(vec!["h", "é", "llo"]), out);
+
+        let out = split_n("abc".into(), "".into(), Value::from(-1)).unwrap();
+        assert_eq!(Value::from(vec!["a", "b", "c"]), out);
+    }
+
+    #[test]
+    fn test_trim() {
+        let out = trim("  hello  ".into(), " ".into()).unwrap();
+        assert_eq!(Value::from("hello"), out);
+
+        let out = trim("xyhelloyx".into(), "xy".into()).unwrap();
+        assert_eq!(Value::from("hello"), out);
+
+        let out = trim_left("xyhelloyx".into(), "xy".into()).unwrap();
+        assert_eq!(Value::from("helloyx"), out);
+
+        let out = trim_right("xyhelloyx".into(), "xy".into()).unwrap();
+        assert_eq!(Value::from("xyhello"), out);
+
+        let out = trim("hello".into(), "".into()).unwrap();
+        assert_eq!(Value::from("hello"), out);
+
+        let out = trim(Value::from(1), "".into());
+        assert!(out.is_err());
+    }
+
+    #[test]
+    fn test_trim_multibyte() {
+        // "é", "è" and "ê" share their leading utf-8 byte, so a byte-based
+        // cutset would also strip half of the "ê" and leave invalid utf-8.
+        let out = trim("éèêtextêèé".into(), "éè".into()).unwrap();
+        assert_eq!(Value::from("êtextê"), out);
+
+        let out = trim_left("日本語日".into(), "日本".into()).unwrap();
+        assert_eq!(Value::from("語日"), out);
+
+        let out = trim_right("日本語日".into(), "日".into()).unwrap();
+        assert_eq!(Value::from("日本語"), out);
+
+        let out = trim("🦀a🦀".into(), "🦀".into()).unwrap();
+        assert_eq!(Value::from("a"), out);
+    }
+
+    #[test]
+    fn test_format_int() {
+        let format = |n: Value, base: i64| format_int(n, base.into()).unwrap();
+        assert_eq!(Value::from("1010"), format(10.into(), 2));
+        assert_eq!(Value::from("-17"), format((-15).into(), 8));
+        assert_eq!(Value::from("ff"), format(255.into(), 16));
+        assert_eq!(Value::from("0"), format(0.into(), 16));
+        assert_eq!(Value::from("3"), format(3.9.into(), 10));
+        assert_eq!(Value::from("-3"), format((-3.1).into(), 10));
+        assert_eq!(Value::from("10"), format(2.5.into(), 2));
+        assert_eq!(
+            Value::from("-8000000000000000"),
+            format(i64::min_value().into(), 16)
+        );
+
+        let big = Value::Number(Number::from("18446744073709551616".to_string()));
+        assert_eq!(Value::from("10000000000000000"), format(big, 16));
+    }
+
+    #[test]
+    fn test_format_int_invalid() {
+        for base in &[0, 1, 3, 36, -2] {
+            let err = format_int(10.into(), (*base).into()).unwrap_err();
+            assert!(matches!(err, Error::InvalidType(_, _)));
+        }
+        assert!(format_int(10.into(), 2.5.into()).is_err());
+        assert!(format_int("10".into(), 10.into()).is_err());
+    }
+
+    #[test]
+    fn test_to_string() {
+        let render = |v: Value| to_string(v).unwrap().try_into_string().unwrap();
+        assert_eq!("null", render(Value::Null));
+        assert_eq!("true", render(true.into()));
+        assert_eq!("false", render(false.into()));
+        assert_eq!("hello", render("hello".into()));
+        assert_eq!(
+            "[1,\"a\"]",
+            render(Value::Array(vec![1.into(), "a".into()]))
+        );
+
+        let mut object = crate::value::Map::new();
+        object.insert("a".to_string(), Value::from(1));
+        assert_eq!("{\"a\":1}", render(Value::Object(object.clone())));
+
+        let set = Value::from_set_iter(vec![Value::from("b"), Value::from("a")]);
+        assert_eq!("[\"a\",\"b\"]", render(set));
+
+        // Numbers nested in arrays and objects are trimmed too
+        let n = |s: &str| Value::Number(s.parse::<Number>().unwrap());
+        object.insert("b".to_string(), Value::Array(vec![n("1.50"), n("2.0")]));
+        assert_eq!("{\"a\":1,\"b\":[1.5,2]}", render(Value::Object(object)));
+    }
+
+    #[test]
+    fn test_to_string_numbers() {
+        let render = |v: Value| to_string(v).unwrap().try_into_string().unwrap();
+        assert_eq!("42", render(42.into()));
+        assert_eq!("-7", render((-7).into()));
+        assert_eq!("1.5", render(1.5.into()));
+        assert_eq!("2", render(2.0.into()));
+        assert_eq!("0.1", render(0.1.into()));
+
+        let n = |s: &str| Value::Number(s.parse::<Number>().unwrap());
+        assert_eq!("1.5", render(n("1.50")));
+        assert_eq!("2", render(n("2.000")));
+        assert_eq!("100", render(n("100")));
+        assert_eq!("1.5e10", render(n("1.500e10")));
+        assert_eq!("12345678901234567890", render(n("12345678901234567890")));
+    }
+}
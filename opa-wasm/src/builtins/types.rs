@@ -28,3 +28,52 @@ pub fn type_name(val: Value) -> Result<Value, Error> {
     };
     Ok(v)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{self, Number};
+
+    fn values() -> Vec<Value> {
+        vec![
+            Value::Null,
+            Value::Bool(true),
+            Value::Number(1.into()),
+            Value::Number(1.5.into()),
+            Value::Number(Number::from("12345678901234567890".to_string())),
+            Value::String("s".to_string()),
+            Value::Array(vec![]),
+            Value::Object(value::Map::new()),
+            Value::Set(value::Set::new()),
+        ]
+    }
+
+    fn check(func: fn(Value) -> Result<Value, Error>, expected: &[bool]) {
+        for (val, expected) in values().into_iter().zip(expected) {
+            let out = func(val.clone()).unwrap();
+            assert_eq!(Value::Bool(*expected), out, "{:?}", val);
+        }
+    }
+
+    #[test]
+    fn test_is_funcs() {
+        let f = false;
+        let t = true;
+        check(is_null, &[t, f, f, f, f, f, f, f, f]);
+        check(is_boolean, &[f, t, f, f, f, f, f, f, f]);
+        check(is_number, &[f, f, t, t, t, f, f, f, f]);
+        check(is_string, &[f, f, f, f, f, t, f, f, f]);
+        check(is_array, &[f, f, f, f, f, f, t, f, f]);
+        check(is_object, &[f, f, f, f, f, f, f, t, f]);
+        check(is_set, &[f, f, f, f, f, f, f, f, t]);
+    }
+
+    #[test]
+    fn test_is_number_ref() {
+        let out = is_number(Value::Number(Number::from("1.5".to_string()))).unwrap();
+        assert_eq!(Value::Bool(true), out);
+
+        let out = is_number(Value::Number(Number::from("10".to_string()))).unwrap();
+        assert_eq!(Value::Bool(true), out);
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::runtime::Module;
+use crate::{Error, Policy, Value};
+
+struct Cached {
+    // Holding on to the module keeps its address, which is the cache key,
+    // from being reused by another module.
+    _module: Arc<Module>,
+    data: Value,
+    policy: Policy,
+}
+
+thread_local! {
+    static POLICIES: RefCell<HashMap<usize, Cached>> = RefCell::new(HashMap::new());
+    // Policies created, for the tests to check instances are reused
+    #[cfg(test)]
+    static CREATED: Cell<usize> = Cell::new(0);
+}
+
+fn module_id(module: &Arc<Module>) -> usize {
+    &**module as *const Module as usize
+}
+
+/// Evaluates a module against `data` and `input` using a policy that is kept
+/// warm for the current thread.
+///
+/// Policies are keyed by the module, so callers should share one
+/// `Arc<Module>` rather than loading the wasm again. The data is compared on
+/// every call and reloaded when it changes.
+pub fn eval_cached<T: Serialize>(
+    module: &Arc<Module>,
+    data: &Value,
+    input: &T,
+) -> Result<Value, Error> {
+    POLICIES.with(|policies| {
+        let mut policies = policies.borrow_mut();
+        let id = module_id(module);
+
+        if let Some(cached) = policies.get_mut(&id) {
+            if cached.data != *data {
+                if let Err(e) = cached.policy.set_data(data) {
+                    policies.remove(&id);
+                    return Err(e);
+                }
+                cached.data = data.clone();
+            }
+        } else {
+            let mut policy = Policy::from_module(module.clone())?;
+            policy.set_data(data)?;
+            #[cfg(test)]
+            CREATED.with(|created| created.set(created.get() + 1));
+            let cached = Cached {
+                _module: module.clone(),
+                data: data.clone(),
+                policy,
+            };
+            policies.insert(id, cached);
+        }
+
+        let cached = policies.get_mut(&id).expect("cached policy");
+        cached.policy.evaluate(input)
+    })
+}
+
+/// Drops the policies cached by `eval_cached` on the current thread.
+pub fn clear_cached() {
+    POLICIES.with(|policies| policies.borrow_mut().clear());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::value::Map;
+
+    use super::*;
+
+    fn created() -> usize {
+        CREATED.with(|created| created.get())
+    }
+
+    #[test]
+    fn test_eval_cached_reuses_instance() {
+        let bytes = fs::read("tests/empty.wasm").unwrap();
+        let module = Arc::new(Module::from_bytes(bytes).unwrap());
+        let data = Value::Object(Map::new());
+
+        let before = created();
+        for _ in 0..3 {
+            eval_cached(&module, &data, &()).unwrap();
+        }
+        assert_eq!(before + 1, created());
+
+        // New data reloads the existing instance
+        let mut changed = Map::new();
+        changed.insert("a".to_string(), Value::Bool(true));
+        eval_cached(&module, &Value::Object(changed), &()).unwrap();
+        assert_eq!(before + 1, created());
+
+        // A different module gets its own instance
+        let bytes = fs::read("tests/empty.wasm").unwrap();
+        let other = Arc::new(Module::from_bytes(bytes).unwrap());
+        eval_cached(&other, &data, &()).unwrap();
+        assert_eq!(before + 2, created());
+
+        clear_cached();
+        eval_cached(&module, &data, &()).unwrap();
+        assert_eq!(before + 3, created());
+    }
+}
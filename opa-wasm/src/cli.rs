@@ -0,0 +1,46 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use crate::{Error, Policy, Value};
+
+fn read_json(path: Option<&Path>) -> Result<Value, Error> {
+    let contents = match path {
+        Some(path) => fs::read_to_string(path).map_err(Error::FileRead)?,
+        None => {
+            let mut contents = String::new();
+            io::stdin()
+                .read_to_string(&mut contents)
+                .map_err(Error::FileRead)?;
+            contents
+        }
+    };
+    serde_json::from_str(&contents).map_err(Error::Json)
+}
+
+// Evaluates a compiled policy and writes the decision as JSON to `out`.
+// The input is read from stdin when no path is given.
+pub fn run<B, W>(
+    wasm: B,
+    data: Option<&Path>,
+    input: Option<&Path>,
+    out: &mut W,
+) -> Result<(), Error>
+where
+    B: AsRef<[u8]>,
+    W: Write,
+{
+    let mut policy = Policy::from_wasm(wasm)?;
+    if let Some(path) = data {
+        let data = read_json(Some(path))?;
+        policy.set_data(&data)?;
+    }
+
+    let input = read_json(input)?;
+    let result = policy.evaluate(&input)?;
+
+    let json = serde_json::Value::from(result);
+    serde_json::to_writer(&mut *out, &json).map_err(Error::Json)?;
+    writeln!(out).map_err(Error::Output)?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use serde::Serialize;
+
+use crate::runtime::{Instance, Memory, Module};
+use crate::value::Map;
+use crate::{opa_serde, Error, Value, ValueAddr};
+
+/// A data document serialized once against a module, which can then be
+/// attached to any number of policies created from that module with
+/// `Policy::attach_data`.
+///
+/// The serialized document contains absolute addresses, so it is only valid
+/// at the heap offset it was built at. Every policy created from the same
+/// module starts its data at the same offset, so the bytes are copied as-is.
+#[derive(Clone, Debug)]
+pub struct DataHandle {
+    start: ValueAddr,
+    data_addr: ValueAddr,
+    bytes: Vec<u8>,
+}
+
+impl DataHandle {
+    pub fn new<T: Serialize>(module: &Module, data: &T) -> Result<Self, Error> {
+        let memory = Memory::from_module(module);
+        let instance = Instance::new(module, memory)?;
+
+        // Mirror the heap layout of a new policy
+        let initial = Value::Object(Map::new());
+        opa_serde::to_instance(&instance, &initial)?;
+
+        let start = instance.functions().heap_ptr_get()?;
+        let data_addr = opa_serde::to_instance(&instance, data)?;
+        let end = instance.functions().heap_ptr_get()?;
+        let bytes = instance
+            .memory()
+            .get_bytes(start, (end.0 - start.0) as usize)?;
+
+        let handle = DataHandle {
+            start,
+            data_addr,
+            bytes,
+        };
+        Ok(handle)
+    }
+
+    pub(crate) fn start(&self) -> ValueAddr {
+        self.start
+    }
+
+    pub(crate) fn data_addr(&self) -> ValueAddr {
+        self.data_addr
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
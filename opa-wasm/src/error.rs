@@ -6,7 +6,7 @@ use thiserror::Error;
 #[cfg(target_arch = "x86_64")]
 use wasmtime::Trap;
 
-use crate::{opa_serde, Value};
+use crate::{opa_serde, Value, ValueAddr};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -20,6 +20,8 @@ pub enum Error {
     Wasmi(#[source] wasmi::Error),
     #[error("Expected exported function {0}")]
     MissingExport(&'static str),
+    #[error("Unknown import required: {0}")]
+    UnknownImport(String),
     #[cfg(target_arch = "x86_64")]
     #[error("A wasm function call trapped.")]
     Trap(
@@ -33,6 +35,35 @@ pub enum Error {
     FileOpen(#[source] io::Error),
     #[error("Failed to read file.")]
     FileRead(#[source] io::Error),
+    #[cfg(feature = "gzip")]
+    #[error("Failed to decompress gzip module.")]
+    Decompress(#[source] io::Error),
+    #[error("Failed to read input.")]
+    Input(#[source] io::Error),
+    #[error("Failed to write output.")]
+    Output(#[source] io::Error),
+    #[error("Failed to parse json.")]
+    Json(#[source] serde_json::Error),
+    #[error("Failed to parse json in instance: {0}")]
+    JsonParse(String),
+    #[cfg(feature = "json5")]
+    #[error("Failed to parse json5.")]
+    Json5(#[source] json5::Error),
+    #[cfg(feature = "parallel")]
+    #[error("Failed to start thread pool.")]
+    ThreadPool(#[source] rayon::ThreadPoolBuildError),
+    #[cfg(feature = "yaml")]
+    #[error("Failed to parse yaml.")]
+    Yaml(#[source] serde_yaml::Error),
+    #[cfg(feature = "toml")]
+    #[error("Failed to parse toml.")]
+    Toml(#[source] toml::de::Error),
+    #[cfg(feature = "msgpack")]
+    #[error("Failed to decode msgpack.")]
+    MsgpackDecode(#[source] rmp_serde::decode::Error),
+    #[cfg(feature = "msgpack")]
+    #[error("Failed to encode msgpack.")]
+    MsgpackEncode(#[source] rmp_serde::encode::Error),
     #[error("Failed to call opa compiler.")]
     OpaCommand(#[source] io::Error),
     #[error("Failed to compile rego file: {0}")]
@@ -41,6 +72,8 @@ pub enum Error {
     DeserializeValue(String),
     #[error("Failed to serialize: {0}")]
     SerializeValue(String),
+    #[error("Invalid number: {0}")]
+    InvalidNumber(String),
     #[error("Invalid type in builtin function: expected {0}, got {1:?}")]
     InvalidType(&'static str, Value),
     #[error("Invalid type conversion in builtin function: expected {0}")]
@@ -49,20 +82,75 @@ pub enum Error {
     UnknownBuiltin(String),
     #[error("Unknown builtin id: {0}")]
     UnknownBuiltinId(i32),
+    #[error("Builtin function {0} is undefined for its arguments.")]
+    BuiltinUndefined(&'static str),
+    #[error("Builtin function failed: {0}")]
+    BuiltinFailed(String),
+    #[error("Builtin function {0} is disabled in deterministic mode.")]
+    Nondeterministic(String),
+    #[error("Unknown entrypoint: {0}")]
+    UnknownEntrypoint(String),
+    #[error("Entrypoint {0:?} is required to be defined, but was undefined.")]
+    Undefined(String),
     #[error("Unknown timezone: {0}")]
     UnknownTimezone(String),
     #[error("Failed to parse datetime.")]
     ParseDatetime(#[source] chrono::ParseError),
     #[error("Invalid ip network.")]
     InvalidIpNetwork(#[source] ipnetwork::IpNetworkError),
+    #[cfg(feature = "dns")]
+    #[error("Failed to lookup host {0}.")]
+    LookupHost(String, #[source] io::Error),
     #[error("Invalid regex.")]
     InvalidRegex(#[source] regex::Error),
+    #[error("Unsupported regex construct: {0}")]
+    UnsupportedRegex(String),
     #[error("Invalid function return. Expected {0}")]
     InvalidResult(&'static str),
+    #[error("Result is nested deeper than the maximum depth of {0}.")]
+    TooDeep(usize),
     #[error("Failed to serialize value to instance.")]
     InstanceSerde(#[source] opa_serde::Error),
+    #[error("Failed while serializing input.")]
+    SerializeInput(#[source] opa_serde::Error),
+    #[error("Failed while deserializing result.")]
+    DeserializeResult(#[source] opa_serde::Error),
+    #[error("Malformed result: {0}. The module may have been built for a different ABI.")]
+    MalformedResult(String),
     #[error("Invalid buffer length when casting to struct. Expected {0}, got {1}.")]
     NotEnoughData(usize, usize),
+    #[error("Memory access out of bounds at {0}.")]
+    OutOfBounds(ValueAddr),
+    #[error(
+        "Memory limit exceeded. Requested {requested_pages} pages, the maximum is {max_pages}."
+    )]
+    MemoryLimitExceeded {
+        requested_pages: usize,
+        max_pages: usize,
+    },
+    #[error(
+        "The module's memory import allows at most {max_pages} pages, fewer than the {initial_pages} pages it needs."
+    )]
+    MemoryImportTooSmall {
+        max_pages: usize,
+        initial_pages: usize,
+    },
+    #[error("Data handle was built at {0}, but the policy's data starts at {1}.")]
+    DataHandleMismatch(ValueAddr, ValueAddr),
+    #[error("Prepared input was built at {0}, but the policy's heap starts at {1}.")]
+    PreparedInputMismatch(ValueAddr, ValueAddr),
+    #[error("Evaluation was cancelled.")]
+    Cancelled,
+    #[error("Evaluation ran out of fuel.")]
+    FuelExhausted,
+    #[error("Failed to add fuel metering to the module: {0}")]
+    Metering(String),
+    #[error("Eval fuel needs a module compiled with Module::from_bytes_metered.")]
+    UnmeteredModule,
+    #[error("Invalid value pointer: {0}")]
+    InvalidPointer(String),
+    #[error("Conflicting values when merging at {0:?}.")]
+    MergeConflict(String),
 }
 
 impl de::Error for Error {
@@ -79,6 +167,40 @@ impl ser::Error for Error {
 
 impl From<opa_serde::Error> for Error {
     fn from(error: opa_serde::Error) -> Error {
-        Error::InstanceSerde(error)
+        match error {
+            opa_serde::Error::TooDeep(max_depth) => Error::TooDeep(max_depth),
+            opa_serde::Error::MalformedResult(shape) => Error::MalformedResult(shape),
+            opa_serde::Error::Alloc(source) | opa_serde::Error::MemSet(source)
+                if is_memory_limit(&*source) =>
+            {
+                *source.downcast::<Error>().expect("memory limit error")
+            }
+            error => Error::InstanceSerde(error),
+        }
     }
 }
+
+impl Error {
+    // Depth and memory limit errors keep their own variants, everything else
+    // from opa_serde is tagged with the side of the evaluation that failed.
+    pub(crate) fn from_input(error: opa_serde::Error) -> Error {
+        match Error::from(error) {
+            Error::InstanceSerde(error) => Error::SerializeInput(error),
+            error => error,
+        }
+    }
+
+    pub(crate) fn from_result(error: opa_serde::Error) -> Error {
+        match Error::from(error) {
+            Error::InstanceSerde(error) => Error::DeserializeResult(error),
+            error => error,
+        }
+    }
+}
+
+fn is_memory_limit(error: &(dyn std::error::Error + 'static)) -> bool {
+    matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::MemoryLimitExceeded { .. })
+    )
+}
@@ -0,0 +1,30 @@
+use crate::runtime::{Instance, Memory, Module};
+use crate::{opa_serde, Error, Value};
+
+const EMPTY_WASM: &[u8] = include_bytes!("../tests/empty.wasm");
+
+// Serializes `value` into a fresh instance, overwrites bytes in the region it
+// was written to and deserializes it again. Offsets wrap around the region.
+// A corrupted value may still deserialize successfully, but it must never
+// panic or read outside of memory.
+pub fn corrupt_roundtrip(value: &Value, corruptions: &[(u32, u8)]) -> Result<Value, Error> {
+    let module = Module::from_bytes(EMPTY_WASM)?;
+    let memory = Memory::from_module(&module);
+    let instance = Instance::new(&module, memory)?;
+
+    let start = instance.functions().heap_ptr_get()?;
+    let addr = opa_serde::to_instance(&instance, value)?;
+    let end = instance.functions().heap_ptr_get()?;
+
+    let len = i32::from(end) - i32::from(start);
+    if len > 0 {
+        for (offset, byte) in corruptions {
+            let offset = (*offset as usize) % len as usize;
+            let pos = start.checked_add(offset).ok_or(Error::OutOfBounds(start))?;
+            instance.memory().set(pos, &&[*byte][..])?;
+        }
+    }
+
+    let value = opa_serde::from_instance(&instance, addr)?;
+    Ok(value)
+}
@@ -1,19 +1,55 @@
-use std::{fmt, ops};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
+use serde::de::{DeserializeOwned, IgnoredAny};
 use serde::Serialize;
+use tracing::{debug, error};
 
 mod builtins;
+mod cache;
+#[cfg(feature = "cli")]
+mod cli;
+mod data;
 mod error;
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzzing;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 mod opa_serde;
+#[cfg(feature = "parallel")]
+mod pool;
+mod prepared;
+#[cfg(feature = "request")]
+pub mod request;
 mod runtime;
 pub mod set;
+pub mod time;
 pub mod value;
 
-use runtime::{Instance, Memory, Module};
+use builtins::Builtins;
+use runtime::{Instance, Memory};
 use value::Map;
 
+pub use builtins::BuiltinCall;
+pub use cache::{clear_cached, eval_cached};
+#[cfg(feature = "cli")]
+pub use cli::run;
+pub use data::DataHandle;
 pub use error::Error;
+#[cfg(feature = "parallel")]
+pub use pool::PolicyPool;
+pub use prepared::PreparedInput;
+pub use runtime::Module;
+#[cfg(feature = "schemars")]
+pub use schemars;
 pub use value::Value;
+#[cfg(target_arch = "x86_64")]
+pub use wasmtime;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct ValueAddr(i32);
@@ -36,28 +72,388 @@ impl From<ValueAddr> for i32 {
     }
 }
 
-impl ops::Add<usize> for ValueAddr {
-    type Output = ValueAddr;
+impl ValueAddr {
+    pub(crate) fn checked_add(self, offset: usize) -> Option<ValueAddr> {
+        i32::try_from(offset)
+            .ok()
+            .and_then(|offset| self.0.checked_add(offset))
+            .map(ValueAddr)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MemoryStats {
+    pub heap_ptr: usize,
+    pub heap_top: usize,
+    pub base_heap_ptr: usize,
+    pub data_heap_ptr: usize,
+    pub pages: usize,
+}
+
+/// How the memory grew during the last evaluation, from
+/// `Policy::last_metrics`.
+///
+/// Frequent growth means the memory starts too small for the inputs it sees.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    /// Times the memory grew, counted once per call into the instance.
+    pub memory_grows: usize,
+    /// The most pages the memory had.
+    pub peak_pages: usize,
+}
+
+/// Memory used by a single evaluation, from `Policy::evaluate_with_metrics`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct EvalMetrics {
+    /// The heap pointer when the evaluation started, right above the data.
+    pub heap_ptr_before: usize,
+    /// The heap pointer once the result was read.
+    pub heap_ptr_after: usize,
+    pub memory: Metrics,
+}
+
+impl EvalMetrics {
+    /// Bytes allocated on the heap by the evaluation, its input included.
+    pub fn allocated(&self) -> usize {
+        self.heap_ptr_after.saturating_sub(self.heap_ptr_before)
+    }
+}
+
+/// The result of evaluating a rule that may be undefined, such as an object
+/// with `allow` and `reason` fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decision<T> {
+    result: Option<T>,
+}
+
+impl<T> Decision<T> {
+    pub fn is_defined(&self) -> bool {
+        self.result.is_some()
+    }
+
+    pub fn result(&self) -> Option<&T> {
+        self.result.as_ref()
+    }
+
+    pub fn into_result(self) -> Option<T> {
+        self.result
+    }
+}
 
-    fn add(self, rhs: usize) -> Self {
-        ValueAddr(self.0 + rhs as i32)
+// Reused buffer for the JSON encoded input, so repeated evaluations don't
+// allocate on the rust side.
+#[derive(Default)]
+struct EvalScratch {
+    input: Vec<u8>,
+}
+
+impl EvalScratch {
+    // Sets and number refs serialize as structs named by private tokens,
+    // which serde_json would write as objects. When there are any, the JSON
+    // is read back into a Value and written with Value::to_opa_json, which
+    // renders sets as arrays and number refs as they are.
+    fn json<T: Serialize>(&mut self, value: &T) -> Result<&str, Error> {
+        self.input.clear();
+        serde_json::to_writer(&mut self.input, value).map_err(Error::Json)?;
+        let json = std::str::from_utf8(&self.input)
+            .map_err(|_| Error::JsonParse("invalid utf8".to_string()))?;
+        if json.contains(set::TOKEN) || json.contains(value::number::TOKEN) {
+            let value: Value = serde_json::from_str(json).map_err(Error::Json)?;
+            self.input.clear();
+            self.input.extend_from_slice(value.to_opa_json().as_bytes());
+        }
+        std::str::from_utf8(&self.input).map_err(|_| Error::JsonParse("invalid utf8".to_string()))
+    }
+}
+
+// Results of evaluate_cached, keyed by the policy version, whether it was
+// evaluated in deterministic mode and the JSON encoded input. Like the regex
+// cache, it's cleared when full instead of tracking which entries were used
+// recently.
+#[derive(Default)]
+struct DecisionCache {
+    capacity: usize,
+    decisions: HashMap<(u64, bool, String), Value>,
+}
+
+impl DecisionCache {
+    fn get(&self, key: &(u64, bool, String)) -> Option<&Value> {
+        self.decisions.get(key)
+    }
+
+    fn insert(&mut self, key: (u64, bool, String), value: Value) {
+        if self.decisions.len() >= self.capacity {
+            self.decisions.clear();
+        }
+        self.decisions.insert(key, value);
+    }
+
+    fn clear(&mut self) {
+        self.decisions.clear();
+    }
+}
+
+/// The outcome of an evaluation, telling an undefined rule apart from one
+/// that was undefined because a builtin failed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvalOutcome {
+    Value(Value),
+    Undefined,
+    /// The first builtin failure, such as
+    /// `upper: Invalid type in builtin function: ...`.
+    BuiltinError(String),
+}
+
+/// Where a result came from: the text and location of the expression that
+/// produced it, as found in OPA's result envelope
+/// (`{"expressions": [{"value": ..., "text": ..., "location": ...}]}`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Provenance {
+    /// The expression, e.g. `data.example.allow`.
+    pub text: String,
+    /// The row and column of the expression, if known.
+    pub location: Option<(usize, usize)>,
+}
+
+impl Provenance {
+    /// Splits one result into its value and provenance. Results without an
+    /// envelope, like those of compiled wasm, have no provenance and are
+    /// returned with a single entry binding such as `{"x": 1}` unwrapped.
+    pub fn from_result(result: Value) -> (Value, Option<Provenance>) {
+        if let Some(expression) = result.get("expressions").and_then(|e| e.get(0)) {
+            let text = expression.get("text").and_then(Value::as_str);
+            if let (Some(value), Some(text)) = (expression.get("value"), text) {
+                let position = |key| {
+                    let n = expression.get("location")?.get(key)?.as_i64()?;
+                    usize::try_from(n).ok()
+                };
+                let provenance = Provenance {
+                    text: text.to_string(),
+                    location: position("row").and_then(|row| Some((row, position("col")?))),
+                };
+                return (value.clone(), Some(provenance));
+            }
+        }
+
+        match result {
+            Value::Object(ref binding) if binding.len() == 1 => {
+                let value = binding.values().next().cloned().unwrap_or_default();
+                (value, None)
+            }
+            result => (result, None),
+        }
+    }
+}
+
+/// A named entrypoint of a module, looked up once with `Policy::entrypoint`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entrypoint {
+    name: String,
+    id: i32,
+}
+
+impl Entrypoint {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+/// How `Policy::evaluate_ndjson` handles a line that isn't valid JSON.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MalformedLine {
+    /// Write `{"error": "..."}` in place of the result and continue.
+    Skip,
+    /// Stop and return the parse error.
+    Abort,
+}
+
+/// A function run on every input, as a Value, before it's written to the
+/// instance, e.g. to inject a request timestamp. Inputs read as raw JSON, by
+/// `Policy::evaluate_ndjson`, skip it, and `Policy::evaluate_cached` doesn't
+/// cache decisions when there's a transform. Prepared inputs are transformed
+/// once, by `Policy::prepare_input`.
+#[derive(Clone)]
+pub struct InputTransform(Arc<dyn Fn(&mut Value) + Send + Sync>);
+
+impl InputTransform {
+    pub fn new<F>(transform: F) -> Self
+    where
+        F: Fn(&mut Value) + Send + Sync + 'static,
+    {
+        InputTransform(Arc::new(transform))
+    }
+
+    fn apply(&self, value: &mut Value) {
+        (self.0)(value)
+    }
+}
+
+impl fmt::Debug for InputTransform {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "InputTransform")
+    }
+}
+
+/// Options applied when creating a policy. The defaults match a policy
+/// created with `Policy::from_module`. `Policy::reload` and `PolicyPool`
+/// create their policies with the same config.
+#[derive(Clone, Debug)]
+pub struct PolicyConfig {
+    /// The data document to load, instead of an empty object.
+    pub data: Option<Value>,
+    /// Results nested deeper than this fail with `Error::TooDeep`.
+    pub max_result_depth: usize,
+    /// Caps memory growth, in 64KiB pages. Writing past the cap fails with
+    /// `Error::MemoryLimitExceeded`. Memory always starts with 5 pages.
+    pub max_memory_pages: Option<usize>,
+    /// How many results `evaluate_cached` keeps. 0 disables the cache.
+    pub decision_cache_size: usize,
+    /// The entrypoint that `evaluate`, and every other method without an
+    /// entrypoint argument, runs instead of the one with id 0. Unknown names
+    /// fail with `Error::UnknownEntrypoint`.
+    pub entrypoint: Option<String>,
+    /// How many wasm instructions each evaluation may run before failing with
+    /// `Error::FuelExhausted`. Needs a module compiled with
+    /// `Module::from_bytes_metered`.
+    pub eval_fuel: Option<u64>,
+    /// Lets typed results read integers from integral floats, e.g. an i64
+    /// field from OPA's 3.0, rather than failing. Results read as Value keep
+    /// their floats.
+    pub coerce_integral_floats: bool,
+    /// Decodes each string in a result once, however many times OPA
+    /// references it, which speeds up reading large results built from
+    /// repetitive data. Every Value still owns a copy of its string.
+    pub cache_strings: bool,
+    /// Fails evaluation with `Error::BuiltinFailed`, carrying the first
+    /// failure, instead of leaving the failed call undefined as OPA does.
+    pub strict_builtin_errors: bool,
+    /// Fails evaluation with `Error::UnknownBuiltinId` when the module calls
+    /// a builtin id missing from its builtin table, instead of logging it and
+    /// leaving the call undefined, so an ABI mismatch isn't mistaken for an
+    /// undefined rule.
+    pub strict_builtin_ids: bool,
+    /// Checked by batch evaluations, `Policy::evaluate_ndjson` and
+    /// `PolicyPool::evaluate_par`, before each item, which stop with
+    /// `Error::Cancelled` once it is set. Lines `evaluate_ndjson` already
+    /// wrote are kept.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Runs on every input before it's written to the instance.
+    pub input_transform: Option<InputTransform>,
+    /// Entrypoints, by name, that fail with `Error::Undefined` instead of
+    /// returning an empty result when they are undefined. For decisions that
+    /// must always be made, where undefined is a bug in the policy. The only
+    /// entrypoint of a module compiled from a single query is named "".
+    pub require_defined: Vec<String>,
+    /// Logs, at debug level, where each evaluation's input, context and
+    /// result are written in the instance's memory and how many bytes each
+    /// took, to line them up with a memory dump.
+    pub trace_addresses: bool,
+    /// Records every builtin call, with its arguments and result, for
+    /// `Policy::take_builtin_trace`.
+    pub trace_builtins: bool,
+    /// Makes evaluation depend only on the input and data, e.g. to replay
+    /// decisions from an audit log. time.now_ns returns the Unix epoch,
+    /// rand.intn draws from a generator with a fixed seed, and builtins with
+    /// no stable result, like net.lookup_ip_addr, fail with
+    /// `Error::Nondeterministic`, leaving their expression undefined.
+    pub deterministic: bool,
+}
+
+impl PolicyConfig {
+    // Compiles a module this config can be used with, metered when there's
+    // eval fuel.
+    pub(crate) fn compile(&self, bytes: &[u8]) -> Result<Module, Error> {
+        if self.eval_fuel.is_some() {
+            Module::from_bytes_metered(bytes)
+        } else {
+            Module::from_bytes(bytes)
+        }
+    }
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        PolicyConfig {
+            data: None,
+            max_result_depth: opa_serde::DEFAULT_MAX_DEPTH,
+            max_memory_pages: None,
+            decision_cache_size: 0,
+            entrypoint: None,
+            eval_fuel: None,
+            coerce_integral_floats: false,
+            cache_strings: false,
+            strict_builtin_errors: false,
+            strict_builtin_ids: false,
+            cancel: None,
+            input_transform: None,
+            require_defined: Vec::new(),
+            trace_addresses: false,
+            trace_builtins: false,
+            deterministic: false,
+        }
     }
 }
 
 #[allow(dead_code)]
 pub struct Policy {
+    module: Arc<Module>,
     instance: Instance,
     data_addr: ValueAddr,
     base_heap_ptr: ValueAddr,
     base_heap_top: ValueAddr,
     data_heap_ptr: ValueAddr,
     data_heap_top: ValueAddr,
+    config: PolicyConfig,
+    base_input: Option<Value>,
+    base_input_addr: Option<ValueAddr>,
+    scratch: EvalScratch,
+    entrypoints: Map<String, i32>,
+    entrypoint: Entrypoint,
+    version: u64,
+    decisions: DecisionCache,
+    required: Map<i32, String>,
+    #[cfg(feature = "metrics")]
+    recorder: Option<Arc<dyn metrics::Recorder>>,
 }
 
 impl Policy {
     pub fn from_wasm<B: AsRef<[u8]>>(bytes: B) -> Result<Self, Error> {
         let module = Module::from_bytes(bytes)?;
-        let memory = Memory::from_module(&module);
+        Self::from_module(Arc::new(module))
+    }
+
+    // Loads a gzip compressed module. Plain wasm is detected by the missing
+    // gzip magic bytes and loaded as is.
+    #[cfg(feature = "gzip")]
+    pub fn from_wasm_gz<B: AsRef<[u8]>>(bytes: B) -> Result<Self, Error> {
+        use std::io::Read;
+
+        let bytes = bytes.as_ref();
+        if !bytes.starts_with(&[0x1f, 0x8b]) {
+            return Self::from_wasm(bytes);
+        }
+
+        let mut wasm = Vec::new();
+        flate2::read::GzDecoder::new(bytes)
+            .read_to_end(&mut wasm)
+            .map_err(Error::Decompress)?;
+        Self::from_wasm(wasm)
+    }
+
+    pub fn from_module(module: Arc<Module>) -> Result<Self, Error> {
+        Self::with_config(module, PolicyConfig::default())
+    }
+
+    pub fn with_config(module: Arc<Module>, mut config: PolicyConfig) -> Result<Self, Error> {
+        if config.eval_fuel.is_some() && !module.is_metered() {
+            return Err(Error::UnmeteredModule);
+        }
+
+        let memory = Memory::with_max_pages(&module, config.max_memory_pages);
         let instance = Instance::new(&module, memory)?;
 
         // Load initial data
@@ -69,54 +465,1138 @@ impl Policy {
         let data_heap_ptr = base_heap_ptr;
         let data_heap_top = base_heap_top;
 
-        let policy = Policy {
+        // Modules compiled from a single query, like those from OPA 0.18,
+        // don't list their entrypoints.
+        let entrypoints = match instance.functions().entrypoints()? {
+            Some(addr) => opa_serde::from_instance(&instance, addr)?,
+            None => Map::new(),
+        };
+        let entrypoint = match config.entrypoint {
+            Some(ref name) => find_entrypoint(&entrypoints, name)?,
+            None => {
+                let name = entrypoints
+                    .iter()
+                    .find(|(_, id)| **id == 0)
+                    .map(|(name, _)| name.clone())
+                    .unwrap_or_default();
+                Entrypoint { name, id: 0 }
+            }
+        };
+        let required = config
+            .require_defined
+            .iter()
+            .map(|name| {
+                let entrypoint = find_entrypoint(&entrypoints, name)?;
+                Ok((entrypoint.id, entrypoint.name))
+            })
+            .collect::<Result<Map<i32, String>, Error>>()?;
+
+        instance.builtins().set_deterministic(config.deterministic);
+        instance.builtins().set_tracing(config.trace_builtins);
+
+        let data = config.data.take();
+        let decision_cache_size = config.decision_cache_size;
+        let mut policy = Policy {
+            module,
             instance,
             data_addr,
             base_heap_ptr,
             base_heap_top,
             data_heap_ptr,
             data_heap_top,
+            config,
+            base_input: None,
+            base_input_addr: None,
+            scratch: EvalScratch::default(),
+            entrypoints,
+            entrypoint,
+            version: 0,
+            decisions: DecisionCache {
+                capacity: decision_cache_size,
+                ..DecisionCache::default()
+            },
+            required,
+            #[cfg(feature = "metrics")]
+            recorder: None,
         };
 
+        if let Some(data) = data {
+            policy.set_data(&data)?;
+        }
+
         Ok(policy)
     }
 
+    // Swaps in a new module, keeping the current data and settings. The
+    // version is bumped so decisions cached for the old module are not
+    // returned.
+    pub fn reload_wasm<B: AsRef<[u8]>>(&mut self, bytes: B) -> Result<(), Error> {
+        let data = self.data()?;
+        self.reload(bytes.as_ref(), &data)
+    }
+
+    // Swaps in a new module and data together, keeping the current config.
+    // The new instance is built and its data loaded before it replaces this
+    // one, so evaluations never see the new module with the old data or the
+    // other way around, and on error the policy is left as it was.
+    pub fn reload<T: Serialize>(&mut self, wasm: &[u8], data: &T) -> Result<(), Error> {
+        let module = self.config.compile(wasm)?;
+        let mut policy = Policy::with_config(Arc::new(module), self.config.clone())?;
+        policy.base_input = self.base_input.clone();
+        policy.set_data(data)?;
+        policy.version = self.version + 1;
+        #[cfg(feature = "metrics")]
+        {
+            policy.recorder = self.recorder.take();
+        }
+        *self = policy;
+        Ok(())
+    }
+
+    // Sets PolicyConfig::strict_builtin_errors on a policy already created.
+    pub fn set_strict_builtin_errors(&mut self, strict: bool) {
+        self.config.strict_builtin_errors = strict;
+    }
+
+    // Sets PolicyConfig::deterministic on a policy already created.
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.config.deterministic = deterministic;
+        self.instance.builtins().set_deterministic(deterministic);
+    }
+
+    // Sets PolicyConfig::strict_builtin_ids on a policy already created.
+    pub fn set_strict_builtin_ids(&mut self, strict: bool) {
+        self.config.strict_builtin_ids = strict;
+    }
+
+    // Sets PolicyConfig::cancel on a policy already created.
+    pub fn set_cancel_flag(&mut self, cancel: Arc<AtomicBool>) {
+        self.config.cancel = Some(cancel);
+    }
+
+    // Sets PolicyConfig::input_transform on a policy already created.
+    pub fn set_input_transform<F>(&mut self, transform: F)
+    where
+        F: Fn(&mut Value) + Send + Sync + 'static,
+    {
+        self.config.input_transform = Some(InputTransform::new(transform));
+    }
+
+    fn check_cancelled(&self) -> Result<(), Error> {
+        match self.config.cancel {
+            Some(ref cancel) if cancel.load(Ordering::SeqCst) => Err(Error::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    // Sets PolicyConfig::trace_addresses on a policy already created.
+    pub fn set_trace_addresses(&mut self, trace: bool) {
+        self.config.trace_addresses = trace;
+    }
+
+    // Sets PolicyConfig::trace_builtins on a policy already created.
+    pub fn set_trace_builtins(&mut self, trace: bool) {
+        self.config.trace_builtins = trace;
+        self.instance.builtins().set_tracing(trace);
+    }
+
+    // The heap pointer, when addresses are traced.
+    fn traced_heap_ptr(&self) -> Result<Option<ValueAddr>, Error> {
+        if self.config.trace_addresses {
+            self.instance.functions().heap_ptr_get().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Bytes allocated since `before`, when addresses are traced.
+    fn traced_size(&self, before: Option<ValueAddr>) -> Result<Option<i32>, Error> {
+        match before {
+            Some(before) => {
+                let after = self.instance.functions().heap_ptr_get()?;
+                Ok(Some(after.0 - before.0))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // Bumped every time a new module is installed with reload_wasm.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    // Names of the module's entrypoints, empty for modules compiled from a
+    // single query.
+    pub fn list_entrypoints(&self) -> Vec<String> {
+        self.entrypoints.keys().cloned().collect()
+    }
+
+    pub fn entrypoint(&self, name: &str) -> Result<Entrypoint, Error> {
+        let id = self
+            .entrypoints
+            .get(name)
+            .ok_or_else(|| Error::UnknownEntrypoint(name.to_string()))?;
+        let entrypoint = Entrypoint {
+            name: name.to_string(),
+            id: *id,
+        };
+        Ok(entrypoint)
+    }
+
+    // The entrypoint evaluate and the other methods without an entrypoint
+    // argument run: PolicyConfig::entrypoint, or else the one with id 0. A
+    // module compiled from a single query has only that query, and no name
+    // for it.
+    pub fn default_entrypoint(&self) -> Entrypoint {
+        self.entrypoint.clone()
+    }
+
+    // Adds the entrypoint to PolicyConfig::require_defined on a policy
+    // already created.
+    pub fn require_defined(&mut self, entrypoint: &Entrypoint) {
+        let name = entrypoint.name().to_string();
+        if !self.config.require_defined.contains(&name) {
+            self.config.require_defined.push(name.clone());
+        }
+        self.required.insert(entrypoint.id(), name);
+    }
+
+    // Reports how long each call to evaluate takes, failed calls included.
+    #[cfg(feature = "metrics")]
+    pub fn set_recorder<R: metrics::Recorder + 'static>(&mut self, recorder: R) {
+        self.recorder = Some(Arc::new(recorder));
+    }
+
     // This takes a &mut self because calling it potentially mutates the
     // memory. We could make this take &self, if we add a mutex.
+    #[cfg(feature = "metrics")]
+    pub fn evaluate<T: Serialize>(&mut self, input: &T) -> Result<Value, Error> {
+        let recorder = match self.recorder {
+            Some(ref recorder) => recorder.clone(),
+            None => return self.evaluate_untimed(input),
+        };
+        let start = std::time::Instant::now();
+        let result = self.evaluate_untimed(input);
+        recorder.record(start.elapsed());
+        result
+    }
+
+    #[cfg(not(feature = "metrics"))]
     pub fn evaluate<T: Serialize>(&mut self, input: &T) -> Result<Value, Error> {
-        // Reset the heap pointers
+        self.evaluate_untimed(input)
+    }
+
+    fn evaluate_untimed<T: Serialize>(&mut self, input: &T) -> Result<Value, Error> {
+        self.begin_eval()?;
+
+        // Load input data
+        let input_addr = self.input_to_instance(input)?;
+        self.eval_ctx(input_addr, self.data_addr)
+    }
+
+    // Like evaluate, but runs the given entrypoint instead of the default
+    // one, for modules compiled with several. Entrypoints come from
+    // entrypoint or default_entrypoint, and one from another module fails
+    // with Error::UnknownEntrypoint.
+    pub fn evaluate_entrypoint<T: Serialize>(
+        &mut self,
+        entrypoint: &Entrypoint,
+        input: &T,
+    ) -> Result<Value, Error> {
+        self.check_entrypoint(entrypoint)?;
+        self.begin_eval()?;
+        let input_addr = self.input_to_instance(input)?;
+        let result_addr = self.eval_entrypoint_addr(entrypoint.id(), input_addr, self.data_addr)?;
+        self.result(result_addr)
+    }
+
+    fn check_entrypoint(&self, entrypoint: &Entrypoint) -> Result<(), Error> {
+        let known = self.entrypoints.get(entrypoint.name()) == Some(&entrypoint.id())
+            || *entrypoint == self.default_entrypoint();
+        if !known {
+            return Err(Error::UnknownEntrypoint(entrypoint.name().to_string()));
+        }
+        Ok(())
+    }
+
+    // Like evaluate, but returns the stored result when the same input was
+    // evaluated before against the same module and data. The cache size is
+    // set with PolicyConfig::decision_cache_size. With an input transform,
+    // which can change the decision for the same input, e.g. by adding a
+    // timestamp, this is the same as evaluate.
+    pub fn evaluate_cached<T: Serialize>(&mut self, input: &T) -> Result<Value, Error> {
+        if self.decisions.capacity == 0 || self.config.input_transform.is_some() {
+            return self.evaluate(input);
+        }
+
+        let json = serde_json::to_string(input).map_err(Error::Json)?;
+        let key = (self.version, self.config.deterministic, json);
+        if let Some(value) = self.decisions.get(&key) {
+            return Ok(value.clone());
+        }
+
+        let value = self.evaluate(input)?;
+        self.decisions.insert(key, value.clone());
+        Ok(value)
+    }
+
+    // Like evaluate, but writes the input as JSON into a buffer kept on the
+    // policy and parses it inside the instance. This is a single copy into
+    // wasm memory instead of one per value, which is faster for large inputs.
+    // JSON has no sets, so sets in the input are written as arrays.
+    pub fn evaluate_buffered<T: Serialize>(&mut self, input: &T) -> Result<Value, Error> {
+        self.begin_eval()?;
+
+        let transformed = self.transform_input(input)?;
+        let json = match transformed {
+            Some(ref input) => self.scratch.json(input)?,
+            None => self.scratch.json(input)?,
+        };
+        let raw_addr = self.instance.functions().malloc(json.len())?;
+        if self.config.trace_addresses {
+            debug!(addr = %raw_addr, size = json.len(), "allocated input buffer");
+        }
+        self.instance.memory().set(raw_addr, &json)?;
+        // json borrows the scratch buffer, so this can't go through
+        // traced_heap_ptr
+        let before = if self.config.trace_addresses {
+            Some(self.instance.functions().heap_ptr_get()?)
+        } else {
+            None
+        };
+        let input_addr = self.instance.functions().json_parse(raw_addr, json)?;
+        if let Some(size) = self.traced_size(before)? {
+            debug!(addr = %input_addr, size = size, "parsed input");
+        }
+        self.eval_ctx(input_addr, self.data_addr)
+    }
+
+    // Evaluates and returns the result set as JSON. Modules built for ABI 1.2
+    // export opa_eval, which parses the input, evaluates and dumps the result
+    // in a single call. Older modules fall back to an evaluation context and
+    // json_dump. Like evaluate_buffered, sets in the input become arrays.
+    pub fn evaluate_json<T: Serialize>(&mut self, input: &T) -> Result<String, Error> {
+        self.begin_eval()?;
+
+        let transformed = self.transform_input(input)?;
+        let json = match transformed {
+            Some(ref input) => self.scratch.json(input)?,
+            None => self.scratch.json(input)?,
+        };
+        let raw_addr = self.instance.functions().malloc(json.len())?;
+        self.instance.memory().set(raw_addr, &json)?;
+        let json_len = json.len();
+        if !self.instance.functions().has_opa_eval() {
+            let input_addr = self.instance.functions().json_parse(raw_addr, json)?;
+            let result_addr = self.eval_result_addr(input_addr, self.data_addr)?;
+            let json_addr = self.instance.functions().json_dump(result_addr)?;
+            return self.instance.memory().get_cstr(json_addr);
+        }
+
+        let entrypoint = self.entrypoint.id();
+        let heap_ptr = self.instance.functions().heap_ptr_get()?;
+        self.clear_builtin_errors();
+        let json_addr = self.with_fuel(|| {
+            self.instance.functions().opa_eval(
+                entrypoint,
+                self.data_addr,
+                raw_addr,
+                json_len,
+                heap_ptr,
+            )
+        })?;
+        self.check_builtin_errors()?;
+        let json = self.instance.memory().get_cstr(json_addr)?;
+
+        // The same checks eval_entrypoint_addr makes on the result set
+        let bindings = serde_json::from_str::<Vec<HashMap<String, IgnoredAny>>>(&json)
+            .map_err(|e| Error::MalformedResult(format!("expected a set of bindings, {}", e)))?;
+        if let Some(name) = self.required.get(&entrypoint) {
+            if bindings.is_empty() {
+                return Err(Error::Undefined(name.clone()));
+            }
+        }
+        Ok(json)
+    }
+
+    // Serializes the input once, so it can be evaluated repeatedly with
+    // evaluate_prepared without running serde again. The prepared input is
+    // tied to the current data; set_data and friends invalidate it.
+    pub fn prepare_input<T: Serialize>(&mut self, input: &T) -> Result<PreparedInput, Error> {
+        self.reset_heap()?;
+        let start = self.instance.functions().heap_ptr_get()?;
+        let input_addr = self.input_to_instance(input)?;
+        let end = self.instance.functions().heap_ptr_get()?;
+        let bytes = self
+            .instance
+            .memory()
+            .get_bytes(start, (end.0 - start.0) as usize)?;
+        self.reset_heap()?;
+        Ok(PreparedInput::new(start, input_addr, bytes))
+    }
+
+    // Like evaluate, but copies an input serialized by prepare_input into the
+    // heap instead of serializing it.
+    pub fn evaluate_prepared(&mut self, prepared: &PreparedInput) -> Result<Value, Error> {
+        if prepared.start() != self.data_heap_ptr {
+            return Err(Error::PreparedInputMismatch(
+                prepared.start(),
+                self.data_heap_ptr,
+            ));
+        }
+        self.begin_eval()?;
+        // malloc grows the memory if needed
+        let addr = self.instance.functions().malloc(prepared.bytes().len())?;
+        if addr != prepared.start() {
+            return Err(Error::PreparedInputMismatch(prepared.start(), addr));
+        }
+        self.instance.memory().set(addr, &prepared.bytes())?;
+        self.eval_ctx(prepared.input_addr(), self.data_addr)
+    }
+
+    // Evaluates one JSON input per line of reader, writing one JSON result per
+    // line to writer. Blank lines are ignored. Returns the number of lines
+    // written.
+    pub fn evaluate_ndjson<R: BufRead, W: Write>(
+        &mut self,
+        mut reader: R,
+        mut writer: W,
+        malformed: MalformedLine,
+    ) -> Result<usize, Error> {
+        let mut written = 0;
+        loop {
+            self.scratch.input.clear();
+            let read = reader
+                .read_until(b'\n', &mut self.scratch.input)
+                .map_err(Error::Input)?;
+            if read == 0 {
+                break;
+            }
+
+            let line = &self.scratch.input;
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            if let Err(e) = self.check_cancelled() {
+                writer.flush().map_err(Error::Output)?;
+                return Err(e);
+            }
+
+            let result = std::str::from_utf8(line)
+                .map_err(|_| Error::JsonParse("invalid utf8".to_string()))
+                .and_then(|json| {
+                    self.begin_eval()?;
+                    let raw_addr = self.instance.functions().malloc(json.len())?;
+                    self.instance.memory().set(raw_addr, &json)?;
+                    self.instance.functions().json_parse(raw_addr, json)
+                });
+            let value = match result {
+                Ok(input_addr) => self.eval_ctx(input_addr, self.data_addr)?,
+                Err(e @ Error::JsonParse(_)) if malformed == MalformedLine::Skip => {
+                    let mut error = Map::new();
+                    error.insert("error".to_string(), Value::String(e.to_string()));
+                    Value::Object(error)
+                }
+                Err(e) => return Err(e),
+            };
+
+            let json = serde_json::Value::from(value);
+            serde_json::to_writer(&mut writer, &json).map_err(Error::Json)?;
+            writer.write_all(b"\n").map_err(Error::Output)?;
+            written += 1;
+        }
+        writer.flush().map_err(Error::Output)?;
+        Ok(written)
+    }
+
+    // Returns None when the query is undefined, otherwise the first result
+    // binding deserialized as R.
+    pub fn evaluate_opt<T, R>(&mut self, input: &T) -> Result<Option<R>, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        self.begin_eval()?;
+        let input_addr = self.input_to_instance(input)?;
+        let result_addr = self.eval_result_addr(input_addr, self.data_addr)?;
+        match opa_serde::result_binding(&self.instance, result_addr).map_err(Error::from_result)? {
+            Some(addr) => {
+                let r = self.result(addr)?;
+                Ok(Some(r))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // Collects every binding in the result set, for queries like
+    // `x = data.example.users[_]` that have more than one result.
+    pub fn evaluate_all<T: Serialize>(&mut self, input: &T) -> Result<Vec<Value>, Error> {
+        self.begin_eval()?;
+        let input_addr = self.input_to_instance(input)?;
+        let result_addr = self.eval_result_addr(input_addr, self.data_addr)?;
+        opa_serde::result_bindings(&self.instance, result_addr)
+            .map_err(Error::from_result)?
+            .into_iter()
+            .map(|addr| self.result(addr))
+            .collect()
+    }
+
+    // Evaluates with no input and returns the value at a dotted path inside
+    // the decision, for reading computed config-like values. For a module
+    // built from the query data.config, "limits.max" reads
+    // data.config.limits.max. Paths starting with "data" are full paths,
+    // resolved against the entrypoint's name. Modules that don't name their
+    // entrypoint, like those from OPA 0.18, only take relative paths, and
+    // fail with Error::UnknownEntrypoint for full ones. Fails with
+    // Error::Undefined when there's no value at the path.
+    pub fn read_data_path(&mut self, path: &str) -> Result<Value, Error> {
+        let relative = match strip_path(path, "data") {
+            Some(full) => {
+                let root = self.default_entrypoint().name().replace('/', ".");
+                if root.is_empty() {
+                    return Err(Error::UnknownEntrypoint(path.to_string()));
+                }
+                strip_path(full, &root).ok_or_else(|| Error::Undefined(path.to_string()))?
+            }
+            None => path,
+        };
+
+        let result: Option<Value> = self.evaluate_opt(&())?;
+        result
+            .as_ref()
+            .and_then(|value| value.lookup(relative))
+            .cloned()
+            .ok_or_else(|| Error::Undefined(path.to_string()))
+    }
+
+    // Evaluates and returns the address of the result set in the instance's
+    // memory, to be read with deserialize_addr. The address is only valid
+    // until the next call to evaluate, set_data or any other method that
+    // writes to the instance.
+    pub fn evaluate_addr<T: Serialize>(&mut self, input: &T) -> Result<ValueAddr, Error> {
+        self.begin_eval()?;
+        let input_addr = self.input_to_instance(input)?;
+        self.eval_result_addr(input_addr, self.data_addr)
+    }
+
+    // Deserializes the value at an address returned by evaluate_addr. The
+    // same address can be read any number of times, into different types.
+    pub fn deserialize_addr<R: DeserializeOwned>(&self, addr: ValueAddr) -> Result<R, Error> {
+        self.result(addr)
+    }
+
+    /// Returns the JSON Schema of a result type, to document the shape of a
+    /// policy's decisions.
+    ///
+    /// ```
+    /// use opa_wasm::Policy;
+    /// use schemars::JsonSchema;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize, JsonSchema)]
+    /// struct Decision {
+    ///     allow: bool,
+    ///     reasons: Vec<String>,
+    /// }
+    ///
+    /// let schema = Policy::result_schema::<Decision>().unwrap();
+    /// let properties = schema.lookup("properties").unwrap();
+    /// assert!(properties.lookup("allow").is_some());
+    /// ```
+    #[cfg(feature = "schemars")]
+    pub fn result_schema<R: schemars::JsonSchema>() -> Result<Value, Error> {
+        let schema = schemars::gen::SchemaGenerator::default().into_root_schema_for::<R>();
+        let json = serde_json::to_value(&schema).map_err(Error::Json)?;
+        serde_json::from_value(json).map_err(Error::Json)
+    }
+
+    // Like evaluate_all, but also returns each result's provenance, when the
+    // result set carries it. See Provenance::from_result.
+    pub fn evaluate_with_provenance<T: Serialize>(
+        &mut self,
+        input: &T,
+    ) -> Result<Vec<(Value, Option<Provenance>)>, Error> {
+        let results = match self.evaluate(input)? {
+            Value::Set(results) => results,
+            value => return Err(Error::InvalidType("set", value)),
+        };
+        Ok(results.into_iter().map(Provenance::from_result).collect())
+    }
+
+    pub fn evaluate_decision<T, R>(&mut self, input: &T) -> Result<Decision<R>, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let result = self.evaluate_opt(input)?;
+        Ok(Decision { result })
+    }
+
+    // Returns `default` when the query is undefined.
+    //
+    // The compiled wasm doesn't say whether a result came from a `default`
+    // rule, so a policy's own default is indistinguishable from an explicit
+    // result. This only covers rules that have no default and are undefined.
+    pub fn evaluate_or<T, R>(&mut self, input: &T, default: R) -> Result<R, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let result = self.evaluate_opt(input)?;
+        Ok(result.unwrap_or(default))
+    }
+
+    // Like evaluate_opt, but reports builtin failures. OPA treats a failed
+    // builtin call as undefined, so any failure during the evaluation is
+    // returned even if the query was otherwise defined.
+    pub fn evaluate_outcome<T: Serialize>(&mut self, input: &T) -> Result<EvalOutcome, Error> {
+        self.begin_eval()?;
+        let input_addr = self.input_to_instance(input)?;
+        let result_addr = match self.eval_result_addr(input_addr, self.data_addr) {
+            Err(Error::BuiltinFailed(error)) => return Ok(EvalOutcome::BuiltinError(error)),
+            result => result?,
+        };
+        let errors = self.instance.builtins().take_errors();
+        if let Some(error) = errors.into_iter().next() {
+            return Ok(EvalOutcome::BuiltinError(error));
+        }
+
+        match opa_serde::result_binding(&self.instance, result_addr).map_err(Error::from_result)? {
+            Some(addr) => Ok(EvalOutcome::Value(self.result(addr)?)),
+            None => Ok(EvalOutcome::Undefined),
+        }
+    }
+
+    // Checks whether the query produced any result without deserializing it.
+    pub fn is_defined<T: Serialize>(&mut self, input: &T) -> Result<bool, Error> {
+        self.begin_eval()?;
+        let input_addr = self.input_to_instance(input)?;
+        let result_addr = self.eval_result_addr(input_addr, self.data_addr)?;
+        let empty = opa_serde::is_empty_set(&self.instance, result_addr)?;
+        Ok(!empty)
+    }
+
+    // Evaluates the policy with the overlay merged into data. The merged data
+    // is written above the current data on the heap, so resetting the heap
+    // afterwards restores the original data exactly, even if evaluation fails.
+    pub fn evaluate_with_overlay<T: Serialize>(
+        &mut self,
+        input: &T,
+        overlay: &Value,
+    ) -> Result<Value, Error> {
+        self.begin_eval()?;
+
+        let result = self.data().and_then(|mut data| {
+            data.merge(overlay.clone());
+            let data_addr = opa_serde::to_instance(&self.instance, &data)?;
+            let input_addr = self.input_to_instance(input)?;
+            self.eval_ctx(input_addr, data_addr)
+        });
+
+        self.reset_heap()?;
+        result
+    }
+
+    // Evaluates against data for this call only, in place of the policy's
+    // data, which is left as it is. Unlike evaluate_with_overlay, nothing is
+    // merged, so data must be the complete document.
+    pub fn evaluate_with_data<T: Serialize>(
+        &mut self,
+        input: &T,
+        data: &Value,
+    ) -> Result<Value, Error> {
+        self.begin_eval()?;
+
+        let result = opa_serde::to_instance(&self.instance, data)
+            .map_err(Error::from)
+            .and_then(|data_addr| {
+                let input_addr = self.input_to_instance(input)?;
+                self.eval_ctx(input_addr, data_addr)
+            });
+
+        self.reset_heap()?;
+        result
+    }
+
+    pub fn data(&self) -> Result<Value, Error> {
+        let data = opa_serde::from_instance(&self.instance, self.data_addr)?;
+        Ok(data)
+    }
+
+    fn reset_heap(&self) -> Result<(), Error> {
         self.instance.functions().heap_ptr_set(self.data_heap_ptr)?;
         self.instance.functions().heap_top_set(self.data_heap_top)?;
+        Ok(())
+    }
 
-        // Load input data
-        let input_addr = opa_serde::to_instance(&self.instance, input)?;
+    // Resets the heap and the growth counter before an evaluation, so
+    // last_metrics covers it. Resetting the heap after an evaluation goes
+    // through reset_heap, which keeps the metrics.
+    fn begin_eval(&self) -> Result<(), Error> {
+        self.reset_heap()?;
+        let memory = self.instance.memory();
+        memory.growth().reset(memory.pages());
+        Ok(())
+    }
+
+    fn eval_ctx(&self, input_addr: ValueAddr, data_addr: ValueAddr) -> Result<Value, Error> {
+        let result_addr = self.eval_result_addr(input_addr, data_addr)?;
+        self.result(result_addr)
+    }
+
+    fn result<R: DeserializeOwned>(&self, addr: ValueAddr) -> Result<R, Error> {
+        let mut deserializer = opa_serde::Deserializer::from_instance(&self.instance, addr)
+            .max_depth(self.config.max_result_depth)
+            .coerce_integral_floats(self.config.coerce_integral_floats)
+            .cache_strings(self.config.cache_strings);
+        let r = R::deserialize(&mut deserializer).map_err(Error::from_result)?;
+        Ok(r)
+    }
+
+    fn input_to_instance<T: Serialize>(&self, input: &T) -> Result<ValueAddr, Error> {
+        let before = self.traced_heap_ptr()?;
+        let addr = match self.transform_input(input)? {
+            Some(input) => opa_serde::to_instance(&self.instance, &input),
+            None => opa_serde::to_instance(&self.instance, input),
+        }
+        .map_err(Error::from_input)?;
+        if let Some(size) = self.traced_size(before)? {
+            debug!(addr = %addr, size = size, "wrote input");
+        }
+        Ok(addr)
+    }
 
+    // Returns the transformed input, or None when there's no transform and
+    // the input can be written as it is. Sets and number refs survive the
+    // round trip through serde_json as their private structs.
+    fn transform_input<T: Serialize>(&self, input: &T) -> Result<Option<Value>, Error> {
+        let transform = match self.config.input_transform {
+            Some(ref transform) => transform,
+            None => return Ok(None),
+        };
+        let json = serde_json::to_value(input).map_err(Error::Json)?;
+        let mut value = serde_json::from_value(json).map_err(Error::Json)?;
+        transform.apply(&mut value);
+        Ok(Some(value))
+    }
+
+    fn eval_result_addr(
+        &self,
+        input_addr: ValueAddr,
+        data_addr: ValueAddr,
+    ) -> Result<ValueAddr, Error> {
+        self.eval_entrypoint_addr(self.entrypoint.id(), input_addr, data_addr)
+    }
+
+    fn eval_entrypoint_addr(
+        &self,
+        entrypoint: i32,
+        input_addr: ValueAddr,
+        data_addr: ValueAddr,
+    ) -> Result<ValueAddr, Error> {
         // setup the context
+        let before = self.traced_heap_ptr()?;
         let ctx_addr = self.instance.functions().eval_ctx_new()?;
+        if let Some(size) = self.traced_size(before)? {
+            debug!(
+                addr = %ctx_addr,
+                size = size,
+                input = %input_addr,
+                data = %data_addr,
+                "created eval context"
+            );
+        }
         self.instance
             .functions()
             .eval_ctx_set_input(ctx_addr, input_addr)?;
         self.instance
             .functions()
-            .eval_ctx_set_data(ctx_addr, self.data_addr)?;
+            .eval_ctx_set_data(ctx_addr, data_addr)?;
+        self.instance
+            .functions()
+            .eval_ctx_set_entrypoint(ctx_addr, entrypoint)?;
 
         // Eval
-        self.instance.functions().eval(ctx_addr)?;
+        self.clear_builtin_errors();
+        let before = self.traced_heap_ptr()?;
+        self.with_fuel(|| self.instance.functions().eval(ctx_addr))?;
+        self.check_builtin_errors()?;
 
         let result_addr = self.instance.functions().eval_ctx_get_result(ctx_addr)?;
-        let v = opa_serde::from_instance(&self.instance, result_addr)?;
-        Ok(v)
+        // The size covers everything evaluation allocated, the result included
+        if let Some(size) = self.traced_size(before)? {
+            debug!(addr = %result_addr, size = size, "evaluated result");
+        }
+        opa_serde::check_result_set(&self.instance, result_addr).map_err(Error::from_result)?;
+        if let Some(name) = self.required.get(&entrypoint) {
+            if opa_serde::is_empty_set(&self.instance, result_addr)? {
+                return Err(Error::Undefined(name.clone()));
+            }
+        }
+        Ok(result_addr)
+    }
+
+    // Runs an evaluation with the configured fuel. Calls outside of it, like
+    // writing the input, aren't limited.
+    fn with_fuel<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce() -> Result<T, Error>,
+    {
+        let fuel = self.instance.fuel();
+        fuel.reset(self.config.eval_fuel);
+        let result = f();
+        let exhausted = fuel.exhausted();
+        fuel.reset(None);
+        match result {
+            Err(_) if exhausted => Err(Error::FuelExhausted),
+            result => result,
+        }
+    }
+
+    // Drops failures and prints left over from earlier calls, so only this
+    // evaluation's are checked and kept. Without this they would grow for
+    // as long as nothing takes them.
+    fn clear_builtin_errors(&self) {
+        self.instance.builtins().clear();
+    }
+
+    fn check_builtin_errors(&self) -> Result<(), Error> {
+        if self.config.strict_builtin_ids {
+            let ids = self.instance.builtins().take_unknown_ids();
+            if let Some(id) = ids.into_iter().next() {
+                return Err(Error::UnknownBuiltinId(id));
+            }
+        }
+        if self.config.strict_builtin_errors {
+            let errors = self.instance.builtins().take_errors();
+            if let Some(error) = errors.into_iter().next() {
+                return Err(Error::BuiltinFailed(error));
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "msgpack")]
+    pub fn evaluate_msgpack(&mut self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        let input = rmp_serde::from_slice::<Value>(input).map_err(Error::MsgpackDecode)?;
+        let mut result = self.evaluate(&input)?;
+        // msgpack has no sets, and a set would be encoded as a map holding
+        // the private set token, so they become arrays like in JSON
+        result.visit_mut(|_, value| {
+            if let Value::Set(set) = value {
+                let elements = std::mem::take(set);
+                *value = Value::Array(elements.into_iter().collect());
+            }
+        });
+        rmp_serde::to_vec_named(&result).map_err(Error::MsgpackEncode)
+    }
+
+    // Results nested deeper than this fail with Error::TooDeep, instead of
+    // overflowing the stack while deserializing.
+    pub fn set_max_result_depth(&mut self, max_depth: usize) {
+        self.config.max_result_depth = max_depth;
+    }
+
+    // Sets PolicyConfig::coerce_integral_floats on a policy already created.
+    pub fn set_coerce_integral_floats(&mut self, coerce: bool) {
+        self.config.coerce_integral_floats = coerce;
+    }
+
+    // Sets PolicyConfig::cache_strings on a policy already created.
+    pub fn set_cache_strings(&mut self, cache: bool) {
+        self.config.cache_strings = cache;
+    }
+
+    // Sets the input that evaluate_with_delta merges into. Modules that
+    // export opa_value_add_path get it written into the instance once, above
+    // the data, so the heap reset before each evaluation keeps it. Setting it
+    // again leaves the old copy on the heap until the data is next set.
+    pub fn set_base_input(&mut self, input: Value) -> Result<(), Error> {
+        self.base_input = Some(input);
+        self.pin_base_input()
+    }
+
+    // Writes the base input above the data, the way set_data keeps the data
+    // above the base of the heap. Setting the data starts again from the
+    // base of the heap, so it calls this to write the input back.
+    fn pin_base_input(&mut self) -> Result<(), Error> {
+        self.base_input_addr = None;
+        let input = match self.base_input {
+            Some(ref input) if self.instance.functions().has_value_paths() => input,
+            _ => return Ok(()),
+        };
+        self.reset_heap()?;
+        let addr = opa_serde::to_instance(&self.instance, input)?;
+        self.base_input_addr = Some(addr);
+        self.data_heap_ptr = self.instance.functions().heap_ptr_get()?;
+        self.data_heap_top = self.instance.functions().heap_top_get()?;
+        Ok(())
+    }
+
+    // Evaluates the policy with the delta merged into the base input using
+    // Value::merge. When the base input is in the instance only the delta is
+    // written: each value it sets is added to the base with
+    // opa_value_add_path, and the base is put back after the evaluation.
+    // Otherwise, e.g. for OPA 0.18 modules, policies with an input transform
+    // or deltas that aren't objects, the merged input is written in full.
+    pub fn evaluate_with_delta(&mut self, delta: Value) -> Result<Value, Error> {
+        let base_addr = match (self.base_input_addr, &self.base_input, &delta) {
+            (Some(addr), Some(Value::Object(_)), Value::Object(_))
+                if self.config.input_transform.is_none() =>
+            {
+                addr
+            }
+            _ => {
+                let mut input = self.base_input.clone().unwrap_or_default();
+                input.merge(delta);
+                return self.evaluate(&input);
+            }
+        };
+
+        let mut leaves = Vec::new();
+        if let Some(ref base) = self.base_input {
+            delta_leaves(base, delta, "", &mut leaves);
+        }
+
+        self.begin_eval()?;
+        let mut added = Vec::with_capacity(leaves.len());
+        let result = leaves
+            .iter()
+            .try_for_each(|(pointer, value)| {
+                added.push(self.add_delta(base_addr, pointer, value)?);
+                Ok(())
+            })
+            .and_then(|_| self.eval_ctx(base_addr, self.data_addr));
+
+        // Put the base back, last change first, before the heap holding the
+        // delta is reused
+        for (path_addr, replaced) in added.into_iter().rev() {
+            match replaced {
+                Some(addr) => self
+                    .instance
+                    .functions()
+                    .value_add_path(base_addr, path_addr, addr)?,
+                None => self
+                    .instance
+                    .functions()
+                    .value_remove_path(base_addr, path_addr)?,
+            };
+        }
+        result
+    }
+
+    // Adds a value from a delta to the base input in the instance. Returns
+    // the path it was added at and the value it replaced, or None for a new
+    // key.
+    fn add_delta(
+        &self,
+        base_addr: ValueAddr,
+        pointer: &str,
+        value: &Value,
+    ) -> Result<(ValueAddr, Option<ValueAddr>), Error> {
+        let tokens = value::pointer_tokens(pointer)?;
+        let (segments, replaced) = opa_serde::path_segments(&self.instance, base_addr, tokens)?;
+        let value_addr = opa_serde::to_instance(&self.instance, value)?;
+        let path_addr = opa_serde::to_instance(&self.instance, &segments)?;
+        let code = self
+            .instance
+            .functions()
+            .value_add_path(base_addr, path_addr, value_addr)?;
+        if code != 0 {
+            return Err(Error::InvalidPointer(pointer.to_string()));
+        }
+        Ok((path_addr, replaced))
     }
 
     pub fn set_data<T: Serialize>(&mut self, data: &T) -> Result<(), Error> {
         self.instance.functions().heap_ptr_set(self.base_heap_ptr)?;
         self.instance.functions().heap_top_set(self.base_heap_top)?;
         self.data_addr = opa_serde::to_instance(&self.instance, data)?;
+        self.decisions.clear();
+        self.data_heap_ptr = self.instance.functions().heap_ptr_get()?;
+        self.data_heap_top = self.instance.functions().heap_top_get()?;
+        self.pin_base_input()
+    }
+
+    // Updates the data at a JSON pointer, creating missing intermediate
+    // objects. Modules that export opa_value_add_path are patched in place,
+    // older ones fall back to reserializing the whole document.
+    //
+    // Patching in place keeps each new value on the heap, and the value it
+    // replaces isn't reclaimed, so the memory grows with every patch. After
+    // many patches, set_data with the current data() compacts it again.
+    pub fn patch_data(&mut self, path: &str, value: Value) -> Result<(), Error> {
+        if path.is_empty() {
+            return self.set_data(&value);
+        }
+
+        if !self.instance.functions().has_value_paths() {
+            let mut data = self.data()?;
+            data.insert_at_creating(path, value)?;
+            return self.set_data(&data);
+        }
+
+        let tokens = value::pointer_tokens(path)?;
+        let (segments, _) = opa_serde::path_segments(&self.instance, self.data_addr, tokens)?;
+        self.reset_heap()?;
+        let value_addr = opa_serde::to_instance(&self.instance, &value)?;
+        let path_start = self.instance.functions().heap_ptr_get()?;
+        let path_addr = opa_serde::to_instance(&self.instance, &segments)?;
+        let before = self.instance.functions().heap_ptr_get()?;
+        let code =
+            self.instance
+                .functions()
+                .value_add_path(self.data_addr, path_addr, value_addr)?;
+        if code != 0 {
+            self.reset_heap()?;
+            return Err(Error::InvalidPointer(path.to_string()));
+        }
+
+        // The path is only read during the call, so it's dropped, unless the
+        // call allocated above it, e.g. objects for missing keys, which are
+        // now part of the data.
+        if self.instance.functions().heap_ptr_get()? == before {
+            self.instance.functions().heap_ptr_set(path_start)?;
+        }
+
+        self.decisions.clear();
+
+        // The new value is now part of the data, so keep it off the heap
+        // that is reset before each evaluation.
         self.data_heap_ptr = self.instance.functions().heap_ptr_get()?;
         self.data_heap_top = self.instance.functions().heap_top_get()?;
         Ok(())
     }
 
+    // Removes the data at a JSON pointer. Missing paths are left as is. The
+    // empty pointer, for the whole document, and malformed pointers fail
+    // with Error::InvalidPointer, whether or not the module exports
+    // opa_value_remove_path.
+    pub fn remove_data(&mut self, path: &str) -> Result<(), Error> {
+        let tokens = value::pointer_tokens(path)?;
+        if tokens.is_empty() {
+            return Err(Error::InvalidPointer(path.to_string()));
+        }
+
+        if !self.instance.functions().has_value_paths() {
+            let mut data = self.data()?;
+            if data.remove_at(path).is_some() {
+                self.set_data(&data)?;
+            }
+            return Ok(());
+        }
+
+        let (segments, _) = opa_serde::path_segments(&self.instance, self.data_addr, tokens)?;
+        self.reset_heap()?;
+        let path_addr = opa_serde::to_instance(&self.instance, &segments)?;
+        // A non-zero code means the path didn't exist
+        self.instance
+            .functions()
+            .value_remove_path(self.data_addr, path_addr)?;
+        self.reset_heap()?;
+        self.decisions.clear();
+        Ok(())
+    }
+
+    // The JSON is parsed above the current data first, so a malformed
+    // document leaves it in place. Once that succeeds it's parsed again at the
+    // base of the heap, where it replaces the old data instead of piling up
+    // on top of it.
+    pub fn set_data_json(&mut self, json: &str) -> Result<(), Error> {
+        self.reset_heap()?;
+        let parsed = self.parse_json(json);
+        self.reset_heap()?;
+        parsed?;
+
+        self.instance.functions().heap_ptr_set(self.base_heap_ptr)?;
+        self.instance.functions().heap_top_set(self.base_heap_top)?;
+        self.data_addr = self.parse_json(json)?;
+        self.decisions.clear();
+        self.data_heap_ptr = self.instance.functions().heap_ptr_get()?;
+        self.data_heap_top = self.instance.functions().heap_top_get()?;
+        self.pin_base_input()
+    }
+
+    fn parse_json(&self, json: &str) -> Result<ValueAddr, Error> {
+        let raw_addr = self.instance.functions().malloc(json.len())?;
+        self.instance.memory().set(raw_addr, &json)?;
+        self.instance.functions().json_parse(raw_addr, json)
+    }
+
+    // Copies the prebuilt data in the handle into this policy's heap, replacing
+    // the current data, without reserializing it.
+    pub fn attach_data(&mut self, handle: &DataHandle) -> Result<(), Error> {
+        if handle.start() != self.base_heap_ptr {
+            return Err(Error::DataHandleMismatch(
+                handle.start(),
+                self.base_heap_ptr,
+            ));
+        }
+        self.instance.functions().heap_ptr_set(self.base_heap_ptr)?;
+        self.instance.functions().heap_top_set(self.base_heap_top)?;
+        // malloc grows the memory if needed
+        let addr = self.instance.functions().malloc(handle.bytes().len())?;
+        if addr != handle.start() {
+            return Err(Error::DataHandleMismatch(handle.start(), addr));
+        }
+        self.instance.memory().set(addr, &handle.bytes())?;
+        self.data_addr = handle.data_addr();
+        self.decisions.clear();
+        self.data_heap_ptr = self.instance.functions().heap_ptr_get()?;
+        self.data_heap_top = self.instance.functions().heap_top_get()?;
+        self.pin_base_input()
+    }
+
+    // Memory growth since the last evaluation started, including serializing
+    // its input.
+    pub fn last_metrics(&self) -> Metrics {
+        self.instance.memory().growth().get()
+    }
+
+    // Like evaluate, but also reports the heap pointer before and after the
+    // call, to see how much each evaluation allocates.
+    pub fn evaluate_with_metrics<T: Serialize>(
+        &mut self,
+        input: &T,
+    ) -> Result<(Value, EvalMetrics), Error> {
+        let result = self.evaluate(input)?;
+        // evaluate starts by resetting the heap to the top of the data
+        let heap_ptr_before = self.data_heap_ptr;
+        let heap_ptr_after = self.instance.functions().heap_ptr_get()?;
+        let metrics = EvalMetrics {
+            heap_ptr_before: heap_ptr_before.0 as usize,
+            heap_ptr_after: heap_ptr_after.0 as usize,
+            memory: self.last_metrics(),
+        };
+        Ok((result, metrics))
+    }
+
+    pub fn memory_usage(&self) -> Result<MemoryStats, Error> {
+        let heap_ptr = self.instance.functions().heap_ptr_get()?;
+        let heap_top = self.instance.functions().heap_top_get()?;
+        let stats = MemoryStats {
+            heap_ptr: heap_ptr.0 as usize,
+            heap_top: heap_top.0 as usize,
+            base_heap_ptr: self.base_heap_ptr.0 as usize,
+            data_heap_ptr: self.data_heap_ptr.0 as usize,
+            pages: self.instance.memory().pages(),
+        };
+        Ok(stats)
+    }
+
+    // The messages printed by the last evaluation. Batch evaluations only
+    // keep the last item's.
+    pub fn take_prints(&mut self) -> Vec<String> {
+        self.instance.builtins().take_prints()
+    }
+
+    // The builtin calls traced since the last take, in the order they were
+    // made. Batch evaluations trace every item's calls.
+    pub fn take_builtin_trace(&mut self) -> Vec<BuiltinCall> {
+        self.instance.builtins().take_trace()
+    }
+
     // TODO: add proper parsing here
     // pub fn builtins(&mut self) -> Result<String, Error> {
     //     let addr = self.instance.functions().builtins()?;
@@ -125,6 +1605,61 @@ impl Policy {
     // }
 }
 
+// Finds an entrypoint named in a config. The only entrypoint of a module
+// compiled from a single query is named "".
+fn find_entrypoint(entrypoints: &Map<String, i32>, name: &str) -> Result<Entrypoint, Error> {
+    let id = match entrypoints.get(name) {
+        Some(id) => *id,
+        None if name.is_empty() && entrypoints.is_empty() => 0,
+        None => return Err(Error::UnknownEntrypoint(name.to_string())),
+    };
+    let entrypoint = Entrypoint {
+        name: name.to_string(),
+        id,
+    };
+    Ok(entrypoint)
+}
+
+// Splits a delta into the values Value::merge sets on the base, each with
+// its pointer. Objects on both sides are merged key by key, and anything else
+// in the delta replaces the base's value.
+fn delta_leaves(base: &Value, delta: Value, pointer: &str, leaves: &mut Vec<(String, Value)>) {
+    match (base, delta) {
+        (Value::Object(ref base), Value::Object(delta)) => {
+            for (k, v) in delta {
+                let pointer = value::pointer_child(pointer, &k);
+                match base.get(&k) {
+                    Some(existing) => delta_leaves(existing, v, &pointer, leaves),
+                    None => leaves.push((pointer, v)),
+                }
+            }
+        }
+        (_, delta) => leaves.push((pointer.to_string(), delta)),
+    }
+}
+
+// Returns the rest of a dotted path after its leading segments `prefix`, or
+// None when it doesn't start with them.
+fn strip_path<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    if path == prefix {
+        return Some("");
+    }
+    match path.get(prefix.len()..) {
+        Some(rest) if path.starts_with(prefix) && rest.starts_with('.') => Some(&rest[1..]),
+        _ => None,
+    }
+}
+
 fn abort(_a: i32) {
-    println!("abort");
+    error!("abort");
+}
+
+// Messages from opa_println go to the same buffer as print calls, to be
+// read with Policy::take_prints.
+fn println(memory: &Memory, builtins: &Builtins, addr: ValueAddr) {
+    let message = match memory.get_cstr(addr) {
+        Ok(s) => s,
+        Err(e) => format!("failed to read message: {}", e),
+    };
+    builtins.println(message);
 }
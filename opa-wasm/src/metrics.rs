@@ -0,0 +1,15 @@
+use std::time::Duration;
+
+/// Receives the latency of each `Policy::evaluate` call.
+///
+/// Implement this to forward timings to a histogram in any metrics backend.
+/// Closures taking a `Duration` implement it already.
+pub trait Recorder {
+    fn record(&self, duration: Duration);
+}
+
+impl<F: Fn(Duration)> Recorder for F {
+    fn record(&self, duration: Duration) {
+        self(duration)
+    }
+}
@@ -1,26 +1,103 @@
 #![allow(dead_code)]
 
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::os::raw::*;
 use std::str;
 
-use serde::de::{self, IntoDeserializer, Visitor};
+use serde::de::{self, Deserialize, IntoDeserializer, Visitor};
 
+use crate::opa_serde::error::TypeTag;
 use crate::opa_serde::{Error, Result};
 use crate::runtime::Instance;
-use crate::value::number;
-use crate::{set, ValueAddr};
+use crate::value::{number, pointer_index};
+use crate::{set, Value, ValueAddr};
 
 use super::*;
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DuplicateKeyPolicy {
+    LastWins,
+    FirstWins,
+    Error,
+}
+
+impl Default for DuplicateKeyPolicy {
+    fn default() -> Self {
+        DuplicateKeyPolicy::LastWins
+    }
+}
+
+// Deserialization recurses once per nested array, object, set or enum, so
+// this bounds the stack used for pathological values.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
 pub struct Deserializer<'de> {
     instance: &'de Instance,
     addr: ValueAddr,
+    duplicate_keys: DuplicateKeyPolicy,
+    depth: usize,
+    max_depth: usize,
+    coerce_integral_floats: bool,
+    strings: Option<RefCell<HashMap<i32, String>>>,
 }
 
 impl<'de> Deserializer<'de> {
     pub fn from_instance(instance: &'de Instance, addr: ValueAddr) -> Self {
-        Self { instance, addr }
+        Self {
+            instance,
+            addr,
+            duplicate_keys: DuplicateKeyPolicy::default(),
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            coerce_integral_floats: false,
+            strings: None,
+        }
+    }
+
+    pub fn duplicate_key_policy(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    // Lets integers be read from floats with no fractional part, e.g. 3.0 as
+    // 3, which OPA produces from arithmetic on integers. Other floats still
+    // fail with Error::ExpectedInteger.
+    pub fn coerce_integral_floats(mut self, coerce: bool) -> Self {
+        self.coerce_integral_floats = coerce;
+        self
+    }
+
+    // Caches strings by address, so a string OPA references from several
+    // places in a result, like a key repeated across objects built from the
+    // same data, is read from memory and checked as UTF-8 once.
+    pub fn cache_strings(mut self, cache: bool) -> Self {
+        self.strings = if cache {
+            Some(RefCell::new(HashMap::new()))
+        } else {
+            None
+        };
+        self
+    }
+
+    // Runs f one level deeper, failing if that exceeds the maximum depth.
+    fn nested<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        if self.depth >= self.max_depth {
+            return Err(Error::TooDeep(self.max_depth));
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
     }
 }
 
@@ -33,6 +110,157 @@ where
     Ok(t)
 }
 
+pub fn is_empty_set(instance: &Instance, addr: ValueAddr) -> Result<bool> {
+    let deserializer = Deserializer::from_instance(instance, addr);
+    match deserializer.peek_type()? {
+        OPA_SET => {
+            let set = instance.memory().get::<opa_set_t>(addr)?;
+            Ok(set.head == 0)
+        }
+        ty => Err(Error::ExpectedSet(ty)),
+    }
+}
+
+// Returns the address of the first binding in a result set, or None if the
+// set is empty. A binding object with a single entry, like `{"result": x}`,
+// is unwrapped to the address of its value.
+pub fn result_binding(instance: &Instance, addr: ValueAddr) -> Result<Option<ValueAddr>> {
+    let head = result_set_head(instance, addr)?;
+    if head == 0 {
+        return Ok(None);
+    }
+    let elem = instance.memory().get::<opa_set_elem_t>(ValueAddr(head))?;
+    let binding = unwrap_binding(instance, ValueAddr(elem.v))?;
+    Ok(Some(binding))
+}
+
+// Like result_binding, but returns every binding in the result set.
+pub fn result_bindings(instance: &Instance, addr: ValueAddr) -> Result<Vec<ValueAddr>> {
+    let mut bindings = Vec::new();
+    let mut next = result_set_head(instance, addr)?;
+    while next != 0 {
+        let elem = instance.memory().get::<opa_set_elem_t>(ValueAddr(next))?;
+        bindings.push(unwrap_binding(instance, ValueAddr(elem.v))?);
+        next = elem.next;
+    }
+    Ok(bindings)
+}
+
+// Checks that addr holds a result set, a set of binding objects, so that a
+// module built for a different ABI fails clearly instead of with whatever
+// error deserializing its result happens to hit.
+pub fn check_result_set(instance: &Instance, addr: ValueAddr) -> Result<()> {
+    if addr.0 == 0 {
+        return Err(Error::MalformedResult("no result".to_string()));
+    }
+
+    let memory = instance.memory();
+    let ty = memory.get::<opa_value>(addr)?.ty;
+    if ty != OPA_SET {
+        return Err(Error::MalformedResult(format!(
+            "expected a set of bindings, found {}",
+            TypeTag(ty as u8)
+        )));
+    }
+
+    let mut next = memory.get::<opa_set_t>(addr)?.head;
+    while next != 0 {
+        let elem = memory.get::<opa_set_elem_t>(ValueAddr(next))?;
+        let ty = memory.get::<opa_value>(ValueAddr(elem.v))?.ty;
+        if ty != OPA_OBJECT {
+            return Err(Error::MalformedResult(format!(
+                "expected a set of bindings, found a set containing {}",
+                TypeTag(ty as u8)
+            )));
+        }
+        next = elem.next;
+    }
+    Ok(())
+}
+
+fn result_set_head(instance: &Instance, addr: ValueAddr) -> Result<i32> {
+    let memory = instance.memory();
+    let ty = memory.get::<opa_value>(addr)?.ty;
+    if ty != OPA_SET {
+        return Err(Error::ExpectedSet(ty));
+    }
+    let set = memory.get::<opa_set_t>(addr)?;
+    Ok(set.head)
+}
+
+fn unwrap_binding(instance: &Instance, binding: ValueAddr) -> Result<ValueAddr> {
+    let memory = instance.memory();
+    if memory.get::<opa_value>(binding)?.ty == OPA_OBJECT {
+        let object = memory.get::<opa_object_t>(binding)?;
+        if object.head != 0 {
+            let entry = memory.get::<opa_object_elem_t>(ValueAddr(object.head))?;
+            if entry.next == 0 {
+                return Ok(ValueAddr(entry.v));
+            }
+        }
+    }
+    Ok(binding)
+}
+
+// Converts JSON pointer tokens into a path for opa_value_add_path and
+// opa_value_remove_path, which look up array elements by number. The tokens
+// are walked through the value at addr: those indexing an array become
+// numbers and the rest stay strings. Once a step is missing, the remaining
+// tokens are left as strings, like the objects add_path creates for them.
+// Also returns the address of the value at the path, if it exists.
+pub fn path_segments(
+    instance: &Instance,
+    addr: ValueAddr,
+    tokens: Vec<String>,
+) -> Result<(Vec<Value>, Option<ValueAddr>)> {
+    let memory = instance.memory();
+    let mut node = Some(addr);
+    let mut segments = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let current = match node {
+            Some(current) => current,
+            None => {
+                segments.push(Value::String(token));
+                continue;
+            }
+        };
+
+        node = None;
+        match memory.get::<opa_value>(current)?.ty {
+            OPA_ARRAY => match pointer_index(&token) {
+                Some(index) => {
+                    let array = memory.get::<opa_array_t>(current)?;
+                    if index < array.len as usize {
+                        let size = std::mem::size_of::<opa_array_elem_t>();
+                        let elem_addr = elem_addr(array.elems.into(), index, size)?;
+                        node = Some(memory.get::<opa_array_elem_t>(elem_addr)?.v.into());
+                    }
+                    segments.push(Value::from(index));
+                }
+                None => segments.push(Value::String(token)),
+            },
+            OPA_OBJECT => {
+                let mut next = memory.get::<opa_object_t>(current)?.head;
+                while next != 0 {
+                    let elem = memory.get::<opa_object_elem_t>(ValueAddr(next))?;
+                    let key = ValueAddr(elem.k);
+                    if memory.get::<opa_value>(key)?.ty == OPA_STRING {
+                        let s = memory.get::<opa_string_t>(key)?;
+                        if memory.get_bytes(s.v.into(), s.len as usize)? == token.as_bytes() {
+                            node = Some(ValueAddr(elem.v));
+                            break;
+                        }
+                    }
+                    next = elem.next;
+                }
+                segments.push(Value::String(token));
+            }
+            _ => segments.push(Value::String(token)),
+        }
+    }
+    Ok((segments, node))
+}
+
 impl<'de> Deserializer<'de> {
     fn peek_type(&self) -> Result<c_uchar> {
         let c = self
@@ -78,14 +306,41 @@ impl<'de> Deserializer<'de> {
         }
 
         let n = self.instance.memory().get::<opa_number_t>(self.addr)?;
-        if n.repr != OPA_NUMBER_REPR_INT {
-            return Err(Error::ExpectedInteger(n.repr as u8));
-        }
+        let i = match n.repr {
+            OPA_NUMBER_REPR_INT => unsafe { n.v.i },
+            OPA_NUMBER_REPR_FLOAT if self.coerce_integral_floats => {
+                let f = unsafe { n.v.f };
+                // The upper bound, 2^63, is exact as a float but too big for i64
+                if f.fract() != 0.0 || f < i64::MIN as f64 || f >= -(i64::MIN as f64) {
+                    return Err(Error::ExpectedInteger(n.repr as u8));
+                }
+                f as i64
+            }
+            repr => return Err(Error::ExpectedInteger(repr as u8)),
+        };
 
-        let i = unsafe { T::try_from(n.v.i).map_err(|e| e.into())? };
+        let i = T::try_from(i).map_err(|e| e.into())?;
         Ok(i)
     }
 
+    // Integers too big for i64 come back from OPA as number refs, so 128-bit
+    // integers are parsed from the ref's string.
+    fn parse_big<T>(&self) -> Result<T>
+    where
+        T: str::FromStr + TryFrom<i64>,
+        <T as TryFrom<i64>>::Error: Into<Error>,
+    {
+        match self.peek_num_repr()? {
+            OPA_NUMBER_REPR_INT => self.parse_int(),
+            OPA_NUMBER_REPR_FLOAT if self.coerce_integral_floats => self.parse_int(),
+            OPA_NUMBER_REPR_REF => {
+                let s = self.parse_number_ref()?;
+                s.parse().map_err(|_| Error::ExpectedBigInteger(s))
+            }
+            repr => Err(Error::ExpectedInteger(repr as u8)),
+        }
+    }
+
     fn parse_float(&self) -> Result<f64> {
         let ty = self.peek_type()?;
         if ty != OPA_NUMBER {
@@ -97,7 +352,12 @@ impl<'de> Deserializer<'de> {
             return Err(Error::ExpectedFloat(n.repr as u8));
         }
 
+        // OPA's JSON can't hold NaN or infinities, and letting one through
+        // would put a value in a Value that from_f64 would never create.
         let f = unsafe { n.v.f };
+        if !f.is_finite() {
+            return Err(Error::NonFiniteNumber(f));
+        }
         Ok(f)
     }
 
@@ -123,12 +383,46 @@ impl<'de> Deserializer<'de> {
         if ty != OPA_STRING {
             return Err(Error::ExpectedString(ty as u8));
         }
+        if let Some(s) = self
+            .strings
+            .as_ref()
+            .and_then(|s| s.borrow().get(&self.addr.0).cloned())
+        {
+            return Ok(s);
+        }
+
         let s = self.instance.memory().get::<opa_string_t>(self.addr)?;
         let len = s.len as usize;
         let bytes = self.instance.memory().get_bytes(s.v.into(), len)?;
         let s = String::from_utf8(bytes).map_err(Error::InvalidUtf8)?;
+        if let Some(ref strings) = self.strings {
+            strings.borrow_mut().insert(self.addr.0, s.clone());
+        }
         Ok(s)
     }
+
+    // Object keys can be numbers in OPA. Read as strings, they take the text
+    // OPA writes them as in JSON, as "1" for the key 1.
+    fn parse_key(&self) -> Result<String> {
+        match self.peek_type()? {
+            OPA_STRING => self.parse_string(),
+            OPA_NUMBER => match self.peek_num_repr()? {
+                OPA_NUMBER_REPR_INT => Ok(self.parse_int::<i64>()?.to_string()),
+                OPA_NUMBER_REPR_FLOAT => Ok(number::Number::from(self.parse_float()?).to_string()),
+                _ => self.parse_number_ref(),
+            },
+            ty => Err(Error::ExpectedString(ty as u8)),
+        }
+    }
+
+    fn parse_bytes(&self) -> Result<Vec<u8>> {
+        let s = self.instance.memory().get::<opa_string_t>(self.addr)?;
+        let bytes = self
+            .instance
+            .memory()
+            .get_bytes(s.v.into(), s.len as usize)?;
+        Ok(bytes)
+    }
 }
 
 impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
@@ -239,6 +533,20 @@ impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         visitor.visit_u64(self.parse_int()?)
     }
 
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i128(self.parse_big()?)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u128(self.parse_big()?)
+    }
+
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
@@ -284,11 +592,15 @@ impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
 
     // The `Serializer` implementation on the previous page serialized byte
     // arrays as JSON arrays of bytes. Handle that representation here.
+    // Bytes are serialized as a string, but arrays of numbers are accepted too.
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        match self.peek_type()? {
+            OPA_STRING => visitor.visit_byte_buf(self.parse_bytes()?),
+            _ => self.deserialize_seq(visitor),
+        }
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
@@ -356,14 +668,14 @@ impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         V: Visitor<'de>,
     {
         match self.peek_type()? {
-            OPA_ARRAY => {
-                let access = ArrayAccess::from_deserializer(self)?;
+            OPA_ARRAY => self.nested(|de| {
+                let access = ArrayAccess::from_deserializer(de)?;
                 visitor.visit_seq(access)
-            }
-            OPA_SET => {
-                let access = SetAccess::from_deserializer(self)?;
+            }),
+            OPA_SET => self.nested(|de| {
+                let access = SetAccess::from_deserializer(de)?;
                 visitor.visit_seq(access)
-            }
+            }),
             ty => return Err(Error::ExpectedArray(ty as u8)),
         }
     }
@@ -406,8 +718,10 @@ impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
             return Err(Error::ExpectedObject(ty as u8));
         }
 
-        let access = ObjectAccess::from_deserializer(self)?;
-        visitor.visit_map(access)
+        self.nested(|de| {
+            let access = ObjectAccess::from_deserializer(de)?;
+            visitor.visit_map(access)
+        })
     }
 
     // Structs look just like maps in JSON.
@@ -426,7 +740,7 @@ impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         V: Visitor<'de>,
     {
         if name == set::TOKEN && fields == [set::TOKEN] {
-            visitor.visit_map(SetStructAccess::from_deserializer(self)?)
+            self.nested(|de| visitor.visit_map(SetStructAccess::from_deserializer(de)?))
         } else if name == number::TOKEN && fields == [number::TOKEN] {
             visitor.visit_map(NumberRefStructAccess::from_deserializer(self)?)
         } else {
@@ -445,7 +759,7 @@ impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     {
         match self.peek_type()? {
             OPA_STRING => visitor.visit_enum(self.parse_string()?.into_deserializer()),
-            OPA_OBJECT => visitor.visit_enum(EnumAccess::from_deserializer(self)?),
+            OPA_OBJECT => self.nested(|de| visitor.visit_enum(EnumAccess::from_deserializer(de)?)),
             ty => Err(Error::ExpectedEnum(ty as u8)),
         }
     }
@@ -510,7 +824,7 @@ impl<'de, 'a> de::SeqAccess<'de> for ArrayAccess<'a, 'de> {
         if self.n == self.len {
             return Ok(None);
         }
-        let addr = self.elems + self.n * mem::size_of::<opa_array_elem_t>();
+        let addr = elem_addr(self.elems, self.n, mem::size_of::<opa_array_elem_t>())?;
         let elem = self.de.instance.memory().get::<opa_array_elem_t>(addr)?;
 
         self.n = self.n + 1;
@@ -564,22 +878,177 @@ impl<'de, 'a> de::SeqAccess<'de> for SetAccess<'a, 'de> {
 
 struct ObjectAccess<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
-    next: Option<ValueAddr>,
+    entries: Vec<(ValueAddr, ValueAddr)>,
+    n: usize,
 }
 
 impl<'a, 'de> ObjectAccess<'a, 'de> {
     fn from_deserializer(de: &'a mut Deserializer<'de>) -> Result<Self> {
         let object = de.instance.memory().get::<opa_object_t>(de.addr)?;
-        let next = if object.head == 0 {
-            None
-        } else {
-            Some(ValueAddr(object.head as i32))
-        };
-        let access = ObjectAccess { de, next };
+        let mut entries = Vec::new();
+        let mut next = object.head;
+        while next != 0 {
+            let elem = de
+                .instance
+                .memory()
+                .get::<opa_object_elem_t>(ValueAddr(next))?;
+            entries.push((ValueAddr(elem.k), ValueAddr(elem.v)));
+            next = elem.next;
+        }
+
+        // Maps already keep the last value inserted for a key, so the default
+        // policy doesn't need to read every key twice
+        if entries.len() > 1 && de.duplicate_keys != DuplicateKeyPolicy::LastWins {
+            entries = dedup_entries(de, entries)?;
+        }
+
+        let access = ObjectAccess { de, entries, n: 0 };
         Ok(access)
     }
 }
 
+// Deserializes an object key. Keys read as strings accept numbers too, using
+// parse_key, and everything else is read as any other value, so maps keyed
+// by integers get their keys back as numbers.
+struct MapKey<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+}
+
+macro_rules! forward_key {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                self.de.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'a, 'de> de::Deserializer<'de> for MapKey<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_string(self.de.parse_key()?)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    forward_key! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16
+        deserialize_i32 deserialize_i64 deserialize_i128 deserialize_u8
+        deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64 deserialize_char deserialize_bytes
+        deserialize_byte_buf deserialize_option deserialize_unit
+        deserialize_seq deserialize_map deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.deserialize_enum(name, variants, visitor)
+    }
+}
+
+fn dedup_entries(
+    de: &mut Deserializer,
+    entries: Vec<(ValueAddr, ValueAddr)>,
+) -> Result<Vec<(ValueAddr, ValueAddr)>> {
+    let mut seen = BTreeMap::new();
+    let mut keep = vec![true; entries.len()];
+    for (i, (k, _v)) in entries.iter().enumerate() {
+        de.addr = *k;
+        let key = Value::deserialize(&mut *de)?;
+        if let Some(first) = seen.get(&key).copied() {
+            match de.duplicate_keys {
+                DuplicateKeyPolicy::LastWins => {
+                    keep[first] = false;
+                    seen.insert(key, i);
+                }
+                DuplicateKeyPolicy::FirstWins => keep[i] = false,
+                DuplicateKeyPolicy::Error => return Err(Error::DuplicateKey(key.to_string())),
+            }
+        } else {
+            seen.insert(key, i);
+        }
+    }
+
+    let entries = entries
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(entry, keep)| if keep { Some(entry) } else { None })
+        .collect();
+    Ok(entries)
+}
+
 impl<'de, 'a> de::MapAccess<'de> for ObjectAccess<'a, 'de> {
     type Error = Error;
 
@@ -587,14 +1056,9 @@ impl<'de, 'a> de::MapAccess<'de> for ObjectAccess<'a, 'de> {
     where
         K: de::DeserializeSeed<'de>,
     {
-        if let Some(next_addr) = self.next {
-            let elem = self
-                .de
-                .instance
-                .memory()
-                .get::<opa_object_elem_t>(next_addr)?;
-            self.de.addr = ValueAddr(elem.k as i32);
-            seed.deserialize(&mut *self.de).map(Some)
+        if let Some((k, _v)) = self.entries.get(self.n) {
+            self.de.addr = *k;
+            seed.deserialize(MapKey { de: &mut *self.de }).map(Some)
         } else {
             Ok(None)
         }
@@ -604,24 +1068,18 @@ impl<'de, 'a> de::MapAccess<'de> for ObjectAccess<'a, 'de> {
     where
         V: de::DeserializeSeed<'de>,
     {
-        if let Some(next_addr) = self.next {
-            let elem = self
-                .de
-                .instance
-                .memory()
-                .get::<opa_object_elem_t>(next_addr)?;
-            self.next = if elem.next != 0 {
-                Some(elem.next.into())
-            } else {
-                None
-            };
-
-            self.de.addr = ValueAddr(elem.v as i32);
+        if let Some((_k, v)) = self.entries.get(self.n) {
+            self.n += 1;
+            self.de.addr = *v;
             seed.deserialize(&mut *self.de)
         } else {
             Err(Error::ExpectedNextAddr)
         }
     }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len() - self.n)
+    }
 }
 
 struct EnumAccess<'a, 'de: 'a> {
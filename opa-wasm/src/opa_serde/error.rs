@@ -20,39 +20,45 @@ pub enum Error {
     ExpectedSeqLen,
     #[error("Unexpected null pointer.")]
     NullPtr,
-    #[error("Invalid serialized length. Expected len {0}, serialized {1}")]
+    #[error("Invalid serialized length. Expected {0} elements, serialized {1}.")]
     InvalidSeqLen(usize, usize),
-    #[error("Unknown type: {0}")]
+    #[error("Unknown type tag {0}.")]
     UnknownType(u8),
-    #[error("Expected boolean value. Found type {0}")]
+    #[error("Expected boolean value. Found {}.", TypeTag(*.0))]
     ExpectedBoolean(u8),
-    #[error("Expected number value. Found type {0}")]
+    #[error("Expected number value. Found {}.", TypeTag(*.0))]
     ExpectedNumber(u8),
-    #[error("Expected integer value. Found repr {0}")]
+    #[error("Expected integer value. Found {}.", ReprTag(*.0))]
     ExpectedInteger(u8),
-    #[error("Expected float value. Found repr {0}")]
+    #[error("Expected float value. Found {}.", ReprTag(*.0))]
     ExpectedFloat(u8),
-    #[error("Expected number ref. Found repr {0}")]
+    #[error("Expected number ref. Found {}.", ReprTag(*.0))]
     ExpectedNumberRef(u8),
-    #[error("Invalid number repr. Found repr {0}")]
+    #[error("Non-finite number {0} can't be represented in OPA.")]
+    NonFiniteNumber(f64),
+    #[error("Number {0} is not a 128-bit integer.")]
+    ExpectedBigInteger(String),
+    #[error("Invalid number repr {0}.")]
     InvalidNumberRepr(u8),
     #[error("Integer conversion failed.")]
     IntegerConversion(#[source] num::TryFromIntError),
-    #[error("Expected string value. Found type {0}")]
+    #[error("Expected string value. Found {}.", TypeTag(*.0))]
     ExpectedString(u8),
     #[error("Invalid utf8 string.")]
     InvalidUtf8(#[source] string::FromUtf8Error),
     #[error("Invalid char. Expected a string of length one.")]
     InvalidChar,
-    #[error("Expected null value. Found type {0}")]
+    #[error("Expected null value. Found {}.", TypeTag(*.0))]
     ExpectedNull(u8),
-    #[error("Expected array value. Found type {0}")]
+    #[error("Expected array value. Found {}.", TypeTag(*.0))]
     ExpectedArray(u8),
-    #[error("Expected object value. Found type {0}")]
+    #[error("Expected object value. Found {}.", TypeTag(*.0))]
     ExpectedObject(u8),
-    #[error("Expected enum value. Found type {0}")]
+    #[error("Expected set value. Found {}.", TypeTag(*.0))]
+    ExpectedSet(u8),
+    #[error("Expected enum value. Found {}.", TypeTag(*.0))]
     ExpectedEnum(u8),
-    #[error("Expected next address when parsing object element value")]
+    #[error("Expected next address when parsing object element value.")]
     ExpectedNextAddr,
     #[error("Expected entry key when parsing enum.")]
     ExpectedKey,
@@ -64,6 +70,48 @@ pub enum Error {
     NumberRefInvalid,
     #[error("Expected field {0}.")]
     ExpectedField(&'static str),
+    #[error("Address arithmetic out of bounds.")]
+    OutOfBounds,
+    #[error("Value is nested deeper than the maximum depth of {0}.")]
+    TooDeep(usize),
+    #[error("Duplicate object key {0:?}.")]
+    DuplicateKey(String),
+    #[error("Malformed result: {0}.")]
+    MalformedResult(String),
+}
+
+// Renders an OPA value type tag, e.g. "string (type 4)".
+pub(super) struct TypeTag(pub(super) u8);
+
+impl fmt::Display for TypeTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.0 {
+            super::OPA_NULL => "null",
+            super::OPA_BOOLEAN => "boolean",
+            super::OPA_NUMBER => "number",
+            super::OPA_STRING => "string",
+            super::OPA_ARRAY => "array",
+            super::OPA_OBJECT => "object",
+            super::OPA_SET => "set",
+            _ => "unknown",
+        };
+        write!(f, "{} (type {})", name, self.0)
+    }
+}
+
+// Renders an OPA number representation tag, e.g. "float (repr 2)".
+struct ReprTag(u8);
+
+impl fmt::Display for ReprTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.0 {
+            super::OPA_NUMBER_REPR_INT => "integer",
+            super::OPA_NUMBER_REPR_FLOAT => "float",
+            super::OPA_NUMBER_REPR_REF => "ref",
+            _ => "unknown",
+        };
+        write!(f, "{} (repr {})", name, self.0)
+    }
 }
 
 impl ser::Error for Error {
@@ -95,3 +143,55 @@ impl From<crate::Error> for Error {
         Self::General(Box::new(error))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let cases = vec![
+            (
+                Error::ExpectedNumber(super::super::OPA_STRING),
+                "Expected number value. Found string (type 4).",
+            ),
+            (
+                Error::ExpectedObject(super::super::OPA_SET),
+                "Expected object value. Found set (type 7).",
+            ),
+            (
+                Error::ExpectedBoolean(42),
+                "Expected boolean value. Found unknown (type 42).",
+            ),
+            (
+                Error::ExpectedInteger(super::super::OPA_NUMBER_REPR_FLOAT),
+                "Expected integer value. Found float (repr 2).",
+            ),
+            (
+                Error::InvalidSeqLen(3, 2),
+                "Invalid serialized length. Expected 3 elements, serialized 2.",
+            ),
+            (Error::UnknownType(9), "Unknown type tag 9."),
+            (
+                Error::DuplicateKey("a".to_string()),
+                "Duplicate object key \"a\".",
+            ),
+            (Error::ExpectedField("$field"), "Expected field $field."),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(expected, error.to_string());
+        }
+    }
+
+    #[test]
+    fn test_custom() {
+        let error = <Error as de::Error>::custom("missing field `name`");
+        assert_eq!("missing field `name`", error.to_string());
+
+        let error = <Error as ser::Error>::custom("key must be a string");
+        assert_eq!("key must be a string", error.to_string());
+
+        let error = <Error as de::Error>::invalid_length(1, &"2 elements");
+        assert_eq!("invalid length 1, expected 2 elements", error.to_string());
+    }
+}
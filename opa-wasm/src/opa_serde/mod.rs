@@ -2,7 +2,10 @@ mod de;
 mod error;
 mod ser;
 
-pub use de::{from_instance, Deserializer};
+pub use de::{
+    check_result_set, from_instance, is_empty_set, path_segments, result_binding, result_bindings,
+    Deserializer, DuplicateKeyPolicy, DEFAULT_MAX_DEPTH,
+};
 pub use error::{Error, Result};
 pub use ser::{to_instance, Serializer};
 
@@ -26,6 +29,13 @@ const OPA_NUMBER_REPR_REF: c_uchar = 3;
 
 const NULL: opa_value = opa_value { ty: OPA_NULL };
 
+fn elem_addr(base: ValueAddr, index: usize, size: usize) -> Result<ValueAddr> {
+    index
+        .checked_mul(size)
+        .and_then(|offset| base.checked_add(offset))
+        .ok_or(Error::OutOfBounds)
+}
+
 // wasm is 32-bit and doesn't support unsigned ints
 #[allow(non_camel_case_types)]
 type size_t = c_int;
@@ -162,9 +172,13 @@ pub struct opa_string_t {
 
 impl opa_string_t {
     pub fn from_str(s: &str, data: ValueAddr) -> Self {
+        Self::from_bytes(s.as_bytes(), data)
+    }
+
+    pub fn from_bytes(bytes: &[u8], data: ValueAddr) -> Self {
         let hdr = opa_value { ty: OPA_STRING };
         let free = 0 as c_uchar;
-        let len = s.len() as size_t;
+        let len = bytes.len() as size_t;
         opa_string_t {
             hdr,
             free,
@@ -253,7 +267,7 @@ impl opa_set_t {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap, HashSet};
     use std::fs;
     use std::mem;
 
@@ -262,6 +276,7 @@ mod tests {
 
     use crate::opa_serde::to_instance;
     use crate::runtime::{Instance, Memory, Module};
+    use crate::value::arb::arb_value;
     use crate::value::{self, Number, Value};
 
     use super::*;
@@ -298,6 +313,66 @@ mod tests {
         properties: HashMap<String, String>,
     }
 
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Flattened {
+        name: String,
+        #[serde(flatten)]
+        extra: HashMap<String, Value>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Skipped {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        nickname: Option<String>,
+        age: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        email: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Blob {
+        name: String,
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+    }
+
+    // A map whose length hint is off, which serde allows
+    struct Hinted {
+        hint: Option<usize>,
+        len: usize,
+    }
+
+    impl Serialize for Hinted {
+        fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            use serde::ser::SerializeMap;
+            let mut map = serializer.serialize_map(self.hint)?;
+            for i in 0..self.len {
+                map.serialize_entry(&format!("key{}", i), &i)?;
+            }
+            map.end()
+        }
+    }
+
+    struct DuplicateKeys;
+
+    impl Serialize for DuplicateKeys {
+        fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            use serde::ser::SerializeMap;
+            let mut map = serializer.serialize_map(Some(3))?;
+            map.serialize_entry("a", &1)?;
+            map.serialize_entry("b", &2)?;
+            map.serialize_entry("a", &3)?;
+            map.end()
+        }
+    }
+
     #[test]
     fn test_bool_size() {
         assert_eq!(8, mem::size_of::<opa_boolean_t>());
@@ -395,6 +470,66 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_roundtrip_map_integer_keys() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+            let mut input = BTreeMap::new();
+            input.insert(-1_i64, "minus one".to_string());
+            input.insert(0, "zero".to_string());
+            input.insert(i64::max_value(), "max".to_string());
+            let addr = to_instance(&instance, &input).unwrap();
+            let loaded: BTreeMap<i64, String> = from_instance(&instance, addr).unwrap();
+            assert_eq!(input, loaded);
+
+            // The keys are OPA numbers, which read as their text into types
+            // keyed by strings
+            let value: Value = from_instance(&instance, addr).unwrap();
+            assert_eq!(Some(&Value::from("minus one")), value.get("-1"));
+            assert_eq!(Some(&Value::from("max")), value.get("9223372036854775807"));
+            let loaded: HashMap<String, String> = from_instance(&instance, addr).unwrap();
+            assert_eq!(Some("zero"), loaded.get("0").map(String::as_str));
+
+            let empty = BTreeMap::<i64, String>::new();
+            let addr = to_instance(&instance, &empty).unwrap();
+            let loaded: BTreeMap<i64, String> = from_instance(&instance, addr).unwrap();
+            assert!(loaded.is_empty());
+        })
+    }
+
+    #[test]
+    fn test_roundtrip_map_enum_values() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+            let mut input = HashMap::new();
+            input.insert("unit".to_string(), TestEnum::Unit);
+            input.insert("newtype".to_string(), TestEnum::NewType(64));
+            input.insert(
+                "tuple".to_string(),
+                TestEnum::Tuple(64, "Hello".to_string()),
+            );
+            input.insert(
+                "struct".to_string(),
+                TestEnum::Struct {
+                    age: 64,
+                    msg: "Hello".to_string(),
+                },
+            );
+            let addr = to_instance(&instance, &input).unwrap();
+            let loaded: HashMap<String, TestEnum> = from_instance(&instance, addr).unwrap();
+            assert_eq!(input, loaded);
+
+            // Variants with data are objects keyed by the variant name
+            let value: Value = from_instance(&instance, addr).unwrap();
+            assert_eq!(
+                Some(&Value::from(64)),
+                value.get("newtype").and_then(|v| v.get("NewType"))
+            );
+        })
+    }
+
     #[test]
     fn test_roundtrip_empty_map() {
         EMPTY_MODULE.with(|module| {
@@ -407,6 +542,44 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_roundtrip_wide_map() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+            let input = (0..2000)
+                .map(|i| (format!("key{}", i), i))
+                .collect::<HashMap<String, i64>>();
+            let addr = to_instance(&instance, &input).unwrap();
+            let loaded = from_instance(&instance, addr).unwrap();
+            assert_eq!(input, loaded);
+        })
+    }
+
+    #[test]
+    fn test_map_length_hints() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+            let cases = [
+                (None, 3),
+                (Some(3), 3),
+                (Some(5), 3),
+                (Some(2), 4),
+                (Some(3), 0),
+                (Some(0), 2),
+            ];
+            for &(hint, len) in cases.iter() {
+                let addr = to_instance(&instance, &Hinted { hint, len }).unwrap();
+                let loaded: HashMap<String, usize> = from_instance(&instance, addr).unwrap();
+                let expected = (0..len)
+                    .map(|i| (format!("key{}", i), i))
+                    .collect::<HashMap<_, _>>();
+                assert_eq!(expected, loaded, "hint {:?}, len {}", hint, len);
+            }
+        })
+    }
+
     #[test]
     fn test_roundtrip_struct() {
         EMPTY_MODULE.with(|module| {
@@ -426,6 +599,190 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_roundtrip_skipped_fields() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+            let input = Skipped {
+                name: "thename".to_string(),
+                nickname: None,
+                age: 42,
+                email: None,
+            };
+            let addr = to_instance(&instance, &input).unwrap();
+            let loaded = from_instance(&instance, addr).unwrap();
+            assert_eq!(input, loaded);
+
+            let mut expected = value::Map::new();
+            expected.insert("name".to_string(), Value::String("thename".to_string()));
+            expected.insert("age".to_string(), Value::Number(42.into()));
+            let loaded = from_instance::<Value>(&instance, addr).unwrap();
+            assert_eq!(Value::Object(expected), loaded);
+
+            let input = Skipped {
+                nickname: Some("nick".to_string()),
+                ..input
+            };
+            let addr = to_instance(&instance, &input).unwrap();
+            let loaded = from_instance(&instance, addr).unwrap();
+            assert_eq!(input, loaded);
+        })
+    }
+
+    #[test]
+    fn test_roundtrip_flatten() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+            let mut extra = HashMap::new();
+            extra.insert("age".to_string(), Value::Number(42.into()));
+            extra.insert("admin".to_string(), Value::Bool(true));
+            let input = Flattened {
+                name: "thename".to_string(),
+                extra,
+            };
+            let addr = to_instance(&instance, &input).unwrap();
+            let loaded = from_instance(&instance, addr).unwrap();
+            assert_eq!(input, loaded);
+        })
+    }
+
+    #[test]
+    fn test_max_depth() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+
+            let mut deep = Value::Null;
+            for _ in 0..DEFAULT_MAX_DEPTH + 1 {
+                deep = Value::Array(vec![deep]);
+            }
+            let addr = to_instance(&instance, &deep).unwrap();
+
+            let err = from_instance::<Value>(&instance, addr).unwrap_err();
+            assert!(matches!(err, Error::TooDeep(DEFAULT_MAX_DEPTH)));
+
+            let mut deserializer =
+                Deserializer::from_instance(&instance, addr).max_depth(DEFAULT_MAX_DEPTH + 1);
+            let loaded = Value::deserialize(&mut deserializer).unwrap();
+            assert_eq!(deep, loaded);
+
+            let mut object = value::Map::new();
+            object.insert("a".to_string(), Value::Set(Default::default()));
+            let addr = to_instance(&instance, &Value::Object(object)).unwrap();
+            let mut deserializer = Deserializer::from_instance(&instance, addr).max_depth(1);
+            let err = Value::deserialize(&mut deserializer).unwrap_err();
+            assert!(matches!(err, Error::TooDeep(1)));
+        })
+    }
+
+    #[test]
+    fn test_roundtrip_set_wrapper() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+
+            let roles = vec!["admin", "dev"]
+                .into_iter()
+                .map(ToString::to_string)
+                .collect::<HashSet<String>>();
+
+            // Plain sets are sequences to serde, so they become arrays
+            let addr = to_instance(&instance, &roles).unwrap();
+            let ty = instance.memory().get::<opa_value>(addr).unwrap().ty;
+            assert_eq!(OPA_ARRAY, ty);
+
+            let input = crate::set::Set::new(roles);
+            let addr = to_instance(&instance, &input).unwrap();
+            let ty = instance.memory().get::<opa_value>(addr).unwrap().ty;
+            assert_eq!(OPA_SET, ty);
+
+            let loaded =
+                from_instance::<crate::set::Set<HashSet<String>>>(&instance, addr).unwrap();
+            assert_eq!(input, loaded);
+        })
+    }
+
+    #[test]
+    fn test_roundtrip_bytes() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+
+            let input = Blob {
+                name: "blob".to_string(),
+                data: vec![0, 159, 146, 150, 255],
+            };
+            let addr = to_instance(&instance, &input).unwrap();
+            let loaded = from_instance(&instance, addr).unwrap();
+            assert_eq!(input, loaded);
+
+            // Bytes are stored as an OPA string, not an array of numbers
+            let data = input.data.clone();
+            let addr = to_instance(&instance, &serde_bytes::Bytes::new(&data)).unwrap();
+            let ty = instance.memory().get::<opa_value>(addr).unwrap().ty;
+            assert_eq!(OPA_STRING, ty);
+
+            // Arrays of numbers are still accepted
+            let addr = to_instance(&instance, &data).unwrap();
+            let loaded = from_instance::<serde_bytes::ByteBuf>(&instance, addr).unwrap();
+            assert_eq!(data, loaded.into_vec());
+        })
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+            let addr = to_instance(&instance, &DuplicateKeys).unwrap();
+
+            let deserialize = |policy| {
+                let mut de =
+                    Deserializer::from_instance(&instance, addr).duplicate_key_policy(policy);
+                BTreeMap::<String, i64>::deserialize(&mut de)
+            };
+
+            let last = deserialize(DuplicateKeyPolicy::LastWins).unwrap();
+            assert_eq!(Some(&3), last.get("a"));
+            assert_eq!(Some(&2), last.get("b"));
+
+            let first = deserialize(DuplicateKeyPolicy::FirstWins).unwrap();
+            assert_eq!(Some(&1), first.get("a"));
+            assert_eq!(Some(&2), first.get("b"));
+
+            let error = deserialize(DuplicateKeyPolicy::Error);
+            assert!(matches!(error, Err(Error::DuplicateKey(_))));
+
+            let default = from_instance::<BTreeMap<String, i64>>(&instance, addr).unwrap();
+            assert_eq!(last, default);
+            let value = from_instance::<Value>(&instance, addr).unwrap();
+            assert_eq!(Some(&Value::from(3)), value.lookup("a"));
+        })
+    }
+
+    #[test]
+    fn test_array_offset_overflow() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+
+            let array = opa_array_t::new(ValueAddr::from(i32::MAX - 4), i32::MAX as usize);
+            let addr = instance
+                .functions()
+                .malloc(mem::size_of::<opa_array_t>())
+                .unwrap();
+            instance.memory().set(addr, &array).unwrap();
+
+            let loaded = from_instance::<Vec<i64>>(&instance, addr);
+            assert!(loaded.is_err());
+
+            let overflow = elem_addr(ValueAddr::from(i32::MAX - 4), 1, 8);
+            assert!(matches!(overflow, Err(Error::OutOfBounds)));
+        })
+    }
+
     #[test]
     fn test_roundtrip_unit() {
         EMPTY_MODULE.with(|module| {
@@ -469,34 +826,266 @@ mod tests {
         })
     }
 
-    fn arb_number() -> impl Strategy<Value = Number> {
-        prop_oneof![
-            prop::num::i64::ANY.prop_map(Number::from),
-            prop::num::i64::ANY.prop_map(|i| Number::from(i.to_string())),
-            prop::num::f64::ANY.prop_map(Number::from),
-            prop::num::f64::ANY.prop_map(|f| Number::from(f.to_string())),
-        ]
-    }
-
-    fn arb_value() -> impl Strategy<Value = Value> {
-        let leaf = prop_oneof![
-            Just(Value::Null),
-            any::<bool>().prop_map(Value::Bool),
-            arb_number().prop_map(Value::Number),
-            ".*".prop_map(Value::String),
-        ];
-        leaf.prop_recursive(
-            8,   // 8 levels deep
-            256, // Shoot for maximum size of 256 nodes
-            10,  // We put up to 10 items per collection
-            |inner| {
-                prop_oneof![
-                    prop::collection::vec(inner.clone(), 0..10).prop_map(Value::Array),
-                    prop::collection::btree_map(".*", inner.clone(), 0..10).prop_map(Value::Object),
-                    prop::collection::btree_set(inner.clone(), 0..10).prop_map(Value::Set),
-                ]
-            },
-        )
+    #[test]
+    fn test_roundtrip_chars() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+
+            // 1 to 4 byte utf8 encodings
+            let input = "aZ0 é☃€😀\u{10ffff}"
+                .chars()
+                .cycle()
+                .take(1000)
+                .collect::<Vec<char>>();
+            let addr = to_instance(&instance, &input).unwrap();
+            let loaded: Vec<char> = from_instance(&instance, addr).unwrap();
+            assert_eq!(input, loaded);
+
+            let loaded: Vec<String> = from_instance(&instance, addr).unwrap();
+            assert_eq!("😀", loaded[7]);
+        })
+    }
+
+    #[test]
+    fn test_serialize_non_finite() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+
+            for f in &[f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+                let err = to_instance(&instance, f).unwrap_err();
+                assert!(matches!(err, Error::NonFiniteNumber(_)));
+
+                let err = to_instance(&instance, &Value::Number(Number::from(*f))).unwrap_err();
+                assert!(matches!(err, Error::NonFiniteNumber(_)));
+            }
+
+            let err = to_instance(&instance, &f32::NAN).unwrap_err();
+            assert!(matches!(err, Error::NonFiniteNumber(_)));
+        })
+    }
+
+    #[test]
+    fn test_deserialize_non_finite() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+
+            for f in &[f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+                let n = opa_number_t::from_f64(*f);
+                let addr = instance.functions().malloc(mem::size_of_val(&n)).unwrap();
+                instance.memory().set(addr, &n).unwrap();
+
+                let err = from_instance::<Value>(&instance, addr).unwrap_err();
+                assert!(matches!(err, Error::NonFiniteNumber(_)));
+                let err = from_instance::<f64>(&instance, addr).unwrap_err();
+                assert!(matches!(err, Error::NonFiniteNumber(_)));
+            }
+
+            // Values that do come back are still usable as set members
+            let input = Value::Set(
+                vec![Value::from(1.5), Value::from(-0.5), Value::from(1.5)]
+                    .into_iter()
+                    .collect(),
+            );
+            let addr = to_instance(&instance, &input).unwrap();
+            let loaded = from_instance::<Value>(&instance, addr).unwrap();
+            assert_eq!(input, loaded);
+        })
+    }
+
+    #[test]
+    fn test_deserialize_i128() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+
+            let big = "-123456789012345678901234567890123456";
+            let input = Value::Number(big.parse().unwrap());
+            let addr = to_instance(&instance, &input).unwrap();
+            let loaded: i128 = from_instance(&instance, addr).unwrap();
+            assert_eq!(big.parse::<i128>().unwrap(), loaded);
+            let err = from_instance::<u128>(&instance, addr).unwrap_err();
+            assert!(matches!(err, Error::ExpectedBigInteger(_)));
+
+            let input = Value::Number(u128::MAX.to_string().parse().unwrap());
+            let addr = to_instance(&instance, &input).unwrap();
+            let loaded: u128 = from_instance(&instance, addr).unwrap();
+            assert_eq!(u128::MAX, loaded);
+
+            // 40 digits doesn't fit
+            let input = Value::Number("1234567890123456789012345678901234567890".parse().unwrap());
+            let addr = to_instance(&instance, &input).unwrap();
+            let err = from_instance::<i128>(&instance, addr).unwrap_err();
+            assert!(matches!(err, Error::ExpectedBigInteger(_)));
+
+            let addr = to_instance(&instance, &42_i64).unwrap();
+            let loaded: i128 = from_instance(&instance, addr).unwrap();
+            assert_eq!(42, loaded);
+
+            let addr = to_instance(&instance, &1.5_f64).unwrap();
+            let err = from_instance::<i128>(&instance, addr).unwrap_err();
+            assert!(matches!(err, Error::ExpectedInteger(_)));
+        })
+    }
+
+    #[test]
+    fn test_coerce_integral_floats() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+            let deserialize = |input: f64, coerce| {
+                let addr = to_instance(&instance, &input).unwrap();
+                let mut de =
+                    Deserializer::from_instance(&instance, addr).coerce_integral_floats(coerce);
+                i64::deserialize(&mut de)
+            };
+
+            let err = deserialize(3.0, false).unwrap_err();
+            assert!(matches!(err, Error::ExpectedInteger(OPA_NUMBER_REPR_FLOAT)));
+            assert_eq!(3, deserialize(3.0, true).unwrap());
+            assert_eq!(-2, deserialize(-2.0, true).unwrap());
+
+            let err = deserialize(3.5, true).unwrap_err();
+            assert!(matches!(err, Error::ExpectedInteger(_)));
+            let err = deserialize(1e19, true).unwrap_err();
+            assert!(matches!(err, Error::ExpectedInteger(_)));
+
+            // Narrower and wider types are coerced too, within their range
+            let addr = to_instance(&instance, &300.0).unwrap();
+            let mut de = Deserializer::from_instance(&instance, addr).coerce_integral_floats(true);
+            assert_eq!(300, u16::deserialize(&mut de).unwrap());
+            let mut de = Deserializer::from_instance(&instance, addr).coerce_integral_floats(true);
+            assert!(u8::deserialize(&mut de).is_err());
+            let mut de = Deserializer::from_instance(&instance, addr).coerce_integral_floats(true);
+            assert_eq!(300, i128::deserialize(&mut de).unwrap());
+
+            // Values keep their floats
+            let mut de = Deserializer::from_instance(&instance, addr).coerce_integral_floats(true);
+            assert_eq!(Value::from(300.0), Value::deserialize(&mut de).unwrap());
+        })
+    }
+
+    #[test]
+    fn test_cache_strings() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+            let deserialize = |addr, cache| {
+                let mut de = Deserializer::from_instance(&instance, addr).cache_strings(cache);
+                Value::deserialize(&mut de).unwrap()
+            };
+
+            let input: Value = serde_json::from_str(
+                r#"[{"role": "admin", "user": "alice"}, {"role": "admin", "user": "bob"}]"#,
+            )
+            .unwrap();
+            let addr = to_instance(&instance, &input).unwrap();
+            assert_eq!(input, deserialize(addr, true));
+            assert_eq!(deserialize(addr, false), deserialize(addr, true));
+
+            // Point every element at the first one's string, as OPA does for
+            // values it reuses
+            let addr = to_instance(&instance, &vec!["a", "b", "c"]).unwrap();
+            let array = instance.memory().get::<opa_array_t>(addr).unwrap();
+            let elem_size = mem::size_of::<opa_array_elem_t>();
+            let first = instance
+                .memory()
+                .get::<opa_array_elem_t>(ValueAddr::from(array.elems))
+                .unwrap();
+            for i in 1..3 {
+                let elem_addr = elem_addr(ValueAddr::from(array.elems), i, elem_size).unwrap();
+                let mut elem = instance
+                    .memory()
+                    .get::<opa_array_elem_t>(elem_addr)
+                    .unwrap();
+                elem.v = first.v;
+                instance.memory().set(elem_addr, &elem).unwrap();
+            }
+            assert_eq!(Value::from(vec!["a", "a", "a"]), deserialize(addr, true));
+            assert_eq!(deserialize(addr, false), deserialize(addr, true));
+        })
+    }
+
+    #[test]
+    fn test_check_result_set() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+
+            let mut binding = value::Map::new();
+            binding.insert("x".to_string(), Value::from(1));
+            let bindings = Value::from_set_iter(vec![Value::Object(binding)]);
+            let addr = to_instance(&instance, &bindings).unwrap();
+            check_result_set(&instance, addr).unwrap();
+            let addr = to_instance(&instance, &Value::from_set_iter(Vec::<Value>::new())).unwrap();
+            check_result_set(&instance, addr).unwrap();
+
+            let addr = to_instance(&instance, &Value::from(vec![1])).unwrap();
+            let err = check_result_set(&instance, addr).unwrap_err();
+            assert_eq!(
+                "Malformed result: expected a set of bindings, found array (type 5).",
+                err.to_string()
+            );
+
+            let addr = to_instance(&instance, &Value::from_set_iter(vec![1])).unwrap();
+            let err = check_result_set(&instance, addr).unwrap_err();
+            assert!(matches!(err, Error::MalformedResult(_)));
+            assert!(err.to_string().contains("containing number"));
+
+            let err = check_result_set(&instance, ValueAddr(0)).unwrap_err();
+            assert!(matches!(err, Error::MalformedResult(_)));
+            let err = crate::Error::from_result(err);
+            assert!(matches!(err, crate::Error::MalformedResult(_)));
+        })
+    }
+
+    #[test]
+    fn test_result_binding() {
+        EMPTY_MODULE.with(|module| {
+            let memory = Memory::from_module(module);
+            let instance = Instance::new(module, memory).unwrap();
+
+            let empty = to_instance(&instance, &Value::Set(value::Set::new())).unwrap();
+            assert_eq!(None, result_binding(&instance, empty).unwrap());
+
+            let mut binding = value::Map::new();
+            binding.insert("result".to_string(), Value::Bool(true));
+            let mut results = value::Set::new();
+            results.insert(Value::Object(binding.clone()));
+            let addr = to_instance(&instance, &Value::Set(results)).unwrap();
+            let result = result_binding(&instance, addr).unwrap().unwrap();
+            assert_eq!(Value::Bool(true), from_instance(&instance, result).unwrap());
+
+            binding.insert("x".to_string(), Value::Null);
+            let mut results = value::Set::new();
+            results.insert(Value::Object(binding.clone()));
+            let addr = to_instance(&instance, &Value::Set(results)).unwrap();
+            let result = result_binding(&instance, addr).unwrap().unwrap();
+            let loaded: Value = from_instance(&instance, result).unwrap();
+            assert_eq!(Value::Object(binding), loaded);
+
+            let not_set = to_instance(&instance, &Value::Null).unwrap();
+            assert!(result_binding(&instance, not_set).is_err());
+
+            let mut results = value::Set::new();
+            for x in 0..3 {
+                let mut binding = value::Map::new();
+                binding.insert("x".to_string(), Value::from(x));
+                results.insert(Value::Object(binding));
+            }
+            let addr = to_instance(&instance, &Value::Set(results)).unwrap();
+            let loaded = result_bindings(&instance, addr)
+                .unwrap()
+                .into_iter()
+                .map(|addr| from_instance(&instance, addr).unwrap())
+                .collect::<Vec<Value>>();
+            assert_eq!(vec![Value::from(0), Value::from(1), Value::from(2)], loaded);
+
+            assert!(result_bindings(&instance, empty).unwrap().is_empty());
+            assert!(result_bindings(&instance, not_set).is_err());
+        })
     }
 
     proptest! {
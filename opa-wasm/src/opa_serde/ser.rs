@@ -26,10 +26,16 @@ pub struct Serializer<'i> {
 
 impl<'i> Serializer<'i> {
     fn alloc(&self, size: usize) -> Result<ValueAddr> {
-        self.instance
+        let addr = self
+            .instance
             .functions()
             .malloc(size)
-            .map_err(|e| Error::Alloc(Box::new(e)))
+            .map_err(|e| Error::Alloc(Box::new(e)))?;
+        self.instance
+            .memory()
+            .check_range(addr, size)
+            .map_err(|e| Error::Alloc(Box::new(e)))?;
+        Ok(addr)
     }
 
     fn memset(&self, addr: ValueAddr, bytes: &[u8]) -> Result<()> {
@@ -99,11 +105,16 @@ impl<'a, 'i> ser::Serializer for &'a mut Serializer<'i> {
     }
 
     fn serialize_f64(self, v: f64) -> Result<ValueAddr> {
+        if !v.is_finite() {
+            return Err(Error::NonFiniteNumber(v));
+        }
         self.store(&opa_number_t::from_f64(v))
     }
 
     fn serialize_char(self, v: char) -> Result<ValueAddr> {
-        self.serialize_str(&v.to_string())
+        // A char is at most 4 bytes of utf8, so skip the String allocation
+        let mut buf = [0; 4];
+        self.serialize_str(v.encode_utf8(&mut buf))
     }
 
     fn serialize_str(self, v: &str) -> Result<ValueAddr> {
@@ -112,13 +123,13 @@ impl<'a, 'i> ser::Serializer for &'a mut Serializer<'i> {
         self.store(&s)
     }
 
+    // OPA strings are byte sequences, so bytes are stored as a string rather
+    // than an array of numbers. This is much more compact, but policies see a
+    // string, and bytes that aren't valid utf8 can only be read back as bytes.
     fn serialize_bytes(self, v: &[u8]) -> Result<ValueAddr> {
-        use serde::ser::SerializeSeq;
-        let mut seq = self.serialize_seq(Some(v.len()))?;
-        for byte in v {
-            seq.serialize_element(byte)?;
-        }
-        seq.end()
+        let data_addr = self.store(v)?;
+        let s = opa_string_t::from_bytes(v, data_addr);
+        self.store(&s)
     }
 
     fn serialize_none(self) -> Result<ValueAddr> {
@@ -205,18 +216,21 @@ impl<'a, 'i> ser::Serializer for &'a mut Serializer<'i> {
         Ok(serializer)
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        let serializer = ObjectSerializer::from_serializer(self)?;
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        let serializer = ObjectSerializer::with_len(self, len.unwrap_or(0))?;
         Ok(serializer)
     }
 
-    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+    // len counts fields skipped with skip_serializing_if. Their preallocated
+    // entries are left unused and unlinked, so skipped fields never appear in
+    // the object.
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
         let serializer = if name == set::TOKEN {
             StructSerializer::Set(self, None)
         } else if name == number::TOKEN {
             StructSerializer::NumberRef(self, None)
         } else {
-            StructSerializer::Object(ObjectSerializer::from_serializer(self)?)
+            StructSerializer::Object(ObjectSerializer::with_len(self, len)?)
         };
         Ok(serializer)
     }
@@ -278,10 +292,12 @@ impl<'i, 'a> ser::SerializeSeq for ArraySerializer<'a, 'i> {
             i: i_addr.0 as intptr_t,
             v: v_addr.0 as intptr_t,
         };
-        self.ser.memset(
-            self.elems_addr + self.count * mem::size_of::<opa_array_elem_t>(),
-            elem.as_bytes(),
+        let addr = elem_addr(
+            self.elems_addr,
+            self.count,
+            mem::size_of::<opa_array_elem_t>(),
         )?;
+        self.ser.memset(addr, elem.as_bytes())?;
 
         // bump the count for the next element
         self.count = self.count + 1;
@@ -474,13 +490,33 @@ pub struct ObjectSerializer<'a, 'i: 'a> {
     ser: &'a mut Serializer<'i>,
     addr: ValueAddr,
     elem: opa_object_elem_t,
-    prev_elem: ValueAddr,
-    first: bool,
+    // The last entry written, kept so the next one can be linked to it
+    // without reading it back from memory.
+    prev: Option<(ValueAddr, opa_object_elem_t)>,
+    // Entries allocated up front for a known length, each already linked to
+    // the next.
+    slots: ValueAddr,
+    len: usize,
+    count: usize,
 }
 
 impl<'a, 'i: 'a> ObjectSerializer<'a, 'i> {
     pub fn from_serializer(ser: &'a mut Serializer<'i>) -> Result<Self> {
-        let obj = opa_object_t::new(ValueAddr(0));
+        Self::with_len(ser, 0)
+    }
+
+    // Allocates len entries contiguously, so writing each of the first len
+    // entries is a single store. Entries past len are allocated one at a time.
+    pub fn with_len(ser: &'a mut Serializer<'i>, len: usize) -> Result<Self> {
+        let slots = if len > 0 {
+            let size = len
+                .checked_mul(mem::size_of::<opa_object_elem_t>())
+                .ok_or(Error::OutOfBounds)?;
+            ser.alloc(size)?
+        } else {
+            ValueAddr(0)
+        };
+        let obj = opa_object_t::new(slots);
         let addr = ser.store(&obj)?;
 
         let elem = opa_object_elem_t {
@@ -492,11 +528,17 @@ impl<'a, 'i: 'a> ObjectSerializer<'a, 'i> {
             ser,
             addr,
             elem,
-            prev_elem: addr,
-            first: true,
+            prev: None,
+            slots,
+            len,
+            count: 0,
         };
         Ok(serializer)
     }
+
+    fn slot(&self, index: usize) -> Result<ValueAddr> {
+        elem_addr(self.slots, index, mem::size_of::<opa_object_elem_t>())
+    }
 }
 
 // Some `Serialize` types are not able to hold a key and value in memory at the
@@ -544,36 +586,57 @@ impl<'i, 'a> ser::SerializeMap for ObjectSerializer<'a, 'i> {
         // update the current entry's pointer to this value
         self.elem.v = v_addr.0 as intptr_t;
 
-        // store this entry
-        let elem_addr = self.ser.store(&self.elem)?;
-
-        if self.first {
-            let mut prev_elem = self
-                .ser
-                .instance
-                .memory()
-                .get::<opa_object_t>(self.prev_elem)?;
-            prev_elem.head = elem_addr.0 as intptr_t;
-            self.ser.instance.memory().set(self.prev_elem, &prev_elem)?;
+        // store this entry, in its preallocated slot if there is one
+        let presized = self.count < self.len;
+        let elem_addr = if presized {
+            if self.count + 1 < self.len {
+                self.elem.next = self.slot(self.count + 1)?.0 as intptr_t;
+            }
+            let addr = self.slot(self.count)?;
+            self.ser.memset(addr, self.elem.as_bytes())?;
+            addr
         } else {
-            let mut prev_elem = self
-                .ser
-                .instance
-                .memory()
-                .get::<opa_object_elem_t>(self.prev_elem)?;
-            prev_elem.next = elem_addr.0 as intptr_t;
-            self.ser.instance.memory().set(self.prev_elem, &prev_elem)?;
+            self.ser.store(&self.elem)?
+        };
+
+        // slots are linked in advance, anything else is linked to the
+        // previous entry or the object's head
+        if !presized {
+            match self.prev {
+                Some((prev_addr, mut prev)) => {
+                    prev.next = elem_addr.0 as intptr_t;
+                    self.ser.memset(prev_addr, prev.as_bytes())?;
+                }
+                None => {
+                    let obj = opa_object_t::new(elem_addr);
+                    self.ser.memset(self.addr, obj.as_bytes())?;
+                }
+            }
         }
 
-        self.first = false;
-        self.prev_elem = elem_addr;
+        self.prev = Some((elem_addr, self.elem));
+        self.count += 1;
         self.elem.k = 0;
         self.elem.v = 0;
         self.elem.next = 0;
         Ok(())
     }
 
+    // Fewer entries than slots, e.g. from skipped struct fields, leaves the
+    // last entry linked to an unused slot, so the list is cut short there.
     fn end(self) -> Result<ValueAddr> {
+        if self.count < self.len {
+            match self.prev {
+                Some((prev_addr, mut prev)) => {
+                    prev.next = 0;
+                    self.ser.memset(prev_addr, prev.as_bytes())?;
+                }
+                None => {
+                    let obj = opa_object_t::new(ValueAddr(0));
+                    self.ser.memset(self.addr, obj.as_bytes())?;
+                }
+            }
+        }
         Ok(self.addr)
     }
 }
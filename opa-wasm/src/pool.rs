@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Error, Policy, PolicyConfig};
+
+thread_local! {
+    // Every thread belongs to a single pool, so it holds at most one policy.
+    static POLICY: RefCell<Option<Policy>> = RefCell::new(None);
+}
+
+/// Evaluates batches of inputs in parallel, with one policy per thread.
+///
+/// Policies can't be sent between threads, so each of the pool's threads
+/// loads the module itself the first time it's used, then keeps its policy
+/// for later batches. The policies are dropped with the pool.
+pub struct PolicyPool {
+    wasm: Arc<Vec<u8>>,
+    config: PolicyConfig,
+    threads: ThreadPool,
+}
+
+impl PolicyPool {
+    /// Creates a pool of `size` threads evaluating the module, or one per
+    /// CPU when `size` is 0.
+    pub fn new<B: Into<Vec<u8>>>(bytes: B, size: usize) -> Result<Self, Error> {
+        Self::with_config(bytes, PolicyConfig::default(), size)
+    }
+
+    /// Like `new`, creating each thread's policy with `config`.
+    pub fn with_config<B: Into<Vec<u8>>>(
+        bytes: B,
+        config: PolicyConfig,
+        size: usize,
+    ) -> Result<Self, Error> {
+        let wasm = bytes.into();
+        // Fail here, rather than on every thread, if the module is invalid
+        Policy::with_config(Arc::new(config.compile(&wasm)?), config.clone())?;
+
+        let threads = ThreadPoolBuilder::new()
+            .num_threads(size)
+            .thread_name(|i| format!("opa-wasm-pool-{}", i))
+            .build()
+            .map_err(Error::ThreadPool)?;
+        let pool = PolicyPool {
+            wasm: Arc::new(wasm),
+            config,
+            threads,
+        };
+        Ok(pool)
+    }
+
+    /// Evaluates every input and returns the results in the same order.
+    ///
+    /// The inputs are spread across the pool's threads. Each evaluation
+    /// resets its policy's heap first, like `Policy::evaluate`, so inputs
+    /// don't see each other. Fails with the first error, in input order, and
+    /// with `Error::Cancelled` once `PolicyConfig::cancel` is set.
+    pub fn evaluate_par<T, R>(&self, inputs: &[T]) -> Result<Vec<R>, Error>
+    where
+        T: Serialize + Sync,
+        R: DeserializeOwned + Send,
+    {
+        self.threads.install(|| {
+            inputs
+                .par_iter()
+                .map(|input| self.evaluate_local(input))
+                .collect()
+        })
+    }
+
+    fn evaluate_local<T, R>(&self, input: &T) -> Result<R, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        POLICY.with(|policy| {
+            let mut policy = policy.borrow_mut();
+            if policy.is_none() {
+                let module = self.config.compile(&self.wasm)?;
+                *policy = Some(Policy::with_config(Arc::new(module), self.config.clone())?);
+            }
+
+            let policy = policy.as_mut().expect("thread policy");
+            policy.check_cancelled()?;
+            let addr = policy.evaluate_addr(input)?;
+            policy.deserialize_addr(addr)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::Value;
+
+    use super::*;
+
+    #[test]
+    fn test_evaluate_par_empty() {
+        let bytes = fs::read("tests/empty.wasm").unwrap();
+        let pool = PolicyPool::new(bytes.clone(), 2).unwrap();
+        let mut policy = Policy::from_wasm(&bytes).unwrap();
+
+        let inputs = (0..20).collect::<Vec<i64>>();
+        let results: Vec<Value> = pool.evaluate_par(&inputs).unwrap();
+        assert_eq!(inputs.len(), results.len());
+        for (input, result) in inputs.iter().zip(results) {
+            assert_eq!(policy.evaluate(input).unwrap(), result);
+        }
+
+        assert!(pool.evaluate_par::<i64, Value>(&[]).unwrap().is_empty());
+        assert!(PolicyPool::new(b"not wasm".to_vec(), 2).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_par_cancelled() {
+        let bytes = fs::read("tests/empty.wasm").unwrap();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let config = PolicyConfig {
+            cancel: Some(cancel.clone()),
+            ..PolicyConfig::default()
+        };
+        let pool = PolicyPool::with_config(bytes, config, 2).unwrap();
+
+        let inputs = (0..20).collect::<Vec<i64>>();
+        assert!(pool.evaluate_par::<i64, Value>(&inputs).is_ok());
+        cancel.store(true, Ordering::SeqCst);
+        let err = pool.evaluate_par::<i64, Value>(&inputs).unwrap_err();
+        assert!(matches!(err, Error::Cancelled));
+    }
+}
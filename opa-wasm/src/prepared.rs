@@ -0,0 +1,36 @@
+use crate::ValueAddr;
+
+/// An input serialized once with `Policy::prepare_input`, which can then be
+/// evaluated any number of times with `Policy::evaluate_prepared`.
+///
+/// Like a `DataHandle`, the serialized input contains absolute addresses, so
+/// it is only valid while the policy's data ends where it did when the input
+/// was prepared. Changing the data invalidates it.
+#[derive(Clone, Debug)]
+pub struct PreparedInput {
+    start: ValueAddr,
+    input_addr: ValueAddr,
+    bytes: Vec<u8>,
+}
+
+impl PreparedInput {
+    pub(crate) fn new(start: ValueAddr, input_addr: ValueAddr, bytes: Vec<u8>) -> Self {
+        PreparedInput {
+            start,
+            input_addr,
+            bytes,
+        }
+    }
+
+    pub(crate) fn start(&self) -> ValueAddr {
+        self.start
+    }
+
+    pub(crate) fn input_addr(&self) -> ValueAddr {
+        self.input_addr
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
@@ -0,0 +1,95 @@
+//! Builds the input HTTP authorization policies commonly expect.
+//!
+//! OPA's HTTP API examples take the request as
+//! `{"method": "GET", "path": ["finance", "salary", "alice"], "user": "alice"}`,
+//! with the path split into its segments. `RequestInput` builds that shape,
+//! plus any extra fields the policy reads.
+//!
+//! ```ignore
+//! let input = RequestInput::new("GET", "/finance/salary/alice").user("alice");
+//! policy.evaluate(&input)?;
+//! ```
+
+use serde::{Serialize, Serializer};
+
+use crate::value::Map;
+use crate::Value;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestInput(Map<String, Value>);
+
+impl RequestInput {
+    /// Starts an input for a request. The method is upper cased and the path
+    /// split on `/`, dropping empty segments, so `/users/` becomes
+    /// `["users"]`.
+    pub fn new(method: &str, path: &str) -> Self {
+        let path = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(Value::from)
+            .collect::<Vec<_>>();
+
+        let mut input = Map::new();
+        input.insert(
+            "method".to_string(),
+            Value::String(method.to_ascii_uppercase()),
+        );
+        input.insert("path".to_string(), Value::Array(path));
+        RequestInput(input)
+    }
+
+    pub fn user(self, user: &str) -> Self {
+        self.field("user", user)
+    }
+
+    /// Adds any other field, such as `"roles"` or `"headers"`, replacing one
+    /// set before.
+    pub fn field<V: Into<Value>>(mut self, key: &str, value: V) -> Self {
+        self.0.insert(key.to_string(), value.into());
+        self
+    }
+
+    pub fn into_value(self) -> Value {
+        Value::Object(self.0)
+    }
+}
+
+impl From<RequestInput> for Value {
+    fn from(input: RequestInput) -> Value {
+        input.into_value()
+    }
+}
+
+impl Serialize for RequestInput {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_input() {
+        let input = RequestInput::new("get", "/finance/salary//alice/")
+            .user("alice")
+            .field("roles", vec!["employee"]);
+        let expected = serde_json::from_str::<Value>(
+            r#"{
+                "method": "GET",
+                "path": ["finance", "salary", "alice"],
+                "user": "alice",
+                "roles": ["employee"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(expected, input.clone().into_value());
+
+        let json = serde_json::to_value(&input).unwrap();
+        assert_eq!(serde_json::Value::from(expected), json);
+
+        let root = RequestInput::new("POST", "/").into_value();
+        assert_eq!(Some(&Value::Array(vec![])), root.get("path"));
+    }
+}
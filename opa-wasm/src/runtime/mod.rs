@@ -1,7 +1,15 @@
-use std::mem;
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::rc::Rc;
 use std::sync::Arc;
+use std::{mem, ptr};
 
-use crate::{Error, ValueAddr};
+use crate::{Error, Metrics, ValueAddr};
+
+// Values are read from wasm memory by reinterpreting the bytes in place,
+// which is only correct when the host shares wasm's little-endian layout.
+#[cfg(target_endian = "big")]
+compile_error!("opa-wasm requires a little-endian host");
 
 #[cfg(target_arch = "x86_64")]
 mod wasmtime;
@@ -21,6 +29,164 @@ use self::wasmtime::FunctionsImpl;
 #[cfg(not(target_arch = "x86_64"))]
 use self::wasmi::FunctionsImpl;
 
+const SNIPPET_LEN: usize = 64;
+const PAGE_SIZE: usize = 65536;
+
+// Memory starts with this many pages, or the module's declared minimum if
+// that's larger, so a cap below it has no effect.
+pub const INITIAL_PAGES: usize = 5;
+
+// The limits a module declares for the memory it imports, in pages. The
+// memory given to it must have at least the minimum and at most the maximum.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoryImport {
+    min: usize,
+    max: Option<usize>,
+}
+
+impl MemoryImport {
+    fn new(min: usize, max: Option<usize>) -> Result<Self, Error> {
+        let import = MemoryImport { min, max };
+        match max {
+            Some(max_pages) if max_pages < import.initial_pages() => {
+                Err(Error::MemoryImportTooSmall {
+                    max_pages,
+                    initial_pages: import.initial_pages(),
+                })
+            }
+            _ => Ok(import),
+        }
+    }
+
+    fn initial_pages(&self) -> usize {
+        self.min.max(INITIAL_PAGES)
+    }
+
+    // The most pages the memory may grow to: the caller's cap, raised to the
+    // initial size, and lowered to the module's maximum.
+    fn max_pages(&self, cap: Option<usize>) -> Option<usize> {
+        let cap = cap.map(|cap| cap.max(self.initial_pages()));
+        match (cap, self.max) {
+            (Some(cap), Some(max)) => Some(cap.min(max)),
+            (cap, max) => cap.or(max),
+        }
+    }
+}
+
+// The wasm malloc doesn't check whether growing the memory succeeded, so an
+// allocation past a capped memory comes back as an address beyond its end.
+fn check_limit(
+    addr: ValueAddr,
+    len: usize,
+    pages: usize,
+    max_pages: Option<usize>,
+) -> Result<(), Error> {
+    let end = match usize::try_from(addr.0)
+        .ok()
+        .and_then(|a| a.checked_add(len))
+    {
+        Some(end) => end,
+        None => return Err(Error::OutOfBounds(addr)),
+    };
+    let requested_pages = (end + PAGE_SIZE - 1) / PAGE_SIZE;
+    match max_pages {
+        Some(max_pages) if requested_pages > pages && requested_pages > max_pages => {
+            Err(Error::MemoryLimitExceeded {
+                requested_pages,
+                max_pages,
+            })
+        }
+        _ if requested_pages > pages => Err(Error::OutOfBounds(addr)),
+        _ => Ok(()),
+    }
+}
+
+// Counts how often a memory grew, and the most pages it reached, since the
+// last reset. Every clone of a memory shares the same counter. Memory never
+// shrinks, so the peak is also the size last seen.
+#[derive(Clone, Debug, Default)]
+pub struct GrowthCounter(Rc<Cell<Metrics>>);
+
+impl GrowthCounter {
+    pub fn reset(&self, pages: usize) {
+        self.0.set(Metrics {
+            memory_grows: 0,
+            peak_pages: pages,
+        });
+    }
+
+    pub fn get(&self) -> Metrics {
+        self.0.get()
+    }
+
+    fn observe(&self, pages: usize) {
+        let mut metrics = self.0.get();
+        if pages > metrics.peak_pages {
+            metrics.memory_grows += 1;
+            metrics.peak_pages = pages;
+        }
+        self.0.set(metrics);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Fuel {
+    left: Option<u64>,
+    exhausted: bool,
+}
+
+// The fuel left for a metered module, shared with the instance's gas
+// import. A metered module calls the import with the cost of each block
+// before running it, and the call traps once there isn't enough left.
+#[derive(Clone, Debug, Default)]
+pub struct FuelCounter(Rc<Cell<Fuel>>);
+
+impl FuelCounter {
+    // Sets the fuel left, None for no limit.
+    pub fn reset(&self, fuel: Option<u64>) {
+        self.0.set(Fuel {
+            left: fuel,
+            exhausted: false,
+        });
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.0.get().exhausted
+    }
+
+    // Returns false, and the call should trap, when there isn't enough left
+    fn consume(&self, cost: u32) -> bool {
+        let mut fuel = self.0.get();
+        match fuel.left {
+            Some(left) if left < u64::from(cost) => {
+                fuel.left = Some(0);
+                fuel.exhausted = true;
+            }
+            Some(left) => fuel.left = Some(left - u64::from(cost)),
+            None => (),
+        }
+        self.0.set(fuel);
+        !fuel.exhausted
+    }
+}
+
+// Adds an env.gas import to the module and calls it at the start of every
+// block, with a cost of one per instruction.
+fn meter(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let module = parity_wasm::deserialize_buffer::<parity_wasm::elements::Module>(bytes)
+        .map_err(|e| Error::Metering(e.to_string()))?;
+    let module = pwasm_utils::inject_gas_counter(module, &pwasm_utils::rules::Set::default())
+        .map_err(|_| Error::Metering("failed to inject the gas counter".to_string()))?;
+    parity_wasm::serialize(module).map_err(|e| Error::Metering(e.to_string()))
+}
+
+fn snippet(json: &str) -> String {
+    match json.char_indices().nth(SNIPPET_LEN) {
+        Some((end, _)) => format!("{}...", &json[..end]),
+        None => json.to_string(),
+    }
+}
+
 pub trait AsBytes {
     fn as_bytes(&self) -> &[u8];
 }
@@ -59,28 +225,85 @@ pub unsafe trait FromBytes: Sized + Copy {
             return Err(Error::NotEnoughData(mem::size_of::<Self>(), bytes.len()));
         }
 
-        let bytes_ptr = bytes.as_ptr();
-        let struct_ptr = bytes_ptr as *const Self;
-        let struct_ref = unsafe { *struct_ptr };
-        Ok(struct_ref)
+        // Memory::get slices at arbitrary offsets, so the pointer may not be
+        // aligned for Self.
+        let struct_ptr = bytes.as_ptr() as *const Self;
+        let value = unsafe { ptr::read_unaligned(struct_ptr) };
+        Ok(value)
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Functions {
     inner: Arc<FunctionsImpl>,
+    memory: Memory,
 }
 
 impl Functions {
-    pub fn from_impl(inner: FunctionsImpl) -> Result<Self, Error> {
+    pub fn from_impl(inner: FunctionsImpl, memory: Memory) -> Result<Self, Error> {
         let f = Self {
             inner: Arc::new(inner),
+            memory,
         };
         Ok(f)
     }
 
-    pub fn builtins(&self) -> Result<ValueAddr, Error> {
+    // The wasm code grows the memory itself, so growth is noticed by
+    // checking the size after each call that may allocate. Calls nested in
+    // an eval, from builtins, are checked too without counting twice.
+    fn track_growth<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&FunctionsImpl) -> Result<T, Error>,
+    {
+        let result = f(&self.inner);
+        self.memory.growth().observe(self.memory.pages());
+        result
+    }
+
+    pub fn builtins(&self) -> Result<Option<ValueAddr>, Error> {
         let addr = self.inner.builtins()?;
+        Ok(addr.map(ValueAddr::from))
+    }
+
+    pub fn entrypoints(&self) -> Result<Option<ValueAddr>, Error> {
+        let addr = self.inner.entrypoints()?;
+        Ok(addr.map(ValueAddr::from))
+    }
+
+    pub fn has_value_paths(&self) -> bool {
+        self.inner.has_value_paths()
+    }
+
+    pub fn value_add_path(
+        &self,
+        base: ValueAddr,
+        path: ValueAddr,
+        value: ValueAddr,
+    ) -> Result<i32, Error> {
+        self.inner.opa_value_add_path(base.0, path.0, value.0)
+    }
+
+    pub fn value_remove_path(&self, base: ValueAddr, path: ValueAddr) -> Result<i32, Error> {
+        self.inner.opa_value_remove_path(base.0, path.0)
+    }
+
+    pub fn has_opa_eval(&self) -> bool {
+        self.inner.has_opa_eval()
+    }
+
+    // Evaluates the raw JSON input at input in one call, returning the
+    // address of the result set as a nul terminated JSON string.
+    pub fn opa_eval(
+        &self,
+        entrypoint: i32,
+        data: ValueAddr,
+        input: ValueAddr,
+        input_len: usize,
+        heap_ptr: ValueAddr,
+    ) -> Result<ValueAddr, Error> {
+        let addr = self.track_growth(|inner| {
+            inner.opa_eval(entrypoint, data.0, input.0, input_len as i32, heap_ptr.0)
+        })?;
         Ok(addr.into())
     }
 
@@ -99,8 +322,18 @@ impl Functions {
         Ok(())
     }
 
+    // Selects the entrypoint the context evaluates. Modules compiled from a
+    // single query don't export this and only have entrypoint 0, which
+    // contexts start with.
+    pub fn eval_ctx_set_entrypoint(&self, ctx: ValueAddr, entrypoint: i32) -> Result<(), Error> {
+        if entrypoint == 0 && !self.inner.has_eval_ctx_set_entrypoint() {
+            return Ok(());
+        }
+        self.inner.opa_eval_ctx_set_entrypoint(ctx.0, entrypoint)
+    }
+
     pub fn eval(&self, ctx: ValueAddr) -> Result<(), Error> {
-        self.inner.eval(ctx.0)?;
+        self.track_growth(|inner| inner.eval(ctx.0))?;
         Ok(())
     }
 
@@ -130,7 +363,151 @@ impl Functions {
     }
 
     pub fn malloc(&self, len: usize) -> Result<ValueAddr, Error> {
-        let addr = self.inner.opa_malloc(len as i32)?;
+        let addr = self.track_growth(|inner| inner.opa_malloc(len as i32))?;
         Ok(addr.into())
     }
+
+    pub fn json_dump(&self, addr: ValueAddr) -> Result<ValueAddr, Error> {
+        let raw_addr = self.track_growth(|inner| inner.opa_json_dump(addr.0))?;
+        Ok(raw_addr.into())
+    }
+
+    pub fn json_parse(&self, addr: ValueAddr, json: &str) -> Result<ValueAddr, Error> {
+        let parsed = self.track_growth(|inner| inner.opa_json_parse(addr.0, json.len() as i32))?;
+        if parsed == 0 {
+            return Err(Error::JsonParse(snippet(json)));
+        }
+        Ok(parsed.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    struct Pair {
+        a: i32,
+        b: u64,
+    }
+
+    unsafe impl FromBytes for Pair {}
+
+    #[test]
+    fn test_from_bytes_unaligned() {
+        let expected = Pair {
+            a: -7,
+            b: 0x0102_0304_0506_0708,
+        };
+        let mut bytes = vec![0u8; Pair::len() + 1];
+        unsafe {
+            ptr::write_unaligned(bytes[1..].as_mut_ptr() as *mut Pair, expected);
+        }
+
+        assert_eq!(expected, Pair::from_bytes(&bytes[1..]).unwrap());
+        assert!(Pair::from_bytes(&bytes[1..Pair::len()]).is_err());
+    }
+
+    #[test]
+    fn test_get_bytes_out_of_bounds() {
+        let bytes = std::fs::read("tests/empty.wasm").unwrap();
+        let module = Module::from_bytes(bytes).unwrap();
+        let memory = Memory::from_module(&module);
+        let size = memory.pages() * PAGE_SIZE;
+
+        assert_eq!(4, memory.get_bytes(ValueAddr(0), 4).unwrap().len());
+        assert_eq!(
+            4,
+            memory
+                .get_bytes(ValueAddr(size as i32 - 4), 4)
+                .unwrap()
+                .len()
+        );
+
+        let err = memory.get_bytes(ValueAddr(size as i32 - 4), 5).unwrap_err();
+        assert!(matches!(err, Error::OutOfBounds(_)));
+        let err = memory
+            .get_bytes(ValueAddr(16), usize::max_value())
+            .unwrap_err();
+        assert!(matches!(err, Error::OutOfBounds(_)));
+    }
+
+    #[test]
+    fn test_set_bounds() {
+        let bytes = std::fs::read("tests/empty.wasm").unwrap();
+        let module = Module::from_bytes(bytes).unwrap();
+        let memory = Memory::from_module(&module);
+        let size = memory.pages() * PAGE_SIZE;
+
+        let end = ValueAddr(size as i32 - 4);
+        memory.set(end, &&[1u8, 2, 3, 4][..]).unwrap();
+        assert_eq!(vec![1, 2, 3, 4], memory.get_bytes(end, 4).unwrap());
+
+        let err = memory.set(ValueAddr(size as i32 - 2), &&[0u8; 4][..]);
+        assert!(matches!(err, Err(Error::OutOfBounds(_))));
+        let err = memory.set(ValueAddr(-1), &&[0u8][..]);
+        assert!(matches!(err, Err(Error::OutOfBounds(_))));
+
+        // Failed writes leave the memory untouched
+        assert_eq!(vec![1, 2, 3, 4], memory.get_bytes(end, 4).unwrap());
+    }
+
+    #[test]
+    fn test_memory_import() {
+        let import = MemoryImport::default();
+        assert_eq!(INITIAL_PAGES, import.initial_pages());
+        assert_eq!(None, import.max_pages(None));
+        assert_eq!(Some(INITIAL_PAGES), import.max_pages(Some(1)));
+
+        let import = MemoryImport::new(10, None).unwrap();
+        assert_eq!(10, import.initial_pages());
+        assert_eq!(Some(10), import.max_pages(Some(8)));
+        assert_eq!(Some(20), import.max_pages(Some(20)));
+
+        let import = MemoryImport::new(2, Some(16)).unwrap();
+        assert_eq!(INITIAL_PAGES, import.initial_pages());
+        assert_eq!(Some(16), import.max_pages(None));
+        assert_eq!(Some(8), import.max_pages(Some(8)));
+        assert_eq!(Some(16), import.max_pages(Some(32)));
+
+        let err = MemoryImport::new(2, Some(3)).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::MemoryImportTooSmall {
+                max_pages: 3,
+                initial_pages: INITIAL_PAGES,
+            }
+        ));
+    }
+
+    #[test]
+    fn test_fuel_counter() {
+        let fuel = FuelCounter::default();
+        assert!(fuel.consume(u32::max_value()));
+
+        fuel.reset(Some(10));
+        assert!(fuel.consume(4));
+        assert!(fuel.consume(6));
+        assert!(!fuel.exhausted());
+        assert!(!fuel.consume(1));
+        assert!(fuel.exhausted());
+
+        fuel.reset(None);
+        assert!(!fuel.exhausted());
+        assert!(fuel.consume(1));
+    }
+
+    #[test]
+    fn test_snippet() {
+        assert_eq!("{\"a\": tru", snippet("{\"a\": tru"));
+
+        let long = "x".repeat(100);
+        let expected = format!("{}...", "x".repeat(SNIPPET_LEN));
+        assert_eq!(expected, snippet(&long));
+
+        let wide = "é".repeat(100);
+        let expected = format!("{}...", "é".repeat(SNIPPET_LEN));
+        assert_eq!(expected, snippet(&wide));
+    }
 }
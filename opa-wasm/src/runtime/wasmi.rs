@@ -1,6 +1,7 @@
 use std::fs;
 use std::path::Path;
 
+use parity_wasm::elements;
 use wasmi::memory_units::Pages;
 use wasmi::{
     Externals, FuncInstance, FuncRef, ImportsBuilder, MemoryDescriptor, MemoryInstance, MemoryRef,
@@ -11,63 +12,40 @@ use crate::builtins::Builtins;
 use crate::error::Error;
 use crate::ValueAddr;
 
-use super::{AsBytes, FromBytes, Functions};
+use super::{check_limit, AsBytes, FromBytes, FuelCounter, Functions, GrowthCounter, MemoryImport};
 
 const ABORT_FUNC_INDEX: usize = 1;
-const BUILTIN0_FUNC_INDEX: usize = 2;
-const BUILTIN1_FUNC_INDEX: usize = 3;
-const BUILTIN2_FUNC_INDEX: usize = 4;
-const BUILTIN3_FUNC_INDEX: usize = 5;
-const BUILTIN4_FUNC_INDEX: usize = 6;
+const PRINTLN_FUNC_INDEX: usize = 2;
+const GAS_FUNC_INDEX: usize = 3;
+const BUILTIN_FUNC_INDEX: usize = 16;
+// Like the wasmtime backend, only opa_builtin0 to opa_builtin4 are provided
+const MAX_BUILTIN_ARITY: usize = 4;
 
 #[derive(Clone, Debug)]
 struct HostExternals {
     memory: Memory,
     builtins: Builtins,
+    fuel: FuelCounter,
 }
 
-impl HostExternals {
-    fn check_signature(&self, index: usize, signature: &Signature) -> bool {
-        let (params, ret_ty): (&[ValueType], Option<ValueType>) = match index {
-            ABORT_FUNC_INDEX => (&[ValueType::I32], None),
-            BUILTIN0_FUNC_INDEX => (&[ValueType::I32, ValueType::I32], Some(ValueType::I32)),
-            BUILTIN1_FUNC_INDEX => (
-                &[ValueType::I32, ValueType::I32, ValueType::I32],
-                Some(ValueType::I32),
-            ),
-            BUILTIN2_FUNC_INDEX => (
-                &[
-                    ValueType::I32,
-                    ValueType::I32,
-                    ValueType::I32,
-                    ValueType::I32,
-                ],
-                Some(ValueType::I32),
-            ),
-            BUILTIN3_FUNC_INDEX => (
-                &[
-                    ValueType::I32,
-                    ValueType::I32,
-                    ValueType::I32,
-                    ValueType::I32,
-                    ValueType::I32,
-                ],
-                Some(ValueType::I32),
-            ),
-            BUILTIN4_FUNC_INDEX => (
-                &[
-                    ValueType::I32,
-                    ValueType::I32,
-                    ValueType::I32,
-                    ValueType::I32,
-                    ValueType::I32,
-                    ValueType::I32,
-                ],
-                Some(ValueType::I32),
-            ),
-            _ => return false,
-        };
-        signature.params() == params && signature.return_type() == ret_ty
+fn builtin_arity(field_name: &str) -> Option<usize> {
+    const PREFIX: &str = "opa_builtin";
+    if field_name.starts_with(PREFIX) {
+        field_name[PREFIX.len()..]
+            .parse()
+            .ok()
+            .filter(|arity| *arity <= MAX_BUILTIN_ARITY)
+    } else {
+        None
+    }
+}
+
+fn signature(index: usize) -> Signature {
+    if index >= BUILTIN_FUNC_INDEX {
+        let params = vec![ValueType::I32; index - BUILTIN_FUNC_INDEX + 2];
+        Signature::new(params, Some(ValueType::I32))
+    } else {
+        Signature::new(&[ValueType::I32][..], None)
     }
 }
 
@@ -87,83 +65,30 @@ impl ModuleImportResolver for HostExternals {
     ) -> Result<FuncRef, wasmi::Error> {
         let index = match field_name {
             "opa_abort" => ABORT_FUNC_INDEX,
-            "opa_builtin0" => BUILTIN0_FUNC_INDEX,
-            "opa_builtin1" => BUILTIN1_FUNC_INDEX,
-            "opa_builtin2" => BUILTIN2_FUNC_INDEX,
-            "opa_builtin3" => BUILTIN3_FUNC_INDEX,
-            "opa_builtin4" => BUILTIN4_FUNC_INDEX,
-            _ => {
-                return Err(wasmi::Error::Instantiation(format!(
-                    "Export {} not found",
-                    field_name
-                )))
-            }
+            "opa_println" => PRINTLN_FUNC_INDEX,
+            "gas" => GAS_FUNC_INDEX,
+            _ => match builtin_arity(field_name) {
+                Some(arity) => BUILTIN_FUNC_INDEX + arity,
+                None => {
+                    return Err(wasmi::Error::Instantiation(format!(
+                        "Export {} not found",
+                        field_name
+                    )))
+                }
+            },
         };
 
-        if !self.check_signature(index, signature) {
+        let expected = self::signature(index);
+        if signature.params() != expected.params()
+            || signature.return_type() != expected.return_type()
+        {
             return Err(wasmi::Error::Instantiation(format!(
                 "Export {} has a bad signature",
                 field_name
             )));
         }
 
-        let f = match field_name {
-            "opa_abort" => {
-                FuncInstance::alloc_host(Signature::new(&[ValueType::I32][..], None), index)
-            }
-            "opa_builtin0" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
-                index,
-            ),
-            "opa_builtin1" => FuncInstance::alloc_host(
-                Signature::new(
-                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
-                    Some(ValueType::I32),
-                ),
-                index,
-            ),
-            "opa_builtin2" => FuncInstance::alloc_host(
-                Signature::new(
-                    &[
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                    ][..],
-                    Some(ValueType::I32),
-                ),
-                index,
-            ),
-            "opa_builtin3" => FuncInstance::alloc_host(
-                Signature::new(
-                    &[
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                    ][..],
-                    Some(ValueType::I32),
-                ),
-                index,
-            ),
-            "opa_builtin4" => FuncInstance::alloc_host(
-                Signature::new(
-                    &[
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                    ][..],
-                    Some(ValueType::I32),
-                ),
-                index,
-            ),
-            _ => unreachable!(),
-        };
-        Ok(f)
+        Ok(FuncInstance::alloc_host(expected, index))
     }
 }
 
@@ -179,55 +104,25 @@ impl Externals for HostExternals {
                 crate::abort(addr);
                 None
             }
-            BUILTIN0_FUNC_INDEX => {
-                let id = args.nth_checked(0)?;
-                let ctx: i32 = args.nth_checked(1)?;
-                let result = self.builtins.builtin0(id, ctx.into());
-                Some(RuntimeValue::I32(result.into()))
-            }
-            BUILTIN1_FUNC_INDEX => {
-                let id = args.nth_checked(0)?;
-                let ctx: i32 = args.nth_checked(1)?;
-                let arg0: i32 = args.nth_checked(2)?;
-                let result = self.builtins.builtin1(id, ctx.into(), arg0.into());
-                Some(RuntimeValue::I32(result.into()))
-            }
-            BUILTIN2_FUNC_INDEX => {
-                let id = args.nth_checked(0)?;
-                let ctx: i32 = args.nth_checked(1)?;
-                let arg0: i32 = args.nth_checked(2)?;
-                let arg1: i32 = args.nth_checked(3)?;
-                let result = self
-                    .builtins
-                    .builtin2(id, ctx.into(), arg0.into(), arg1.into());
-                Some(RuntimeValue::I32(result.into()))
+            PRINTLN_FUNC_INDEX => {
+                let addr: i32 = args.nth_checked(0)?;
+                crate::println(&self.memory, &self.builtins, addr.into());
+                None
             }
-            BUILTIN3_FUNC_INDEX => {
-                let id = args.nth_checked(0)?;
-                let ctx: i32 = args.nth_checked(1)?;
-                let arg0: i32 = args.nth_checked(2)?;
-                let arg1: i32 = args.nth_checked(3)?;
-                let arg2: i32 = args.nth_checked(4)?;
-                let result =
-                    self.builtins
-                        .builtin3(id, ctx.into(), arg0.into(), arg1.into(), arg2.into());
-                Some(RuntimeValue::I32(result.into()))
+            GAS_FUNC_INDEX => {
+                let cost: i32 = args.nth_checked(0)?;
+                if !self.fuel.consume(cost as u32) {
+                    return Err(TrapKind::Unreachable.into());
+                }
+                None
             }
-            BUILTIN4_FUNC_INDEX => {
+            index if index >= BUILTIN_FUNC_INDEX => {
                 let id = args.nth_checked(0)?;
                 let ctx: i32 = args.nth_checked(1)?;
-                let arg0: i32 = args.nth_checked(2)?;
-                let arg1: i32 = args.nth_checked(3)?;
-                let arg2: i32 = args.nth_checked(4)?;
-                let arg3: i32 = args.nth_checked(5)?;
-                let result = self.builtins.builtin4(
-                    id,
-                    ctx.into(),
-                    arg0.into(),
-                    arg1.into(),
-                    arg2.into(),
-                    arg3.into(),
-                );
+                let addrs = (2..args.len())
+                    .map(|i| args.nth_checked::<i32>(i).map(ValueAddr::from))
+                    .collect::<Result<Vec<ValueAddr>, Trap>>()?;
+                let result = self.builtins.builtin(id, ctx.into(), &addrs);
                 Some(RuntimeValue::I32(result.into()))
             }
             _ => return Err(TrapKind::ElemUninitialized.into()),
@@ -249,13 +144,14 @@ impl Instance {
         let externals = HostExternals {
             memory: memory.clone(),
             builtins: builtins.clone(),
+            fuel: FuelCounter::default(),
         };
         let imports = ImportsBuilder::new().with_resolver("env", &externals);
         let instance = wasmi::ModuleInstance::new(&module.0, &imports)
             .map_err(Error::Wasmi)?
             .assert_no_start();
         let fimpl = FunctionsImpl::new(instance, externals.clone())?;
-        let functions = Functions::from_impl(fimpl)?;
+        let functions = Functions::from_impl(fimpl, memory.clone())?;
         let instance = Instance {
             memory,
             functions,
@@ -273,38 +169,89 @@ impl Instance {
     pub fn memory(&self) -> &Memory {
         &self.memory
     }
+
+    pub fn builtins(&self) -> &Builtins {
+        &self.externals.builtins
+    }
+
+    pub fn fuel(&self) -> &FuelCounter {
+        &self.externals.fuel
+    }
 }
 
 #[derive(Clone, Debug)]
-pub struct Memory(MemoryRef);
+pub struct Memory(MemoryRef, Option<usize>, GrowthCounter);
 
 impl Memory {
-    pub fn from_module(_module: &Module) -> Self {
-        let memory = MemoryInstance::alloc(Pages(5), None).unwrap();
-        Memory(memory)
+    pub fn from_module(module: &Module) -> Self {
+        Self::with_max_pages(module, None)
+    }
+
+    pub fn with_max_pages(module: &Module, max_pages: Option<usize>) -> Self {
+        let initial_pages = module.1.initial_pages();
+        let max_pages = module.1.max_pages(max_pages);
+        let memory = MemoryInstance::alloc(Pages(initial_pages), max_pages.map(Pages)).unwrap();
+        Memory(memory, max_pages, GrowthCounter::default())
+    }
+
+    pub fn max_pages(&self) -> Option<usize> {
+        self.1
+    }
+
+    pub fn growth(&self) -> &GrowthCounter {
+        &self.2
+    }
+
+    pub fn check_range(&self, addr: ValueAddr, len: usize) -> Result<(), Error> {
+        check_limit(addr, len, self.pages(), self.1)
     }
 
     pub fn get<T: FromBytes>(&self, addr: ValueAddr) -> Result<T, Error> {
         let start = addr.0 as usize;
-        let t = self
-            .0
-            .with_direct_access(|bytes| T::from_bytes(&bytes[start..]))?;
+        let t = self.0.with_direct_access(|bytes| {
+            bytes
+                .get(start..)
+                .ok_or(Error::OutOfBounds(addr))
+                .and_then(T::from_bytes)
+        })?;
         Ok(t)
     }
 
     pub fn get_bytes(&self, addr: ValueAddr, len: usize) -> Result<Vec<u8>, Error> {
-        let start = addr.0 as u32;
-        self.0.get(start, len).map_err(Error::Wasmi)
+        let start = addr.0 as usize;
+        self.0.with_direct_access(|bytes| {
+            start
+                .checked_add(len)
+                .and_then(|end| bytes.get(start..end))
+                .map(<[u8]>::to_vec)
+                .ok_or(Error::OutOfBounds(addr))
+        })
     }
 
     pub fn set<T: AsBytes>(&self, addr: ValueAddr, value: &T) -> Result<(), Error> {
-        self.0
-            .set(addr.0 as u32, value.as_bytes())
-            .map_err(Error::Wasmi)
+        let bytes = value.as_bytes();
+        self.check_range(addr, bytes.len())?;
+        self.0.set(addr.0 as u32, bytes).map_err(Error::Wasmi)
+    }
+
+    pub fn get_cstr(&self, addr: ValueAddr) -> Result<String, Error> {
+        let start = addr.0 as usize;
+        self.0.with_direct_access(|bytes| {
+            let bytes = bytes.get(start..).ok_or(Error::OutOfBounds(addr))?;
+            let end = bytes
+                .iter()
+                .position(|b| *b == 0)
+                .ok_or(Error::OutOfBounds(addr))?;
+            Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+        })
+    }
+
+    pub fn pages(&self) -> usize {
+        self.0.current_size().0
     }
 }
 
-pub struct Module(wasmi::Module);
+pub struct Module(wasmi::Module, MemoryImport, bool);
 
 impl Module {
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Module, Error> {
@@ -312,9 +259,47 @@ impl Module {
         Self::from_bytes(bytes)
     }
 
+    // Parses the module with parity-wasm first, like wasmi::Module::from_buffer
+    // does, to read the limits of its memory import.
     pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Module, Error> {
-        let module = wasmi::Module::from_buffer(&bytes).map_err(Error::Wasmi)?;
-        Ok(Module(module))
+        let module = parity_wasm::deserialize_buffer::<elements::Module>(bytes.as_ref())
+            .map_err(|e| Error::Wasmi(wasmi::Error::Validation(e.to_string())))?;
+        let memory = memory_import(&module)?;
+        let metered = module
+            .import_section()
+            .map(elements::ImportSection::entries)
+            .unwrap_or_default()
+            .iter()
+            .any(|entry| entry.module() == "env" && entry.field() == "gas");
+        let module = wasmi::Module::from_parity_wasm_module(module).map_err(Error::Wasmi)?;
+        Ok(Module(module, memory, metered))
+    }
+
+    // Compiles the module with fuel metering, for PolicyConfig::eval_fuel.
+    pub fn from_bytes_metered<B: AsRef<[u8]>>(bytes: B) -> Result<Module, Error> {
+        Self::from_bytes(super::meter(bytes.as_ref())?)
+    }
+
+    pub fn is_metered(&self) -> bool {
+        self.2
+    }
+}
+
+fn memory_import(module: &elements::Module) -> Result<MemoryImport, Error> {
+    let entries = module
+        .import_section()
+        .map(elements::ImportSection::entries)
+        .unwrap_or_default();
+    let limits = entries.iter().find_map(|entry| match entry.external() {
+        elements::External::Memory(memory) => Some(memory.limits()),
+        _ => None,
+    });
+    match limits {
+        Some(limits) => MemoryImport::new(
+            limits.initial() as usize,
+            limits.maximum().map(|max| max as usize),
+        ),
+        None => Ok(MemoryImport::default()),
     }
 }
 
@@ -333,7 +318,12 @@ impl FunctionsImpl {
         Ok(f)
     }
 
-    pub fn builtins(&self) -> Result<i32, Error> {
+    pub fn builtins(&self) -> Result<Option<i32>, Error> {
+        // Modules that call no builtins may leave this out
+        if self.module_ref.export_by_name("builtins").is_none() {
+            return Ok(None);
+        }
+
         let args = [];
         let mut externals = self.externals.clone();
         self.module_ref
@@ -342,6 +332,112 @@ impl FunctionsImpl {
             .map_err(Error::Wasmi)
             .transpose()
             .unwrap_or_else(|| Err(Error::InvalidResult("i32")))
+            .map(Some)
+    }
+
+    pub fn entrypoints(&self) -> Result<Option<i32>, Error> {
+        // Only modules compiled with multiple entrypoints export this
+        if self.module_ref.export_by_name("entrypoints").is_none() {
+            return Ok(None);
+        }
+
+        let args = [];
+        let mut externals = self.externals.clone();
+        self.module_ref
+            .invoke_export("entrypoints", &args[..], &mut externals)
+            .map(|v| v.and_then(|r| r.try_into::<i32>()))
+            .map_err(Error::Wasmi)
+            .transpose()
+            .unwrap_or_else(|| Err(Error::InvalidResult("i32")))
+            .map(Some)
+    }
+
+    pub fn has_value_paths(&self) -> bool {
+        // Newer ABIs export these to update data in place
+        self.module_ref
+            .export_by_name("opa_value_add_path")
+            .is_some()
+            && self
+                .module_ref
+                .export_by_name("opa_value_remove_path")
+                .is_some()
+    }
+
+    pub fn opa_value_add_path(&self, base: i32, path: i32, value: i32) -> Result<i32, Error> {
+        if self
+            .module_ref
+            .export_by_name("opa_value_add_path")
+            .is_none()
+        {
+            return Err(Error::MissingExport("opa_value_add_path"));
+        }
+
+        let args = [
+            RuntimeValue::I32(base),
+            RuntimeValue::I32(path),
+            RuntimeValue::I32(value),
+        ];
+        let mut externals = self.externals.clone();
+        self.module_ref
+            .invoke_export("opa_value_add_path", &args[..], &mut externals)
+            .map(|v| v.and_then(|r| r.try_into::<i32>()))
+            .map_err(Error::Wasmi)
+            .transpose()
+            .unwrap_or_else(|| Err(Error::InvalidResult("i32")))
+    }
+
+    pub fn opa_value_remove_path(&self, base: i32, path: i32) -> Result<i32, Error> {
+        if self
+            .module_ref
+            .export_by_name("opa_value_remove_path")
+            .is_none()
+        {
+            return Err(Error::MissingExport("opa_value_remove_path"));
+        }
+
+        let args = [RuntimeValue::I32(base), RuntimeValue::I32(path)];
+        let mut externals = self.externals.clone();
+        self.module_ref
+            .invoke_export("opa_value_remove_path", &args[..], &mut externals)
+            .map(|v| v.and_then(|r| r.try_into::<i32>()))
+            .map_err(Error::Wasmi)
+            .transpose()
+            .unwrap_or_else(|| Err(Error::InvalidResult("i32")))
+    }
+
+    pub fn has_opa_eval(&self) -> bool {
+        // ABI 1.2 modules export a single call evaluation
+        self.module_ref.export_by_name("opa_eval").is_some()
+    }
+
+    pub fn opa_eval(
+        &self,
+        entrypoint: i32,
+        data: i32,
+        input: i32,
+        input_len: i32,
+        heap_ptr: i32,
+    ) -> Result<i32, Error> {
+        if self.module_ref.export_by_name("opa_eval").is_none() {
+            return Err(Error::MissingExport("opa_eval"));
+        }
+
+        let args = [
+            RuntimeValue::I32(0),
+            RuntimeValue::I32(entrypoint),
+            RuntimeValue::I32(data),
+            RuntimeValue::I32(input),
+            RuntimeValue::I32(input_len),
+            RuntimeValue::I32(heap_ptr),
+            RuntimeValue::I32(0),
+        ];
+        let mut externals = self.externals.clone();
+        self.module_ref
+            .invoke_export("opa_eval", &args[..], &mut externals)
+            .map(|v| v.and_then(|r| r.try_into::<i32>()))
+            .map_err(Error::Wasmi)
+            .transpose()
+            .unwrap_or_else(|| Err(Error::InvalidResult("i32")))
     }
 
     pub fn opa_eval_ctx_new(&self) -> Result<i32, Error> {
@@ -373,6 +469,26 @@ impl FunctionsImpl {
             .map_err(Error::Wasmi)
     }
 
+    pub fn has_eval_ctx_set_entrypoint(&self) -> bool {
+        // Only modules compiled with multiple entrypoints export this
+        self.module_ref
+            .export_by_name("opa_eval_ctx_set_entrypoint")
+            .is_some()
+    }
+
+    pub fn opa_eval_ctx_set_entrypoint(&self, ctx: i32, entrypoint: i32) -> Result<(), Error> {
+        if !self.has_eval_ctx_set_entrypoint() {
+            return Err(Error::MissingExport("opa_eval_ctx_set_entrypoint"));
+        }
+
+        let args = [RuntimeValue::I32(ctx), RuntimeValue::I32(entrypoint)];
+        let mut externals = self.externals.clone();
+        self.module_ref
+            .invoke_export("opa_eval_ctx_set_entrypoint", &args[..], &mut externals)
+            .map(drop)
+            .map_err(Error::Wasmi)
+    }
+
     pub fn eval(&self, ctx: i32) -> Result<(), Error> {
         let args = [RuntimeValue::I32(ctx)];
         let mut externals = self.externals.clone();
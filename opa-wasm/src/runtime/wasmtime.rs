@@ -1,18 +1,24 @@
+use std::convert::TryFrom;
 use std::fmt;
+use std::ops::Range;
 use std::path::Path;
 
+#[cfg(feature = "wasi")]
+use wasmtime::Val;
 use wasmtime::{Extern, Func, Limits, MemoryType, Store, Trap};
 
 use crate::builtins::Builtins;
 use crate::error::Error;
 use crate::ValueAddr;
 
-use super::{AsBytes, FromBytes, Functions};
+use super::{check_limit, AsBytes, FromBytes, FuelCounter, Functions, GrowthCounter, MemoryImport};
 
 #[derive(Clone)]
 pub struct Instance {
     memory: Memory,
     functions: Functions,
+    builtins: Builtins,
+    fuel: FuelCounter,
 }
 
 impl Instance {
@@ -30,46 +36,79 @@ impl Instance {
         // struct annoyingly complex because we need to use an Arc for shared references
         // as well as mutate the contents, requiring a RefCell.
         let builtins = Builtins::default();
-
-        let b0 = builtins.clone();
-        let b1 = builtins.clone();
-        let b2 = builtins.clone();
-        let b3 = builtins.clone();
-        let b4 = builtins.clone();
-
-        let imports = [
-            Extern::Memory(memory.clone().0),
-            Extern::Func(Func::wrap1(module.0.store(), crate::abort)),
-            Extern::Func(Func::wrap2(module.0.store(), move |id, ctx| {
-                i32::from(b0.builtin0(id, ValueAddr(ctx)))
-            })),
-            Extern::Func(Func::wrap3(module.0.store(), move |id, ctx, a| {
-                i32::from(b1.builtin1(id, ValueAddr(ctx), ValueAddr(a)))
-            })),
-            Extern::Func(Func::wrap4(module.0.store(), move |id, ctx, a, b| {
-                i32::from(b2.builtin2(id, ValueAddr(ctx), ValueAddr(a), ValueAddr(b)))
-            })),
-            Extern::Func(Func::wrap5(module.0.store(), move |id, ctx, a, b, c| {
-                i32::from(b3.builtin3(id, ValueAddr(ctx), ValueAddr(a), ValueAddr(b), ValueAddr(c)))
-            })),
-            Extern::Func(Func::wrap6(module.0.store(), move |id, ctx, a, b, c, d| {
-                i32::from(b4.builtin4(
-                    id,
-                    ValueAddr(ctx),
-                    ValueAddr(a),
-                    ValueAddr(b),
-                    ValueAddr(c),
-                    ValueAddr(d),
-                ))
-            })),
-        ];
+        let fuel = FuelCounter::default();
+        let store = module.0.store();
+
+        let imports = module
+            .0
+            .imports()
+            .iter()
+            .map(|import| {
+                #[cfg(feature = "wasi")]
+                {
+                    if import.module() == WASI_MODULE {
+                        return wasi_stub(store, &memory, import).map(Extern::Func);
+                    }
+                }
+
+                let b = builtins.clone();
+                let ext = match import.name() {
+                    "memory" => Extern::Memory(memory.clone().0),
+                    "opa_abort" => Extern::Func(Func::wrap1(store, crate::abort)),
+                    "opa_println" => {
+                        let m = memory.clone();
+                        let b = builtins.clone();
+                        Extern::Func(Func::wrap1(store, move |addr: i32| {
+                            crate::println(&m, &b, ValueAddr(addr))
+                        }))
+                    }
+                    "gas" => {
+                        let f = fuel.clone();
+                        Extern::Func(Func::wrap1(store, move |cost: i32| {
+                            if f.consume(cost as u32) {
+                                Ok(())
+                            } else {
+                                Err(Trap::new("out of fuel"))
+                            }
+                        }))
+                    }
+                    "opa_builtin0" => Extern::Func(Func::wrap2(store, move |id, ctx| {
+                        i32::from(b.builtin(id, ValueAddr(ctx), &[]))
+                    })),
+                    "opa_builtin1" => Extern::Func(Func::wrap3(store, move |id, ctx, a| {
+                        i32::from(b.builtin(id, ValueAddr(ctx), &[ValueAddr(a)]))
+                    })),
+                    "opa_builtin2" => Extern::Func(Func::wrap4(store, move |id, ctx, a, b_| {
+                        let args = [ValueAddr(a), ValueAddr(b_)];
+                        i32::from(b.builtin(id, ValueAddr(ctx), &args))
+                    })),
+                    "opa_builtin3" => Extern::Func(Func::wrap5(store, move |id, ctx, a, b_, c| {
+                        let args = [ValueAddr(a), ValueAddr(b_), ValueAddr(c)];
+                        i32::from(b.builtin(id, ValueAddr(ctx), &args))
+                    })),
+                    "opa_builtin4" => {
+                        Extern::Func(Func::wrap6(store, move |id, ctx, a, b_, c, d| {
+                            let args = [ValueAddr(a), ValueAddr(b_), ValueAddr(c), ValueAddr(d)];
+                            i32::from(b.builtin(id, ValueAddr(ctx), &args))
+                        }))
+                    }
+                    name => return Err(Error::UnknownImport(name.to_string())),
+                };
+                Ok(ext)
+            })
+            .collect::<Result<Vec<Extern>, Error>>()?;
 
         let instance =
             wasmtime::Instance::new(&module.0, &imports).map_err(|e| Error::Wasmtime(e))?;
         let fimpl = FunctionsImpl::from_instance(instance)?;
-        let functions = Functions::from_impl(fimpl)?;
+        let functions = Functions::from_impl(fimpl, memory.clone())?;
 
-        let instance = Instance { memory, functions };
+        let instance = Instance {
+            memory,
+            functions,
+            builtins: builtins.clone(),
+            fuel,
+        };
         builtins.replace(instance.clone())?;
 
         Ok(instance)
@@ -82,6 +121,14 @@ impl Instance {
     pub fn memory(&self) -> &Memory {
         &self.memory
     }
+
+    pub fn builtins(&self) -> &Builtins {
+        &self.builtins
+    }
+
+    pub fn fuel(&self) -> &FuelCounter {
+        &self.fuel
+    }
 }
 
 impl fmt::Debug for Instance {
@@ -90,38 +137,136 @@ impl fmt::Debug for Instance {
     }
 }
 
+#[cfg(feature = "wasi")]
+const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
+// WASI's errno for a call the sandbox doesn't allow.
+#[cfg(feature = "wasi")]
+const WASI_ERRNO_NOTCAPABLE: i32 = 76;
+
+// Stands in for a WASI function, so modules built against WASI instantiate
+// without access to the host. Empty arguments and environment are reported,
+// exiting traps, and every other call is denied with ENOTCAPABLE.
+#[cfg(feature = "wasi")]
+fn wasi_stub(store: &Store, memory: &Memory, import: &wasmtime::ImportType) -> Result<Func, Error> {
+    let ty = import
+        .ty()
+        .func()
+        .ok_or_else(|| Error::UnknownImport(import.name().to_string()))?;
+    let name = import.name().to_string();
+    let memory = memory.clone();
+    let func = Func::new(store, ty.clone(), move |params, results| {
+        let errno = match (name.as_str(), params) {
+            ("proc_exit", _) => return Err(Trap::new("proc_exit called by a WASI module")),
+            ("args_get", _) | ("environ_get", _) => 0,
+            ("args_sizes_get", [Val::I32(count), Val::I32(size)])
+            | ("environ_sizes_get", [Val::I32(count), Val::I32(size)]) => {
+                let zero = &[0u8; 4][..];
+                match (
+                    memory.set(ValueAddr(*count), &zero),
+                    memory.set(ValueAddr(*size), &zero),
+                ) {
+                    (Ok(()), Ok(())) => 0,
+                    _ => return Err(Trap::new(format!("{}: out of bounds", name))),
+                }
+            }
+            _ => WASI_ERRNO_NOTCAPABLE,
+        };
+        for result in results.iter_mut() {
+            *result = Val::I32(errno);
+        }
+        Ok(())
+    });
+    Ok(func)
+}
+
 #[derive(Clone)]
-pub struct Memory(wasmtime::Memory);
+pub struct Memory(wasmtime::Memory, Option<usize>, GrowthCounter);
 
 impl Memory {
     pub fn from_module(module: &Module) -> Self {
-        let memorytype = MemoryType::new(Limits::new(5, None));
-        let memory = wasmtime::Memory::new(module.0.store(), memorytype);
-        Memory(memory)
+        Self::with_max_pages(module, None)
+    }
+
+    pub fn with_max_pages(module: &Module, max_pages: Option<usize>) -> Self {
+        let initial_pages = module.1.initial_pages();
+        let max_pages = module.1.max_pages(max_pages);
+        let limits = Limits::new(initial_pages as u32, max_pages.map(|max| max as u32));
+        let memory = wasmtime::Memory::new(module.0.store(), MemoryType::new(limits));
+        Memory(memory, max_pages, GrowthCounter::default())
+    }
+
+    pub fn max_pages(&self) -> Option<usize> {
+        self.1
+    }
+
+    pub fn growth(&self) -> &GrowthCounter {
+        &self.2
+    }
+
+    pub fn check_range(&self, addr: ValueAddr, len: usize) -> Result<(), Error> {
+        check_limit(addr, len, self.pages(), self.1)
     }
 
     pub fn get<T: FromBytes>(&self, addr: ValueAddr) -> Result<T, Error> {
-        let start = addr.0 as usize;
-        let t = unsafe { T::from_bytes(&self.0.data_unchecked()[start..])? };
-        Ok(t)
+        let len = self.0.data_size().saturating_sub(start(addr)?);
+        self.read(addr, len, T::from_bytes)
     }
 
     pub fn get_bytes(&self, addr: ValueAddr, len: usize) -> Result<Vec<u8>, Error> {
-        let start = addr.0 as usize;
-        let end = start + len;
-        let t = unsafe { Vec::from(&self.0.data_unchecked()[start..end]) };
-        Ok(t)
+        self.read(addr, len, |bytes| Ok(bytes.to_vec()))
     }
 
     pub fn set<T: AsBytes>(&self, addr: ValueAddr, value: &T) -> Result<(), Error> {
         let bytes = value.as_bytes();
-        unsafe {
-            let start = addr.0 as usize;
-            let end = start + bytes.len();
-            self.0.data_unchecked_mut()[start..end].copy_from_slice(bytes);
-        }
+        self.check_range(addr, bytes.len())?;
+        self.write(addr, bytes)
+    }
+
+    pub fn get_cstr(&self, addr: ValueAddr) -> Result<String, Error> {
+        let len = self.0.data_size().saturating_sub(start(addr)?);
+        self.read(addr, len, |bytes| {
+            let end = bytes
+                .iter()
+                .position(|b| *b == 0)
+                .ok_or(Error::OutOfBounds(addr))?;
+            Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+        })
+    }
+
+    // All access to the raw memory goes through read and write. The slices
+    // wasmtime hands out are invalidated when the memory grows, so they must
+    // not outlive the call, and nothing may call into wasm while one is held.
+    fn read<T, F>(&self, addr: ValueAddr, len: usize, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&[u8]) -> Result<T, Error>,
+    {
+        let range = self.range(addr, len)?;
+        let bytes = unsafe { &self.0.data_unchecked()[range] };
+        f(bytes)
+    }
+
+    fn write(&self, addr: ValueAddr, value: &[u8]) -> Result<(), Error> {
+        let range = self.range(addr, value.len())?;
+        unsafe { self.0.data_unchecked_mut()[range].copy_from_slice(value) };
         Ok(())
     }
+
+    fn range(&self, addr: ValueAddr, len: usize) -> Result<Range<usize>, Error> {
+        let start = start(addr)?;
+        match start.checked_add(len) {
+            Some(end) if end <= self.0.data_size() => Ok(start..end),
+            _ => Err(Error::OutOfBounds(addr)),
+        }
+    }
+
+    pub fn pages(&self) -> usize {
+        self.0.size() as usize
+    }
+}
+
+fn start(addr: ValueAddr) -> Result<usize, Error> {
+    usize::try_from(addr.0).map_err(|_| Error::OutOfBounds(addr))
 }
 
 impl fmt::Debug for Memory {
@@ -131,19 +276,54 @@ impl fmt::Debug for Memory {
 }
 
 #[derive(Clone)]
-pub struct Module(wasmtime::Module);
+pub struct Module(wasmtime::Module, MemoryImport, bool);
 
+// The store a module is compiled in is also the one its memory and instances
+// are created in, so a configured store applies to everything built from it.
 impl Module {
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Module, Error> {
-        let store = Store::default();
-        let module = wasmtime::Module::from_file(&store, &path).map_err(Error::Wasmtime)?;
-        Ok(Module(module))
+        Self::from_file_with_store(&Store::default(), path)
     }
 
     pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Module, Error> {
-        let store = Store::default();
-        let module = wasmtime::Module::new(&store, bytes).map_err(Error::Wasmtime)?;
-        Ok(Module(module))
+        Self::from_bytes_with_store(&Store::default(), bytes)
+    }
+
+    // Compiles the module with fuel metering, for PolicyConfig::eval_fuel.
+    pub fn from_bytes_metered<B: AsRef<[u8]>>(bytes: B) -> Result<Module, Error> {
+        Self::from_bytes(super::meter(bytes.as_ref())?)
+    }
+
+    pub fn from_file_with_store<P: AsRef<Path>>(store: &Store, path: P) -> Result<Module, Error> {
+        let module = wasmtime::Module::from_file(store, &path).map_err(Error::Wasmtime)?;
+        Self::from_module(module)
+    }
+
+    pub fn from_bytes_with_store<B: AsRef<[u8]>>(store: &Store, bytes: B) -> Result<Module, Error> {
+        let module = wasmtime::Module::new(store, bytes).map_err(Error::Wasmtime)?;
+        Self::from_module(module)
+    }
+
+    fn from_module(module: wasmtime::Module) -> Result<Module, Error> {
+        let limits = module
+            .imports()
+            .iter()
+            .find_map(|import| import.ty().memory().map(MemoryType::limits));
+        let memory = match limits {
+            Some(limits) => {
+                MemoryImport::new(limits.min() as usize, limits.max().map(|max| max as usize))?
+            }
+            None => MemoryImport::default(),
+        };
+        let metered = module
+            .imports()
+            .iter()
+            .any(|import| import.module() == "env" && import.name() == "gas");
+        Ok(Module(module, memory, metered))
+    }
+
+    pub fn is_metered(&self) -> bool {
+        self.2
     }
 }
 
@@ -161,8 +341,13 @@ pub struct FunctionsImpl {
     opa_eval_ctx_set_input: Box<dyn Fn(i32, i32) -> Result<(), Trap>>,
     opa_eval_ctx_set_data: Box<dyn Fn(i32, i32) -> Result<(), Trap>>,
     opa_eval_ctx_get_result: Box<dyn Fn(i32) -> Result<i32, Trap>>,
-    builtins: Box<dyn Fn() -> Result<i32, Trap>>,
+    opa_eval_ctx_set_entrypoint: Option<Box<dyn Fn(i32, i32) -> Result<(), Trap>>>,
+    builtins: Option<Box<dyn Fn() -> Result<i32, Trap>>>,
     eval: Box<dyn Fn(i32) -> Result<i32, Trap>>,
+    entrypoints: Option<Box<dyn Fn() -> Result<i32, Trap>>>,
+    opa_value_add_path: Option<Box<dyn Fn(i32, i32, i32) -> Result<i32, Trap>>>,
+    opa_value_remove_path: Option<Box<dyn Fn(i32, i32) -> Result<i32, Trap>>>,
+    opa_eval: Option<Box<dyn Fn(i32, i32, i32, i32, i32, i32, i32) -> Result<i32, Trap>>>,
 }
 
 impl FunctionsImpl {
@@ -233,11 +418,11 @@ impl FunctionsImpl {
             .ok_or_else(|| Error::MissingExport("opa_eval_ctx_get_result"))
             .and_then(|f| f.get1::<i32, i32>().map_err(|e| Error::Wasmtime(e)))?;
 
-        let builtins = instance
-            .get_export("builtins")
-            .and_then(|ext| ext.func())
-            .ok_or_else(|| Error::MissingExport("builtins"))
-            .and_then(|f| f.get0::<i32>().map_err(|e| Error::Wasmtime(e)))?;
+        // Modules that call no builtins may leave this out
+        let builtins = match instance.get_export("builtins").and_then(|ext| ext.func()) {
+            Some(f) => Some(f.get0::<i32>().map_err(|e| Error::Wasmtime(e))?),
+            None => None,
+        };
 
         let eval = instance
             .get_export("eval")
@@ -245,6 +430,52 @@ impl FunctionsImpl {
             .ok_or_else(|| Error::MissingExport("eval"))
             .and_then(|f| f.get1::<i32, i32>().map_err(|e| Error::Wasmtime(e)))?;
 
+        // Only modules compiled with multiple entrypoints export this
+        let entrypoints = match instance
+            .get_export("entrypoints")
+            .and_then(|ext| ext.func())
+        {
+            Some(f) => Some(f.get0::<i32>().map_err(|e| Error::Wasmtime(e))?),
+            None => None,
+        };
+
+        let opa_eval_ctx_set_entrypoint = match instance
+            .get_export("opa_eval_ctx_set_entrypoint")
+            .and_then(|ext| ext.func())
+        {
+            Some(f) => Some(f.get2::<i32, i32, ()>().map_err(|e| Error::Wasmtime(e))?),
+            None => None,
+        };
+
+        // Newer ABIs export these to update data in place
+        let opa_value_add_path = match instance
+            .get_export("opa_value_add_path")
+            .and_then(|ext| ext.func())
+        {
+            Some(f) => Some(
+                f.get3::<i32, i32, i32, i32>()
+                    .map_err(|e| Error::Wasmtime(e))?,
+            ),
+            None => None,
+        };
+
+        let opa_value_remove_path = match instance
+            .get_export("opa_value_remove_path")
+            .and_then(|ext| ext.func())
+        {
+            Some(f) => Some(f.get2::<i32, i32, i32>().map_err(|e| Error::Wasmtime(e))?),
+            None => None,
+        };
+
+        // ABI 1.2 modules export a single call evaluation
+        let opa_eval = match instance.get_export("opa_eval").and_then(|ext| ext.func()) {
+            Some(f) => Some(
+                f.get7::<i32, i32, i32, i32, i32, i32, i32, i32>()
+                    .map_err(|e| Error::Wasmtime(e))?,
+            ),
+            None => None,
+        };
+
         let inner = FunctionsImpl {
             instance,
             opa_malloc: Box::new(opa_malloc),
@@ -258,15 +489,83 @@ impl FunctionsImpl {
             opa_eval_ctx_set_input: Box::new(opa_eval_ctx_set_input),
             opa_eval_ctx_set_data: Box::new(opa_eval_ctx_set_data),
             opa_eval_ctx_get_result: Box::new(opa_eval_ctx_get_result),
-            builtins: Box::new(builtins),
+            builtins: builtins.map(|f| Box::new(f) as Box<dyn Fn() -> Result<i32, Trap>>),
             eval: Box::new(eval),
+            entrypoints: entrypoints.map(|f| Box::new(f) as Box<dyn Fn() -> Result<i32, Trap>>),
+            opa_eval_ctx_set_entrypoint: opa_eval_ctx_set_entrypoint
+                .map(|f| Box::new(f) as Box<dyn Fn(i32, i32) -> Result<(), Trap>>),
+            opa_value_add_path: opa_value_add_path
+                .map(|f| Box::new(f) as Box<dyn Fn(i32, i32, i32) -> Result<i32, Trap>>),
+            opa_value_remove_path: opa_value_remove_path
+                .map(|f| Box::new(f) as Box<dyn Fn(i32, i32) -> Result<i32, Trap>>),
+            opa_eval: opa_eval.map(|f| {
+                Box::new(f) as Box<dyn Fn(i32, i32, i32, i32, i32, i32, i32) -> Result<i32, Trap>>
+            }),
         };
         Ok(inner)
     }
 
-    pub fn builtins(&self) -> Result<i32, Error> {
-        let addr = (self.builtins)().map_err(Error::Trap)?;
-        Ok(addr)
+    pub fn builtins(&self) -> Result<Option<i32>, Error> {
+        match self.builtins {
+            Some(ref builtins) => builtins().map(Some).map_err(Error::Trap),
+            None => Ok(None),
+        }
+    }
+
+    pub fn entrypoints(&self) -> Result<Option<i32>, Error> {
+        match self.entrypoints {
+            Some(ref entrypoints) => entrypoints().map(Some).map_err(Error::Trap),
+            None => Ok(None),
+        }
+    }
+
+    pub fn has_eval_ctx_set_entrypoint(&self) -> bool {
+        self.opa_eval_ctx_set_entrypoint.is_some()
+    }
+
+    pub fn opa_eval_ctx_set_entrypoint(&self, ctx: i32, entrypoint: i32) -> Result<(), Error> {
+        match self.opa_eval_ctx_set_entrypoint {
+            Some(ref set_entrypoint) => set_entrypoint(ctx, entrypoint).map_err(Error::Trap),
+            None => Err(Error::MissingExport("opa_eval_ctx_set_entrypoint")),
+        }
+    }
+
+    pub fn has_value_paths(&self) -> bool {
+        self.opa_value_add_path.is_some() && self.opa_value_remove_path.is_some()
+    }
+
+    pub fn opa_value_add_path(&self, base: i32, path: i32, value: i32) -> Result<i32, Error> {
+        match self.opa_value_add_path {
+            Some(ref add_path) => add_path(base, path, value).map_err(Error::Trap),
+            None => Err(Error::MissingExport("opa_value_add_path")),
+        }
+    }
+
+    pub fn opa_value_remove_path(&self, base: i32, path: i32) -> Result<i32, Error> {
+        match self.opa_value_remove_path {
+            Some(ref remove_path) => remove_path(base, path).map_err(Error::Trap),
+            None => Err(Error::MissingExport("opa_value_remove_path")),
+        }
+    }
+
+    pub fn has_opa_eval(&self) -> bool {
+        self.opa_eval.is_some()
+    }
+
+    pub fn opa_eval(
+        &self,
+        entrypoint: i32,
+        data: i32,
+        input: i32,
+        input_len: i32,
+        heap_ptr: i32,
+    ) -> Result<i32, Error> {
+        match self.opa_eval {
+            Some(ref eval) => {
+                eval(0, entrypoint, data, input, input_len, heap_ptr, 0).map_err(Error::Trap)
+            }
+            None => Err(Error::MissingExport("opa_eval")),
+        }
     }
 
     pub fn opa_eval_ctx_new(&self) -> Result<i32, Error> {
@@ -335,3 +634,56 @@ impl fmt::Debug for FunctionsImpl {
         write!(formatter, "FunctionsImpl")
     }
 }
+
+#[cfg(all(test, feature = "wasi"))]
+mod tests {
+    use super::*;
+
+    // The smallest module the runtime accepts, plus two WASI imports. The
+    // heap getters return what the WASI calls did, so the test can see it.
+    const WASI_WAT: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "args_sizes_get"
+            (func $args_sizes_get (param i32 i32) (result i32)))
+          (import "env" "memory" (memory 2))
+          (func (export "opa_malloc") (param i32) (result i32) i32.const 0)
+          (func (export "opa_json_parse") (param i32 i32) (result i32) i32.const 0)
+          (func (export "opa_json_dump") (param i32) (result i32) i32.const 0)
+          (func (export "opa_heap_ptr_get") (result i32)
+            (call $fd_write (i32.const 1) (i32.const 0) (i32.const 0) (i32.const 0)))
+          (func (export "opa_heap_ptr_set") (param i32))
+          (func (export "opa_heap_top_get") (result i32)
+            (call $args_sizes_get (i32.const 0) (i32.const 4)))
+          (func (export "opa_heap_top_set") (param i32))
+          (func (export "opa_eval_ctx_new") (result i32) i32.const 0)
+          (func (export "opa_eval_ctx_set_input") (param i32 i32))
+          (func (export "opa_eval_ctx_set_data") (param i32 i32))
+          (func (export "opa_eval_ctx_get_result") (param i32) (result i32) i32.const 0)
+          (func (export "eval") (param i32) (result i32) i32.const 0))
+    "#;
+
+    #[test]
+    fn test_wasi_stubs() {
+        let module = Module::from_bytes(wat::parse_str(WASI_WAT).unwrap()).unwrap();
+        let memory = Memory::from_module(&module);
+        let instance = Instance::new(&module, memory).unwrap();
+
+        // Writing to stdout is denied
+        let errno = instance.functions().heap_ptr_get().unwrap();
+        assert_eq!(ValueAddr(WASI_ERRNO_NOTCAPABLE), errno);
+
+        // There are no arguments
+        instance
+            .memory()
+            .set(ValueAddr(0), &&[0xffu8; 8][..])
+            .unwrap();
+        let errno = instance.functions().heap_top_get().unwrap();
+        assert_eq!(ValueAddr(0), errno);
+        assert_eq!(
+            vec![0; 8],
+            instance.memory().get_bytes(ValueAddr(0), 8).unwrap()
+        );
+    }
+}
@@ -1,3 +1,15 @@
+//! Sends collections to OPA as sets rather than arrays.
+//!
+//! Serde serializes `HashSet` and `BTreeSet` as sequences, which become OPA
+//! arrays, so `input.roles == {"admin"}` would never hold. Wrap the
+//! collection in `Set`, or annotate a field with
+//! `#[serde(with = "opa_wasm::set")]`, to get an OPA set instead.
+//!
+//! ```ignore
+//! let roles = ["admin", "dev"].iter().collect::<BTreeSet<_>>();
+//! policy.evaluate(&opa_wasm::set::Set::new(roles))?;
+//! ```
+
 use std::fmt;
 use std::marker::PhantomData;
 
@@ -26,11 +38,32 @@ where
     Ok(s.elements)
 }
 
+/// A collection serialized as an OPA set.
 #[derive(Debug, Clone, PartialEq)]
-struct Set<T> {
+pub struct Set<T> {
     elements: T,
 }
 
+impl<T> Set<T> {
+    pub fn new(elements: T) -> Self {
+        Set { elements }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.elements
+    }
+
+    pub fn into_inner(self) -> T {
+        self.elements
+    }
+}
+
+impl<T> From<T> for Set<T> {
+    fn from(elements: T) -> Self {
+        Set::new(elements)
+    }
+}
+
 impl<T> Serialize for Set<T>
 where
     T: Serialize,
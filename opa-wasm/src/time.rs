@@ -0,0 +1,172 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// A timestamp that serializes to nanoseconds since the epoch, the
+// representation OPA's time.* builtins work with.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct OpaTime(pub DateTime<Utc>);
+
+impl From<DateTime<Utc>> for OpaTime {
+    fn from(datetime: DateTime<Utc>) -> Self {
+        OpaTime(datetime)
+    }
+}
+
+impl From<OpaTime> for DateTime<Utc> {
+    fn from(time: OpaTime) -> Self {
+        time.0
+    }
+}
+
+impl Serialize for OpaTime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::Error;
+
+        // Only about 1677 to 2262 fit in an i64 of nanoseconds
+        let nanos = i128::from(self.0.timestamp()) * 1_000_000_000
+            + i128::from(self.0.timestamp_subsec_nanos());
+        let nanos = i64::try_from(nanos)
+            .map_err(|_| S::Error::custom(format!("{} is out of range for OPA time", self.0)))?;
+        serializer.serialize_i64(nanos)
+    }
+}
+
+impl<'de> Deserialize<'de> for OpaTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct NanosVisitor;
+
+        impl<'de> Visitor<'de> for NanosVisitor {
+            type Value = OpaTime;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("nanoseconds since the epoch")
+            }
+
+            fn visit_i64<E>(self, nanos: i64) -> Result<OpaTime, E> {
+                Ok(OpaTime(Utc.timestamp_nanos(nanos)))
+            }
+
+            fn visit_u64<E>(self, nanos: u64) -> Result<OpaTime, E>
+            where
+                E: de::Error,
+            {
+                let nanos = i64::try_from(nanos).map_err(E::custom)?;
+                self.visit_i64(nanos)
+            }
+        }
+
+        deserializer.deserialize_i64(NanosVisitor)
+    }
+}
+
+// A duration that serializes to nanoseconds, the representation of
+// time.parse_duration_ns, rather than serde's {secs, nanos} struct.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct OpaDuration(pub Duration);
+
+impl From<Duration> for OpaDuration {
+    fn from(duration: Duration) -> Self {
+        OpaDuration(duration)
+    }
+}
+
+impl From<OpaDuration> for Duration {
+    fn from(duration: OpaDuration) -> Self {
+        duration.0
+    }
+}
+
+impl Serialize for OpaDuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::Error;
+
+        let nanos = i64::try_from(self.0.as_nanos()).map_err(S::Error::custom)?;
+        serializer.serialize_i64(nanos)
+    }
+}
+
+impl<'de> Deserialize<'de> for OpaDuration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct NanosVisitor;
+
+        impl<'de> Visitor<'de> for NanosVisitor {
+            type Value = OpaDuration;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a non-negative number of nanoseconds")
+            }
+
+            fn visit_i64<E>(self, nanos: i64) -> Result<OpaDuration, E>
+            where
+                E: de::Error,
+            {
+                let nanos = u64::try_from(nanos).map_err(E::custom)?;
+                self.visit_u64(nanos)
+            }
+
+            fn visit_u64<E>(self, nanos: u64) -> Result<OpaDuration, E> {
+                Ok(OpaDuration(Duration::from_nanos(nanos)))
+            }
+        }
+
+        deserializer.deserialize_i64(NanosVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let time = OpaTime(Utc.ymd(2020, 3, 14).and_hms_nano(15, 9, 26, 535_897_932));
+        let json = serde_json::to_string(&time).unwrap();
+        assert_eq!("1584198566535897932", json);
+        assert_eq!(time, serde_json::from_str::<OpaTime>(&json).unwrap());
+
+        let min = OpaTime(Utc.timestamp_nanos(i64::min_value()));
+        let json = serde_json::to_string(&min).unwrap();
+        assert_eq!(i64::min_value().to_string(), json);
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let time = OpaTime(Utc.ymd(2263, 1, 1).and_hms(0, 0, 0));
+        assert!(serde_json::to_string(&time).is_err());
+        let time = OpaTime(Utc.ymd(1677, 1, 1).and_hms(0, 0, 0));
+        assert!(serde_json::to_string(&time).is_err());
+    }
+
+    #[test]
+    fn test_duration_roundtrip() {
+        let duration = OpaDuration(Duration::new(90, 5));
+        let json = serde_json::to_string(&duration).unwrap();
+        assert_eq!("90000000005", json);
+        assert_eq!(
+            duration,
+            serde_json::from_str::<OpaDuration>(&json).unwrap()
+        );
+
+        assert!(serde_json::from_str::<OpaDuration>("-1").is_err());
+        assert!(
+            serde_json::to_string(&OpaDuration(Duration::from_secs(u64::max_value()))).is_err()
+        );
+    }
+}
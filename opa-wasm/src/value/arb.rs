@@ -0,0 +1,35 @@
+use proptest::prelude::*;
+
+use super::{Number, Value};
+
+pub fn arb_number() -> impl Strategy<Value = Number> {
+    prop_oneof![
+        prop::num::i64::ANY.prop_map(Number::from),
+        prop::num::i64::ANY.prop_map(|i| Number::from(i.to_string())),
+        // Non-finite floats are rejected by the serializer.
+        (prop::num::f64::NORMAL | prop::num::f64::SUBNORMAL | prop::num::f64::ZERO)
+            .prop_map(Number::from),
+        prop::num::f64::ANY.prop_map(|f| Number::from(f.to_string())),
+    ]
+}
+
+pub fn arb_value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        arb_number().prop_map(Value::Number),
+        ".*".prop_map(Value::String),
+    ];
+    leaf.prop_recursive(
+        8,   // 8 levels deep
+        256, // Shoot for maximum size of 256 nodes
+        10,  // We put up to 10 items per collection
+        |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..10).prop_map(Value::Array),
+                prop::collection::btree_map(".*", inner.clone(), 0..10).prop_map(Value::Object),
+                prop::collection::btree_set(inner.clone(), 0..10).prop_map(Value::Set),
+            ]
+        },
+    )
+}
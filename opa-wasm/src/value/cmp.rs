@@ -0,0 +1,180 @@
+use super::{Set, Value};
+
+impl Value {
+    /// Compares two values, allowing numbers at any depth to differ by up to
+    /// `epsilon`. Everything else must match exactly.
+    ///
+    /// Numbers are compared as `f64`, refs included. Sets match when every
+    /// element of each has an approximately equal element in the other.
+    pub fn approx_eq(&self, other: &Value, epsilon: f64) -> bool {
+        match (self, other) {
+            (Value::Number(l), Value::Number(r)) => match (l.as_f64(), r.as_f64()) {
+                (Some(l), Some(r)) => l == r || (l - r).abs() <= epsilon,
+                _ => l == r,
+            },
+            (Value::Array(l), Value::Array(r)) => {
+                l.len() == r.len() && l.iter().zip(r).all(|(l, r)| l.approx_eq(r, epsilon))
+            }
+            (Value::Object(l), Value::Object(r)) => {
+                l.len() == r.len()
+                    && l.iter()
+                        .zip(r)
+                        .all(|((lk, lv), (rk, rv))| lk == rk && lv.approx_eq(rv, epsilon))
+            }
+            (Value::Set(l), Value::Set(r)) => {
+                let contains =
+                    |set: &Set<Value>, v: &Value| set.iter().any(|e| e.approx_eq(v, epsilon));
+                l.len() == r.len()
+                    && l.iter().all(|v| contains(r, v))
+                    && r.iter().all(|v| contains(l, v))
+            }
+            (l, r) => l == r,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::*;
+
+    // One value of each type, in OPA's type order.
+    fn samples() -> Vec<Value> {
+        vec![
+            Value::Null,
+            Value::Bool(false),
+            Value::Number(1.into()),
+            Value::from("a"),
+            Value::Array(vec![]),
+            Value::Object(Default::default()),
+            Value::Set(Default::default()),
+        ]
+    }
+
+    #[test]
+    fn test_ord_type_order() {
+        let samples = samples();
+        for (i, left) in samples.iter().enumerate() {
+            for (j, right) in samples.iter().enumerate() {
+                assert_eq!(i.cmp(&j), left.cmp(right), "{:?} {:?}", left, right);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ord_numbers() {
+        let three = Value::from(3);
+        let float = Value::from(3.0);
+        let reference = Value::Number("3".parse().unwrap());
+        assert_eq!(Ordering::Equal, three.cmp(&float));
+        assert_eq!(Ordering::Equal, three.cmp(&reference));
+        assert_eq!(Ordering::Less, Value::from(2.5).cmp(&three));
+        assert_eq!(Ordering::Greater, reference.cmp(&Value::from(2.5)));
+
+        let big = Value::Number("100000000000000000000".parse().unwrap());
+        assert_eq!(Ordering::Greater, big.cmp(&Value::from(i64::max_value())));
+    }
+
+    #[test]
+    fn test_ord_nested() {
+        let array = |v: Vec<Value>| Value::Array(v);
+        assert_eq!(
+            Ordering::Less,
+            array(vec![Value::from(2.5)]).cmp(&array(vec![Value::from(3)]))
+        );
+        assert_eq!(
+            Ordering::Less,
+            array(vec![Value::from(1)]).cmp(&array(vec![Value::from(1), Value::Null]))
+        );
+
+        // Sets compare by their sorted elements, so 2.5 is compared first
+        let set = Value::from_set_iter(vec![Value::from(3), Value::from(2.5)]);
+        let other = Value::from_set_iter(vec![Value::from(2.7)]);
+        assert_eq!(Ordering::Less, set.cmp(&other));
+
+        let object = |v: Value| vec![("a", v)].into_iter().collect::<Value>();
+        assert_eq!(
+            Ordering::Equal,
+            object(Value::from(1)).cmp(&object(Value::from(1.0)))
+        );
+    }
+
+    #[test]
+    fn test_set_order() {
+        let set = Value::from_set_iter(vec![
+            Value::from("a"),
+            Value::Set(Default::default()),
+            Value::from(3),
+            Value::Array(vec![Value::from(1)]),
+            Value::Null,
+            vec![("k", 1)].into_iter().collect(),
+            Value::from(2.5),
+            Value::Bool(false),
+            Value::Number("10".parse().unwrap()),
+        ]);
+        let ordered = set
+            .as_set()
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["null", "false", "2.5", "3", "10", "\"a\"", "[1]", "{k:1}", "{}"],
+            ordered
+        );
+    }
+
+    #[test]
+    fn test_eq_by_value() {
+        let reference = |s: &str| Value::Number(s.parse().unwrap());
+        let pairs = vec![
+            (Value::from(3), Value::from(3.0)),
+            (Value::from(3), reference("3")),
+            (reference("1e2"), Value::from(100)),
+            (
+                Value::from_set_iter(vec![Value::from(1), Value::from(2.0)]),
+                Value::from_set_iter(vec![Value::from(2), reference("1")]),
+            ),
+            (
+                Value::Array(vec![vec![("a", 1)].into_iter().collect()]),
+                Value::Array(vec![vec![("a", 1.0)].into_iter().collect()]),
+            ),
+        ];
+        for (left, right) in pairs {
+            assert_eq!(left, right);
+            assert_eq!(right, left);
+        }
+
+        let array = Value::Array(vec![Value::from(1), Value::from(2)]);
+        let reversed = Value::Array(vec![Value::from(2), Value::from(1)]);
+        assert_ne!(array, reversed);
+        assert_ne!(Value::from(1), Value::from("1"));
+        assert_eq!(array, array.clone());
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let epsilon = 1e-9;
+        assert!(Value::from(0.1 + 0.2).approx_eq(&Value::from(0.3), epsilon));
+        assert!(Value::from(1).approx_eq(&Value::from(1.0 + 1e-12), epsilon));
+        assert!(Value::Number("0.30000000000000004".parse().unwrap())
+            .approx_eq(&Value::from(0.3), epsilon));
+        assert!(!Value::from(0.3).approx_eq(&Value::from(0.31), epsilon));
+        assert!(Value::from(0.3).approx_eq(&Value::from(0.31), 0.1));
+
+        let nested = |f: f64| {
+            let object = vec![("score", Value::from(f))]
+                .into_iter()
+                .collect::<Value>();
+            Value::Array(vec![object, Value::from_set_iter(vec![f, 2.0])])
+        };
+        assert!(nested(0.1 + 0.2).approx_eq(&nested(0.3), epsilon));
+        assert!(!nested(0.1 + 0.2).approx_eq(&nested(0.4), epsilon));
+
+        assert!(Value::from("a").approx_eq(&Value::from("a"), epsilon));
+        assert!(!Value::from("a").approx_eq(&Value::from("b"), 1.0));
+        assert!(!Value::from(1).approx_eq(&Value::from("1"), 1.0));
+        assert!(!Value::Array(vec![Value::from(1)]).approx_eq(&Value::Array(vec![]), 1.0));
+    }
+}
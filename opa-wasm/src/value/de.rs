@@ -36,8 +36,13 @@ impl<'de> Deserialize<'de> for Value {
             }
 
             #[inline]
-            fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
-                Ok(Value::Number(value.into()))
+            fn visit_f64<E>(self, value: f64) -> Result<Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Number::from_f64(value)
+                    .map(Value::Number)
+                    .ok_or_else(|| serde::de::Error::custom("not a Rego number"))
             }
 
             #[inline]
@@ -0,0 +1,287 @@
+use std::fmt;
+
+use super::pointer::child;
+use super::Value;
+
+/// A difference between two values, at a JSON pointer (RFC 6901) path.
+///
+/// Set members have no path of their own, so they are reported at the path
+/// of the set.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathDiff {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Removed {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl PathDiff {
+    pub fn path(&self) -> &str {
+        match self {
+            PathDiff::Added { path, .. } => path,
+            PathDiff::Removed { path, .. } => path,
+            PathDiff::Changed { path, .. } => path,
+        }
+    }
+}
+
+// The root's path is empty, which reads badly in a message.
+impl fmt::Display for PathDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = match self.path() {
+            "" => "(root)",
+            path => path,
+        };
+        match self {
+            PathDiff::Added { value, .. } => write!(f, "added {}: {}", path, value),
+            PathDiff::Removed { value, .. } => write!(f, "removed {}: {}", path, value),
+            PathDiff::Changed { old, new, .. } => write!(f, "changed {}: {} -> {}", path, old, new),
+        }
+    }
+}
+
+fn diff_into(old: &Value, new: &Value, path: String, diffs: &mut Vec<PathDiff>) {
+    match (old, new) {
+        (Value::Object(ref old), Value::Object(ref new)) => {
+            for (k, v) in old {
+                match new.get(k) {
+                    Some(n) => diff_into(v, n, child(&path, k), diffs),
+                    None => diffs.push(PathDiff::Removed {
+                        path: child(&path, k),
+                        value: v.clone(),
+                    }),
+                }
+            }
+            for (k, v) in new.iter().filter(|(k, _)| !old.contains_key(*k)) {
+                diffs.push(PathDiff::Added {
+                    path: child(&path, k),
+                    value: v.clone(),
+                });
+            }
+        }
+        (Value::Array(ref old), Value::Array(ref new)) => {
+            for (i, (o, n)) in old.iter().zip(new.iter()).enumerate() {
+                diff_into(o, n, child(&path, &i.to_string()), diffs);
+            }
+            for (i, v) in old.iter().enumerate().skip(new.len()) {
+                diffs.push(PathDiff::Removed {
+                    path: child(&path, &i.to_string()),
+                    value: v.clone(),
+                });
+            }
+            for (i, v) in new.iter().enumerate().skip(old.len()) {
+                diffs.push(PathDiff::Added {
+                    path: child(&path, &i.to_string()),
+                    value: v.clone(),
+                });
+            }
+        }
+        (Value::Set(ref old), Value::Set(ref new)) => {
+            for v in old.difference(new) {
+                diffs.push(PathDiff::Removed {
+                    path: path.clone(),
+                    value: v.clone(),
+                });
+            }
+            for v in new.difference(old) {
+                diffs.push(PathDiff::Added {
+                    path: path.clone(),
+                    value: v.clone(),
+                });
+            }
+        }
+        (old, new) if old != new => diffs.push(PathDiff::Changed {
+            path,
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => (),
+    }
+}
+
+impl Value {
+    /// Lists the paths where `other` differs from this value, for reporting
+    /// mismatched decisions in tests.
+    pub fn diff(&self, other: &Value) -> Vec<PathDiff> {
+        let mut diffs = Vec::new();
+        diff_into(self, other, String::new(), &mut diffs);
+        diffs
+    }
+}
+
+/// Asserts that two `Value`s are equal, like `assert_eq!`, but on failure
+/// lists the paths where they differ, from `Value::diff`, instead of
+/// printing both values.
+///
+/// ```should_panic
+/// use opa_wasm::{assert_value_eq, Value};
+///
+/// let expected: Value = vec![("allow", true)].into_iter().collect();
+/// let result: Value = vec![("allow", false)].into_iter().collect();
+/// // panics with "changed /allow: true -> false"
+/// assert_value_eq!(expected, result);
+/// ```
+#[macro_export]
+macro_rules! assert_value_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_value_eq!(@check $left, $right, ::std::string::String::new())
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::assert_value_eq!(@check $left, $right, format!(": {}", format_args!($($arg)+)))
+    };
+    (@check $left:expr, $right:expr, $message:expr) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let (left, right): (&$crate::Value, &$crate::Value) = (left, right);
+                if left != right {
+                    let diffs = left
+                        .diff(right)
+                        .iter()
+                        .map(|diff| format!("\n  {}", diff))
+                        .collect::<::std::string::String>();
+                    panic!("assertion failed: `(left == right)`{}{}", $message, diffs);
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Map, Set};
+    use super::*;
+
+    fn object(entries: Vec<(&str, Value)>) -> Value {
+        Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect::<Map<String, Value>>(),
+        )
+    }
+
+    #[test]
+    fn test_diff_objects() {
+        let old = object(vec![
+            ("allow", true.into()),
+            (
+                "user",
+                object(vec![("name", "alice".into()), ("team/role", "dev".into())]),
+            ),
+        ]);
+        let new = object(vec![
+            ("allow", true.into()),
+            ("reason", "ok".into()),
+            ("user", object(vec![("name", "bob".into())])),
+        ]);
+
+        let expected = vec![
+            PathDiff::Changed {
+                path: "/user/name".to_string(),
+                old: "alice".into(),
+                new: "bob".into(),
+            },
+            PathDiff::Removed {
+                path: "/user/team~1role".to_string(),
+                value: "dev".into(),
+            },
+            PathDiff::Added {
+                path: "/reason".to_string(),
+                value: "ok".into(),
+            },
+        ];
+        assert_eq!(expected, old.diff(&new));
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn test_diff_arrays_and_sets() {
+        let old = Value::from(vec![1, 2]);
+        let new = Value::from(vec![1, 3, 4]);
+        let diffs = old.diff(&new);
+        assert_eq!(2, diffs.len());
+        assert_eq!("/1", diffs[0].path());
+        assert_eq!(
+            PathDiff::Added {
+                path: "/2".to_string(),
+                value: 4.into()
+            },
+            diffs[1]
+        );
+
+        let old = Value::Set(vec![1.into(), 2.into()].into_iter().collect::<Set<_>>());
+        let new = Value::Set(vec![2.into(), 3.into()].into_iter().collect::<Set<_>>());
+        let expected = vec![
+            PathDiff::Removed {
+                path: String::new(),
+                value: 1.into(),
+            },
+            PathDiff::Added {
+                path: String::new(),
+                value: 3.into(),
+            },
+        ];
+        assert_eq!(expected, old.diff(&new));
+
+        let diffs = Value::from(1).diff(&Value::from("1"));
+        assert_eq!(
+            vec![PathDiff::Changed {
+                path: String::new(),
+                old: 1.into(),
+                new: "1".into()
+            }],
+            diffs
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let diffs = object(vec![("a", 1.into()), ("b", 2.into())])
+            .diff(&object(vec![("b", 3.into()), ("c", "x".into())]));
+        let lines = diffs.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            vec!["removed /a: 1", "changed /b: 2 -> 3", "added /c: \"x\""],
+            lines
+        );
+
+        let diffs = Value::from(1).diff(&Value::Null);
+        assert_eq!("changed (root): 1 -> null", diffs[0].to_string());
+    }
+
+    #[test]
+    fn test_assert_value_eq() {
+        let value = object(vec![("user", object(vec![("name", "alice".into())]))]);
+        assert_value_eq!(value, value.clone());
+        assert_value_eq!(Value::from(3), Value::from(3.0), "numbers compare by value");
+    }
+
+    #[test]
+    #[should_panic(expected = "changed /user/name: \"alice\" -> \"bob\"")]
+    fn test_assert_value_eq_reports_path() {
+        let old = object(vec![
+            ("allow", true.into()),
+            ("user", object(vec![("name", "alice".into())])),
+        ]);
+        let new = object(vec![
+            ("allow", true.into()),
+            ("user", object(vec![("name", "bob".into())])),
+        ]);
+        assert_value_eq!(old, new);
+    }
+
+    #[test]
+    #[should_panic(expected = "`(left == right)`: decision for alice\n  added /reason")]
+    fn test_assert_value_eq_message() {
+        let new = object(vec![("reason", "ok".into())]);
+        assert_value_eq!(object(vec![]), new, "decision for {}", "alice");
+    }
+}
@@ -0,0 +1,133 @@
+use std::env;
+
+use super::{Map, Value};
+
+impl Value {
+    /// Collects the environment variables starting with `prefix` into an
+    /// object, to pass runtime configuration to a policy as data. Names are
+    /// split on `_`, so with the prefix `APP`, `APP_DB_HOST=db` becomes
+    /// `{"db": {"host": "db"}}`.
+    ///
+    /// The nesting rules:
+    ///
+    /// - Only names starting with the prefix and then the delimiter are
+    ///   collected, so `APP` doesn't pick up `APPLE`. A prefix already
+    ///   ending with the delimiter works the same way.
+    /// - The rest is split on the delimiter and each part lowercased. Empty
+    ///   parts, from repeated delimiters, are skipped, and a variable with no
+    ///   parts left is ignored.
+    /// - Values are always strings; policies can convert them with
+    ///   `to_number`.
+    /// - A variable that is both a value and a parent, like `APP_DB` next to
+    ///   `APP_DB_HOST`, is nested: the object wins over the string.
+    /// - Variables that aren't valid unicode are ignored.
+    pub fn from_env(prefix: &str) -> Value {
+        Self::from_env_split(prefix, "_")
+    }
+
+    /// Like `from_env`, splitting names on `delimiter` instead of `_`, e.g.
+    /// `__` to keep single underscores within keys.
+    pub fn from_env_split(prefix: &str, delimiter: &str) -> Value {
+        let start = if prefix.ends_with(delimiter) {
+            prefix.to_string()
+        } else {
+            format!("{}{}", prefix, delimiter)
+        };
+        let mut vars = env::vars_os()
+            .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)))
+            .filter(|(k, _)| k.starts_with(&start))
+            .collect::<Vec<_>>();
+        // Sorted, so names differing only in case resolve the same way every
+        // time: the last one wins
+        vars.sort();
+
+        let mut root = Map::new();
+        for (name, value) in vars {
+            let path = split(&name[start.len()..], delimiter);
+            insert(&mut root, &path, value);
+        }
+        Value::Object(root)
+    }
+}
+
+fn split(name: &str, delimiter: &str) -> Vec<String> {
+    if delimiter.is_empty() {
+        return vec![name.to_lowercase()];
+    }
+    name.split(delimiter)
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn insert(map: &mut Map<String, Value>, path: &[String], value: String) {
+    let (key, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    if rest.is_empty() {
+        // Nested values win over the parent's own
+        if let Some(Value::Object(_)) = map.get(key) {
+            return;
+        }
+        map.insert(key.clone(), Value::String(value));
+        return;
+    }
+
+    let child = map
+        .entry(key.clone())
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !child.is_object() {
+        *child = Value::Object(Map::new());
+    }
+    if let Value::Object(child) = child {
+        insert(child, rest, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_from_env() {
+        env::set_var("OPA_ENV_TEST_DB_HOST", "localhost");
+        env::set_var("OPA_ENV_TEST_DB_PORT", "5432");
+        env::set_var("OPA_ENV_TEST_DB", "shadowed");
+        env::set_var("OPA_ENV_TEST_MODE", "strict");
+        env::set_var("OPA_ENV_TEST__LOG__LEVEL", "debug");
+        env::set_var("OPA_ENV_TESTING", "other");
+
+        let expected: Value = serde_json::from_str(
+            r#"{
+                "db": {"host": "localhost", "port": "5432"},
+                "log": {"level": "debug"},
+                "mode": "strict"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(expected, Value::from_env("OPA_ENV_TEST"));
+        assert_eq!(expected, Value::from_env("OPA_ENV_TEST_"));
+        assert_eq!(Value::Object(Map::new()), Value::from_env("OPA_ENV_UNSET"));
+    }
+
+    #[test]
+    fn test_from_env_split() {
+        env::set_var("OPA_SPLIT_TEST__DB_HOST", "localhost");
+        env::set_var("OPA_SPLIT_TEST__DB_PORT", "5432");
+        env::set_var("OPA_SPLIT_TEST__LOG__LEVEL", "debug");
+        env::set_var("OPA_SPLIT_TEST_MODE", "strict");
+
+        let expected: Value = serde_json::from_str(
+            r#"{
+                "db_host": "localhost",
+                "db_port": "5432",
+                "log": {"level": "debug"}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(expected, Value::from_env_split("OPA_SPLIT_TEST", "__"));
+    }
+}
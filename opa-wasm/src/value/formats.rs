@@ -0,0 +1,108 @@
+use super::Value;
+
+#[cfg(feature = "yaml")]
+impl Value {
+    /// Parses a YAML document, for data files. Keys must be strings, as in
+    /// JSON.
+    pub fn from_yaml(s: &str) -> Result<Value, crate::Error> {
+        serde_yaml::from_str(s).map_err(crate::Error::Yaml)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl Value {
+    /// Parses a TOML document, for data files. OPA has no datetime type, so
+    /// datetimes become RFC 3339 strings, which `time.parse_rfc3339_ns`
+    /// reads.
+    pub fn from_toml(s: &str) -> Result<Value, crate::Error> {
+        let value = s.parse::<toml::Value>().map_err(crate::Error::Toml)?;
+        Ok(from_toml_value(value))
+    }
+}
+
+#[cfg(feature = "toml")]
+fn from_toml_value(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(v) => Value::Array(v.into_iter().map(from_toml_value).collect()),
+        toml::Value::Table(m) => Value::Object(
+            m.into_iter()
+                .map(|(k, v)| (k, from_toml_value(v)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected() -> Value {
+        serde_json::from_str(
+            r#"{
+                "users": {
+                    "alice": {"admin": true, "roles": ["dev", "ops"]},
+                    "bob": {"admin": false, "roles": []}
+                },
+                "limit": 10,
+                "ratio": 0.5,
+                "owner": "alice"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_from_yaml() {
+        let doc = r#"
+# Comments are allowed
+users:
+  alice:
+    admin: true
+    roles: [dev, ops]
+  bob:
+    admin: false
+    roles: []
+limit: 10
+ratio: 0.5
+owner: alice
+"#;
+        assert_eq!(expected(), Value::from_yaml(doc).unwrap());
+
+        assert!(Value::from_yaml("users: [alice").is_err());
+        assert!(Value::from_yaml("1: one").is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml() {
+        let doc = r#"
+# Comments are allowed
+limit = 10
+ratio = 0.5
+owner = "alice"
+
+[users.alice]
+admin = true
+roles = ["dev", "ops"]
+
+[users.bob]
+admin = false
+roles = []
+"#;
+        assert_eq!(expected(), Value::from_toml(doc).unwrap());
+
+        let value = Value::from_toml("created = 1979-05-27T07:32:00Z").unwrap();
+        assert_eq!(
+            Some(&Value::from("1979-05-27T07:32:00Z")),
+            value.get("created")
+        );
+
+        assert!(Value::from_toml("limit = ").is_err());
+    }
+}
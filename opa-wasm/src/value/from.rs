@@ -92,8 +92,151 @@ impl<T: Into<Value>> FromIterator<T> for Value {
     }
 }
 
+impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Value {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Value::Object(
+            iter.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Value {
+    /// Collects an iterator into a `Value::Set`.
+    ///
+    /// `collect()` always builds an array, so sets need an explicit
+    /// constructor.
+    pub fn from_set_iter<T, I>(iter: I) -> Self
+    where
+        T: Into<Value>,
+        I: IntoIterator<Item = T>,
+    {
+        Value::Set(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Builds a `Value::Set` from a list of elements of any type convertible
+/// with `Value::from`. Duplicates are dropped.
+///
+/// ```
+/// use opa_wasm::{set, Value};
+///
+/// let roles = set!["admin", "dev", "admin", 1, true];
+/// assert_eq!(4, roles.as_set().map_or(0, |s| s.len()));
+/// assert!(roles.as_set().unwrap().contains(&Value::from("dev")));
+/// ```
+#[macro_export]
+macro_rules! set {
+    ($($value:expr),* $(,)?) => {
+        $crate::Value::from_set_iter::<$crate::Value, ::std::vec::Vec<_>>(::std::vec![
+            $($crate::Value::from($value)),*
+        ])
+    };
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(f: Option<T>) -> Self {
+        f.map_or(Value::Null, Into::into)
+    }
+}
+
 impl From<()> for Value {
     fn from((): ()) -> Self {
         Value::Null
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_scalars() {
+        assert_eq!(Value::String("a".to_string()), Value::from("a"));
+        assert_eq!(Value::String("a".to_string()), Value::from("a".to_string()));
+        assert_eq!(Value::Bool(true), Value::from(true));
+        assert_eq!(Value::Number(Number::from(-1)), Value::from(-1_i64));
+        assert_eq!(Value::Number(Number::from(1)), Value::from(1_u8));
+        assert_eq!(Value::Number(Number::from(1.5)), Value::from(1.5));
+        assert_eq!(Value::Null, Value::from(std::f64::NAN));
+        assert_eq!(Value::Null, Value::from(()));
+    }
+
+    #[test]
+    fn test_from_collections() {
+        let array = Value::from(vec![Value::from(1), Value::from("a")]);
+        assert_eq!(
+            Value::Array(vec![
+                Value::Number(1.into()),
+                Value::String("a".to_string())
+            ]),
+            array
+        );
+        assert_eq!(array, Value::from(vec![Value::from(1), Value::from("a")]));
+        assert_eq!(
+            Value::Array(vec![Value::Bool(true)]),
+            Value::from(vec![true])
+        );
+
+        let mut map = Map::new();
+        map.insert("a".to_string(), Value::from(1));
+        assert_eq!(Value::Object(map.clone()), Value::from(map));
+    }
+
+    #[test]
+    fn test_from_option() {
+        assert_eq!(Value::Null, Value::from(None::<i64>));
+        assert_eq!(Value::from(1), Value::from(Some(1)));
+        assert_eq!(Value::from("a"), Value::from(Some("a")));
+        assert_eq!(
+            Value::Array(vec![Value::from(1), Value::Null]),
+            Value::from(vec![Some(1), None])
+        );
+    }
+
+    #[test]
+    fn test_collect() {
+        let array: Value = (1..=3).collect();
+        assert_eq!(
+            Value::Array(vec![Value::from(1), Value::from(2), Value::from(3)]),
+            array
+        );
+
+        let object: Value = vec![("a", 1), ("b", 2)].into_iter().collect();
+        let mut map = Map::new();
+        map.insert("a".to_string(), Value::from(1));
+        map.insert("b".to_string(), Value::from(2));
+        assert_eq!(Value::Object(map), object);
+
+        let object: Value = vec![("a".to_string(), Value::Null)].into_iter().collect();
+        assert_eq!(
+            Some(&Value::Null),
+            object.as_object().and_then(|o| o.get("a"))
+        );
+
+        let set = Value::from_set_iter(vec!["b", "a", "b"]);
+        let mut expected = Set::new();
+        expected.insert(Value::from("a"));
+        expected.insert(Value::from("b"));
+        assert_eq!(Value::Set(expected), set);
+    }
+
+    #[test]
+    fn test_set_macro() {
+        assert_eq!(Value::Set(Set::new()), crate::set![]);
+
+        let set = crate::set!["a", 1, 1.5, true, (), vec!["b"], "a", 1,];
+        let mut expected = Set::new();
+        expected.insert(Value::from("a"));
+        expected.insert(Value::from(1));
+        expected.insert(Value::from(1.5));
+        expected.insert(Value::Bool(true));
+        expected.insert(Value::Null);
+        expected.insert(Value::Array(vec![Value::from("b")]));
+        assert_eq!(Value::Set(expected), set);
+
+        let nested = crate::set![crate::set![1, 2], crate::set![2, 1]];
+        assert_eq!(1, nested.as_set().map_or(0, |s| s.len()));
+    }
+}
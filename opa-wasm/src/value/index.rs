@@ -42,6 +42,58 @@ impl Index for usize {
     }
 }
 
+// Signed indices count from the end of the array when negative, so -1 is the
+// last element. This is an extension; Rego itself has no negative indexing.
+fn position(index: i64, len: usize) -> Option<usize> {
+    if index >= 0 {
+        Some(index as usize)
+    } else {
+        let back = index.checked_neg()? as usize;
+        len.checked_sub(back)
+    }
+}
+
+macro_rules! impl_index_signed {
+    ($($ty:ty),*) => {
+        $(
+            impl Index for $ty {
+                fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+                    match v {
+                        Value::Array(ref vec) => vec.get(position(*self as i64, vec.len())?),
+                        _ => None,
+                    }
+                }
+
+                fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+                    match v {
+                        Value::Array(ref mut vec) => {
+                            let i = position(*self as i64, vec.len())?;
+                            vec.get_mut(i)
+                        }
+                        _ => None,
+                    }
+                }
+
+                fn index_or_insert<'v>(&self, v: &'v mut Value) -> &'v mut Value {
+                    match v {
+                        Value::Array(ref mut vec) => {
+                            let len = vec.len();
+                            position(*self as i64, len)
+                                .and_then(move |i| vec.get_mut(i))
+                                .unwrap_or_else(|| {
+                                    panic!("cannot access index {} of array of length {}", self, len)
+                                })
+                        }
+                        _ => panic!("cannot access index {} of value {}", self, Type(v)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_index_signed!(i32, i64);
+
 impl Index for str {
     fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
         match *v {
@@ -100,6 +152,8 @@ where
 mod private {
     pub trait Sealed {}
     impl Sealed for usize {}
+    impl Sealed for i32 {}
+    impl Sealed for i64 {}
     impl Sealed for str {}
     impl Sealed for String {}
     impl<'a, T: ?Sized> Sealed for &'a T where T: Sealed {}
@@ -141,3 +195,114 @@ where
         index.index_or_insert(self)
     }
 }
+
+impl Value {
+    /// Follows a path of object keys and array indices, e.g.
+    /// `["servers", "0", "name"]`. Segments index arrays when they parse as
+    /// integers, so negative indices count from the end as with `get`.
+    pub fn at(&self, path: &[&str]) -> Option<&Value> {
+        path.iter().try_fold(self, |value, segment| match value {
+            Value::Array(_) => value.get(segment.parse::<i64>().ok()?),
+            _ => value.get(*segment),
+        })
+    }
+
+    /// Like `at`, with the path given as a dotted string, e.g.
+    /// `"servers.0.name"`. An empty string is the value itself.
+    pub fn lookup(&self, path: &str) -> Option<&Value> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        self.at(&path.split('.').collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn array() -> Value {
+        Value::Array(vec![Value::from(1), Value::from(2), Value::from(3)])
+    }
+
+    #[test]
+    fn test_index_positive() {
+        let value = array();
+        assert_eq!(Some(&Value::from(1)), value.get(0_usize));
+        assert_eq!(Some(&Value::from(2)), value.get(1_i32));
+        assert_eq!(Some(&Value::from(3)), value.get(2_i64));
+        assert_eq!(Value::from(3), value[2]);
+    }
+
+    #[test]
+    fn test_index_negative() {
+        let mut value = array();
+        assert_eq!(Some(&Value::from(3)), value.get(-1));
+        assert_eq!(Some(&Value::from(1)), value.get(-3_i64));
+        assert_eq!(Value::from(2), value[-2]);
+
+        value[-1] = Value::Null;
+        assert_eq!(Some(&Value::Null), value.get(2_usize));
+    }
+
+    #[test]
+    fn test_index_out_of_range() {
+        let value = array();
+        assert_eq!(None, value.get(3_usize));
+        assert_eq!(None, value.get(3));
+        assert_eq!(None, value.get(-4));
+        assert_eq!(None, value.get(i64::min_value()));
+        assert_eq!(Value::Null, value[10]);
+        assert_eq!(None, Value::Null.get(0));
+    }
+
+    fn servers() -> Value {
+        let mut db = BTreeMap::new();
+        db.insert("name".to_string(), Value::from("db"));
+        db.insert("ports".to_string(), Value::from(vec![5432, 5433]));
+        let mut web = BTreeMap::new();
+        web.insert("name".to_string(), Value::from("web"));
+        let mut root = BTreeMap::new();
+        root.insert(
+            "servers".to_string(),
+            Value::Array(vec![Value::Object(db), Value::Object(web)]),
+        );
+        Value::Object(root)
+    }
+
+    #[test]
+    fn test_at() {
+        let value = servers();
+        assert_eq!(
+            Some(&Value::from("db")),
+            value.at(&["servers", "0", "name"])
+        );
+        assert_eq!(
+            Some(&Value::from("web")),
+            value.at(&["servers", "-1", "name"])
+        );
+        assert_eq!(
+            Some(&Value::from(5433)),
+            value.at(&["servers", "0", "ports", "1"])
+        );
+        assert_eq!(Some(&value), value.at(&[]));
+    }
+
+    #[test]
+    fn test_lookup() {
+        let value = servers();
+        assert_eq!(Some(&Value::from("db")), value.lookup("servers.0.name"));
+        assert_eq!(Some(&Value::from(5432)), value.lookup("servers.0.ports.0"));
+        assert_eq!(Some(&value), value.lookup(""));
+    }
+
+    #[test]
+    fn test_lookup_missing() {
+        let value = servers();
+        assert_eq!(None, value.lookup("servers.2.name"));
+        assert_eq!(None, value.lookup("servers.first.name"));
+        assert_eq!(None, value.lookup("servers.1.ports"));
+        assert_eq!(None, value.lookup("servers.0.name.first"));
+        assert_eq!(None, value.lookup("clients"));
+    }
+}
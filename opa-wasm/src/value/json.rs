@@ -0,0 +1,207 @@
+use std::fmt::Write;
+
+use serde_json::Number as JsonNumber;
+
+use super::{Number, Value};
+
+impl From<Number> for serde_json::Value {
+    fn from(n: Number) -> Self {
+        if let Some(i) = n.as_i64() {
+            return serde_json::Value::Number(i.into());
+        }
+        n.as_f64()
+            .and_then(JsonNumber::from_f64)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null)
+    }
+}
+
+// Sets have no JSON representation, so they are converted to arrays, the
+// same way OPA renders them.
+impl From<Value> for serde_json::Value {
+    fn from(v: Value) -> Self {
+        match v {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(b),
+            Value::Number(n) => n.into(),
+            Value::String(s) => serde_json::Value::String(s),
+            Value::Array(v) => serde_json::Value::Array(v.into_iter().map(Into::into).collect()),
+            Value::Object(m) => {
+                serde_json::Value::Object(m.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+            Value::Set(s) => serde_json::Value::Array(s.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+impl Value {
+    /// Renders the value as compact JSON, following the conventions of OPA's
+    /// Go encoding/json.
+    ///
+    /// Object keys are sorted, sets become arrays sorted in OPA's order,
+    /// numbers use Go's formatting and number refs are written as is.
+    /// Strings escape `<`, `>` and `&` and the line and paragraph separators
+    /// like Go does. The output isn't checked against OPA's, so compare
+    /// parsed values rather than bytes.
+    pub fn to_opa_json(&self) -> String {
+        let mut out = String::new();
+        write_opa_json(self, &mut out);
+        out
+    }
+}
+
+fn write_opa_json(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        // Go refuses to encode NaN and infinities, which only floats can hold
+        Value::Number(n) if n.is_f64() && matches!(n.as_f64(), Some(f) if !f.is_finite()) => {
+            out.push_str("null")
+        }
+        Value::Number(n) => {
+            let _ = write!(out, "{}", n);
+        }
+        Value::String(s) => write_opa_string(s, out),
+        Value::Array(v) => write_opa_seq(v.iter(), out),
+        Value::Object(m) => {
+            out.push('{');
+            for (i, (k, v)) in m.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_opa_string(k, out);
+                out.push(':');
+                write_opa_json(v, out);
+            }
+            out.push('}');
+        }
+        // Sets iterate in OPA's order, see Value::Set
+        Value::Set(s) => write_opa_seq(s.iter(), out),
+    }
+}
+
+fn write_opa_seq<'a, I: Iterator<Item = &'a Value>>(values: I, out: &mut String) {
+    out.push('[');
+    for (i, v) in values.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_opa_json(v, out);
+    }
+    out.push(']');
+}
+
+fn write_opa_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '<' | '>' | '&' | '\u{2028}' | '\u{2029}' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(feature = "json5")]
+impl Value {
+    /// Parses a JSON5 document, which allows comments, trailing commas and
+    /// unquoted keys, for data files written by hand.
+    pub fn from_json5(s: &str) -> Result<Value, crate::Error> {
+        json5::from_str(s).map_err(crate::Error::Json5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::super::{Map, Set};
+    use super::*;
+
+    #[test]
+    fn test_into_json() {
+        let mut set = Set::new();
+        set.insert(Value::from(2));
+        set.insert(Value::from(1));
+        let mut map = Map::new();
+        map.insert("set".to_string(), Value::Set(set));
+        map.insert("float".to_string(), Value::from(1.5));
+        map.insert("null".to_string(), Value::Null);
+
+        let json = serde_json::Value::from(Value::Object(map));
+        assert_eq!(json!({"set": [1, 2], "float": 1.5, "null": null}), json);
+    }
+
+    #[test]
+    fn test_to_opa_json() {
+        let value = serde_json::from_str::<Value>(
+            r#"{"b": [1, 2.5, "x"], "a": null, "c": {"d": true, "B": false}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            r#"{"a":null,"b":[1,2.5,"x"],"c":{"B":false,"d":true}}"#,
+            value.to_opa_json()
+        );
+
+        let set = Value::from_set_iter(vec![
+            Value::from("a"),
+            Value::from(3),
+            Value::from(vec![1]),
+            Value::from(1.5),
+            Value::Null,
+        ]);
+        assert_eq!(r#"[null,1.5,3,"a",[1]]"#, set.to_opa_json());
+
+        let numbers = Value::from(vec![
+            Value::from(100.0),
+            Value::from(1e21),
+            Value::from(0.0000001),
+            Value::from(-0.5),
+            Value::Number("123456789012345678901234567890".parse().unwrap()),
+            Value::from(std::f64::NAN),
+        ]);
+        assert_eq!(
+            "[100,1e+21,1e-7,-0.5,123456789012345678901234567890,null]",
+            numbers.to_opa_json()
+        );
+
+        let s = Value::from("<a & b>\n\"q\"\\\u{1}\u{2028}é☃");
+        assert_eq!(
+            r#""\u003ca \u0026 b\u003e\n\"q\"\\\u0001\u2028é☃""#,
+            s.to_opa_json()
+        );
+    }
+
+    #[cfg(feature = "json5")]
+    #[test]
+    fn test_from_json5() {
+        let doc = r#"{
+            // Comments are allowed
+            users: {
+                alice: { admin: true, roles: ['dev', 'ops',] },
+            },
+            /* and trailing commas */
+            limit: 10,
+            ratio: 0.5,
+        }"#;
+        let value = Value::from_json5(doc).unwrap();
+        let expected = json!({
+            "users": {"alice": {"admin": true, "roles": ["dev", "ops"]}},
+            "limit": 10,
+            "ratio": 0.5,
+        });
+        assert_eq!(expected, serde_json::Value::from(value));
+
+        assert!(Value::from_json5("{ limit: }").is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use super::pointer::child;
+use super::Value;
+use crate::Error;
+
+/// How `Value::merge_with` resolves a key both values define, where at least
+/// one side isn't an object. Objects are always merged key by key.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MergeStrategy {
+    /// The new value replaces the existing one.
+    Overwrite,
+    /// The existing value is kept.
+    KeepExisting,
+    /// Arrays are concatenated, existing elements first. Other values are
+    /// overwritten.
+    AppendArrays,
+    /// Sets are unioned. Other values are overwritten.
+    UnionSets,
+    /// Differing values fail with `Error::MergeConflict`, leaving the
+    /// existing value unchanged. Equal values aren't a conflict.
+    Error,
+}
+
+impl Default for MergeStrategy {
+    fn default() -> Self {
+        MergeStrategy::Overwrite
+    }
+}
+
+fn merge_at(
+    this: &mut Value,
+    other: Value,
+    strategy: MergeStrategy,
+    path: &str,
+) -> Result<(), Error> {
+    match (this, other, strategy) {
+        (Value::Object(ref mut left), Value::Object(right), _) => {
+            for (k, v) in right {
+                match left.get_mut(&k) {
+                    Some(existing) => merge_at(existing, v, strategy, &child(path, &k))?,
+                    None => {
+                        left.insert(k, v);
+                    }
+                }
+            }
+        }
+        (_, _, MergeStrategy::KeepExisting) => (),
+        (Value::Array(ref mut left), Value::Array(right), MergeStrategy::AppendArrays) => {
+            left.extend(right)
+        }
+        (Value::Set(ref mut left), Value::Set(right), MergeStrategy::UnionSets) => {
+            left.extend(right)
+        }
+        (this, other, MergeStrategy::Error) if *this != other => {
+            return Err(Error::MergeConflict(path.to_string()));
+        }
+        (this, other, _) => *this = other,
+    }
+    Ok(())
+}
+
+impl Value {
+    /// Deep merges `other` into this value. Objects are merged key by key;
+    /// any other value in `other` replaces the existing one.
+    pub fn merge(&mut self, other: Value) {
+        // Overwriting never conflicts
+        let _ = merge_at(self, other, MergeStrategy::Overwrite, "");
+    }
+
+    /// Like `merge`, resolving values both sides define with `strategy`.
+    /// Conflicts are reported with the JSON pointer of the key, e.g.
+    /// `/users/alice/role`.
+    pub fn merge_with(&mut self, other: Value, strategy: MergeStrategy) -> Result<(), Error> {
+        if strategy != MergeStrategy::Error {
+            return merge_at(self, other, strategy, "");
+        }
+
+        // Merge into a copy, so a conflict leaves this value as it was
+        let mut merged = self.clone();
+        merge_at(&mut merged, other, strategy, "")?;
+        *self = merged;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Map;
+    use super::*;
+
+    fn object(entries: Vec<(&str, Value)>) -> Value {
+        Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect::<Map<String, Value>>(),
+        )
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut base = object(vec![
+            (
+                "user",
+                object(vec![("name", Value::String("alice".to_string()))]),
+            ),
+            (
+                "roles",
+                Value::Array(vec![Value::String("admin".to_string())]),
+            ),
+            ("method", Value::String("GET".to_string())),
+        ]);
+        let delta = object(vec![
+            ("user", object(vec![("id", Value::Null)])),
+            ("roles", Value::Array(vec![])),
+            ("path", Value::String("/".to_string())),
+        ]);
+        base.merge(delta);
+
+        let expected = object(vec![
+            (
+                "user",
+                object(vec![
+                    ("name", Value::String("alice".to_string())),
+                    ("id", Value::Null),
+                ]),
+            ),
+            ("roles", Value::Array(vec![])),
+            ("method", Value::String("GET".to_string())),
+            ("path", Value::String("/".to_string())),
+        ]);
+        assert_eq!(expected, base);
+    }
+
+    #[test]
+    fn test_merge_replaces_non_objects() {
+        let mut base = Value::Null;
+        base.merge(object(vec![("a", Value::Bool(true))]));
+        assert_eq!(object(vec![("a", Value::Bool(true))]), base);
+
+        base.merge(Value::Bool(false));
+        assert_eq!(Value::Bool(false), base);
+    }
+
+    fn from_json(json: &str) -> Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn with_roles(json: &str, roles: Vec<&str>) -> Value {
+        let mut value = from_json(json);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("roles".to_string(), Value::from_set_iter(roles));
+        value
+    }
+
+    fn merged(strategy: MergeStrategy) -> Result<Value, Error> {
+        let mut base = with_roles(
+            r#"{"a": {"x": 1, "tags": ["a"]}, "b": true, "c": null}"#,
+            vec!["dev"],
+        );
+        let other = with_roles(
+            r#"{"a": {"x": 2, "tags": ["b"], "y": 3}, "b": true, "d": 4}"#,
+            vec!["ops"],
+        );
+        base.merge_with(other, strategy).map(|_| base)
+    }
+
+    #[test]
+    fn test_merge_overwrite() {
+        let expected = with_roles(
+            r#"{"a": {"x": 2, "tags": ["b"], "y": 3}, "b": true, "c": null, "d": 4}"#,
+            vec!["ops"],
+        );
+        assert_eq!(expected, merged(MergeStrategy::Overwrite).unwrap());
+        assert_eq!(MergeStrategy::Overwrite, MergeStrategy::default());
+    }
+
+    #[test]
+    fn test_merge_keep_existing() {
+        let expected = with_roles(
+            r#"{"a": {"x": 1, "tags": ["a"], "y": 3}, "b": true, "c": null, "d": 4}"#,
+            vec!["dev"],
+        );
+        assert_eq!(expected, merged(MergeStrategy::KeepExisting).unwrap());
+    }
+
+    #[test]
+    fn test_merge_append_arrays() {
+        let expected = with_roles(
+            r#"{"a": {"x": 2, "tags": ["a", "b"], "y": 3}, "b": true, "c": null, "d": 4}"#,
+            vec!["ops"],
+        );
+        assert_eq!(expected, merged(MergeStrategy::AppendArrays).unwrap());
+    }
+
+    #[test]
+    fn test_merge_union_sets() {
+        let expected = with_roles(
+            r#"{"a": {"x": 2, "tags": ["b"], "y": 3}, "b": true, "c": null, "d": 4}"#,
+            vec!["dev", "ops"],
+        );
+        assert_eq!(expected, merged(MergeStrategy::UnionSets).unwrap());
+    }
+
+    #[test]
+    fn test_merge_error() {
+        let err = merged(MergeStrategy::Error).unwrap_err();
+        assert!(matches!(err, Error::MergeConflict(ref path) if path == "/a/tags"));
+
+        // Equal values and new keys don't conflict
+        let mut base = from_json(r#"{"a/b": {"x": 1}, "y": [1]}"#);
+        base.merge_with(
+            from_json(r#"{"a/b": {"x": 1, "z": 2}, "y": [1]}"#),
+            MergeStrategy::Error,
+        )
+        .unwrap();
+        assert_eq!(from_json(r#"{"a/b": {"x": 1, "z": 2}, "y": [1]}"#), base);
+
+        // A conflict leaves the value unchanged
+        let before = base.clone();
+        let err = base
+            .merge_with(
+                from_json(r#"{"w": 0, "a/b": {"x": 2}}"#),
+                MergeStrategy::Error,
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::MergeConflict(ref path) if path == "/a~1b/x"));
+        assert_eq!(before, base);
+    }
+}
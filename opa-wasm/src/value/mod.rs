@@ -1,21 +1,51 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
+#[cfg(any(test, feature = "fuzzing"))]
+#[doc(hidden)]
+pub mod arb;
+mod cmp;
 mod de;
+mod diff;
+#[cfg(feature = "env")]
+mod env;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+mod formats;
 mod from;
 mod index;
+mod json;
+mod merge;
 pub(crate) mod number;
+mod pointer;
+mod query;
+mod retain;
+mod schema;
 mod ser;
+mod size;
+mod visit;
 
 use crate::error::Error;
 
+pub(crate) use self::pointer::{
+    child as pointer_child, parse_index as pointer_index, tokens as pointer_tokens,
+};
+
+pub use self::diff::PathDiff;
 pub use self::index::Index;
+pub use self::merge::MergeStrategy;
 pub use self::number::Number;
 
 pub type Map<K, V> = BTreeMap<K, V>;
 pub type Set<V> = BTreeSet<V>;
 
-#[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
+/// A value as OPA sees it.
+///
+/// `Value`'s `Ord` is OPA's total order. Values of different types order by
+/// type: null, booleans, numbers, strings, arrays, objects and then sets.
+/// Within a type, numbers compare by numeric value whichever way they are
+/// stored, arrays element by element with a prefix first, objects as their
+/// sorted (key, value) pairs and sets as their sorted elements.
+#[derive(Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Value {
     Null,
     Bool(bool),
@@ -23,6 +53,7 @@ pub enum Value {
     String(String),
     Array(Vec<Value>),
     Object(Map<String, Value>),
+    /// Iterates in OPA's order, the order OPA writes sets out in.
     Set(Set<Value>),
 }
 
@@ -276,4 +307,72 @@ impl Value {
     pub fn is_null(&self) -> bool {
         self.as_null().is_some()
     }
+
+    // Sorts an array in OPA's order: null, booleans, numbers, strings,
+    // arrays, objects, then sets. Other values are left as is.
+    pub fn sort(&mut self) {
+        if let Value::Array(ref mut v) = self {
+            v.sort();
+        }
+    }
+
+    // Removes consecutive equal elements from an array. Sort first to remove
+    // all duplicates.
+    pub fn dedup(&mut self) {
+        if let Value::Array(ref mut v) = self {
+            v.dedup();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort() {
+        let mut value = Value::Array(vec![
+            Value::Set(Set::new()),
+            Value::Object(Map::new()),
+            Value::Array(vec![]),
+            Value::from("a"),
+            Value::from(2),
+            Value::from(1),
+            Value::from(true),
+            Value::from(false),
+            Value::Null,
+        ]);
+        value.sort();
+        let expected = Value::Array(vec![
+            Value::Null,
+            Value::from(false),
+            Value::from(true),
+            Value::from(1),
+            Value::from(2),
+            Value::from("a"),
+            Value::Array(vec![]),
+            Value::Object(Map::new()),
+            Value::Set(Set::new()),
+        ]);
+        assert_eq!(expected, value);
+
+        let mut value = Value::from("b");
+        value.sort();
+        assert_eq!(Value::from("b"), value);
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut value = Value::from(vec![1, 1, 2, 1, 1]);
+        value.dedup();
+        assert_eq!(Value::from(vec![1, 2, 1]), value);
+
+        value.sort();
+        value.dedup();
+        assert_eq!(Value::from(vec![1, 2]), value);
+
+        let mut value = Value::Null;
+        value.dedup();
+        assert_eq!(Value::Null, value);
+    }
 }
@@ -1,6 +1,11 @@
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
 use ordered_float::OrderedFloat;
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
 use serde::de::{self, Visitor};
 use serde::{forward_to_deserialize_any, Deserialize, Deserializer, Serialize, Serializer};
 
@@ -8,12 +13,12 @@ use crate::Error;
 
 pub(crate) const TOKEN: &str = "$policy::value::private::Number";
 
-#[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone)]
 pub struct Number {
     n: N,
 }
 
-#[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone)]
 enum N {
     Int(i64),
     Float(OrderedFloat<f64>),
@@ -86,6 +91,27 @@ impl Number {
         }
     }
 
+    /// Returns the number as an exact decimal, or `None` when it doesn't fit
+    /// in one. Floats convert from their shortest representation, so
+    /// `0.1` becomes exactly `0.1`.
+    #[cfg(feature = "decimal")]
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self.n {
+            N::Int(n) => Some(n.into()),
+            N::Float(f) if f.is_finite() => parse_decimal(&self.to_string()),
+            N::Float(_) => None,
+            N::Ref(ref s) => parse_decimal(s),
+        }
+    }
+
+    // Bytes allocated on the heap, which is only the text of a ref.
+    pub(crate) fn heap_size(&self) -> usize {
+        match self.n {
+            N::Ref(ref s) => s.len(),
+            _ => 0,
+        }
+    }
+
     #[inline]
     pub fn from_f64(f: f64) -> Option<Number> {
         if f.is_finite() {
@@ -97,16 +123,144 @@ impl Number {
     }
 }
 
+// Arithmetic follows OPA's promotion rules: integers stay exact, overflowing
+// into an arbitrary precision ref instead of wrapping, and anything involving
+// a float is computed as a float.
+impl Number {
+    pub fn checked_add(&self, other: &Number) -> Result<Number, Error> {
+        #[cfg(feature = "decimal")]
+        {
+            if let Some(n) = self.decimal_arith(other, Decimal::checked_add) {
+                return Ok(n);
+            }
+        }
+        self.arith(other, i128::checked_add, |a, b| a + b)
+    }
+
+    pub fn checked_sub(&self, other: &Number) -> Result<Number, Error> {
+        #[cfg(feature = "decimal")]
+        {
+            if let Some(n) = self.decimal_arith(other, Decimal::checked_sub) {
+                return Ok(n);
+            }
+        }
+        self.arith(other, i128::checked_sub, |a, b| a - b)
+    }
+
+    pub fn checked_mul(&self, other: &Number) -> Result<Number, Error> {
+        #[cfg(feature = "decimal")]
+        {
+            if let Some(n) = self.decimal_arith(other, Decimal::checked_mul) {
+                return Ok(n);
+            }
+        }
+        self.arith(other, i128::checked_mul, |a, b| a * b)
+    }
+
+    // With the decimal feature, anything that isn't integer arithmetic is
+    // computed exactly, so 0.1 + 0.2 is 0.3. Operands or results that don't
+    // fit in a decimal fall back to floats.
+    #[cfg(feature = "decimal")]
+    fn decimal_arith(
+        &self,
+        other: &Number,
+        op: fn(Decimal, Decimal) -> Option<Decimal>,
+    ) -> Option<Number> {
+        if self.as_i128().is_some() && other.as_i128().is_some() {
+            return None;
+        }
+        op(self.as_decimal()?, other.as_decimal()?).map(Number::from)
+    }
+
+    fn arith<I, F>(&self, other: &Number, int_op: I, float_op: F) -> Result<Number, Error>
+    where
+        I: Fn(i128, i128) -> Option<i128>,
+        F: Fn(f64, f64) -> f64,
+    {
+        if let (Some(a), Some(b)) = (self.as_i128(), other.as_i128()) {
+            if let Some(result) = int_op(a, b) {
+                return Ok(Number::from_i128(result));
+            }
+        }
+
+        match (self.as_f64(), other.as_f64()) {
+            (Some(a), Some(b)) => {
+                let result = float_op(a, b);
+                Number::from_f64(result).ok_or_else(|| Error::InvalidNumber(result.to_string()))
+            }
+            (None, _) => Err(Error::InvalidNumber(self.to_string())),
+            (_, None) => Err(Error::InvalidNumber(other.to_string())),
+        }
+    }
+
+    fn as_i128(&self) -> Option<i128> {
+        match self.n {
+            N::Int(n) => Some(n.into()),
+            N::Float(_) => None,
+            N::Ref(ref s) => s.parse().ok(),
+        }
+    }
+
+    fn from_i128(i: i128) -> Number {
+        let n = if i >= i64::min_value() as i128 && i <= i64::max_value() as i128 {
+            N::Int(i as i64)
+        } else {
+            N::Ref(i.to_string())
+        };
+        Number { n }
+    }
+}
+
+// Parses a JSON number into a decimal, exponent included.
+#[cfg(feature = "decimal")]
+fn parse_decimal(s: &str) -> Option<Decimal> {
+    if s.contains(&['e', 'E'][..]) {
+        Decimal::from_scientific(s).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+// Decimals are stored as refs, in their shortest form, so they keep their
+// precision through the instance.
+#[cfg(feature = "decimal")]
+impl From<Decimal> for Number {
+    fn from(d: Decimal) -> Self {
+        let n = N::Ref(d.normalize().to_string());
+        Number { n }
+    }
+}
+
 impl fmt::Display for Number {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match self.n {
             N::Int(i) => fmt::Display::fmt(&i, formatter),
-            N::Float(f) => fmt::Display::fmt(&f, formatter),
+            N::Float(f) => fmt_float(f.into_inner(), formatter),
             N::Ref(ref s) => fmt::Display::fmt(&s, formatter),
         }
     }
 }
 
+// Formats a float the way OPA's Go encoding/json does: the shortest digits
+// that round trip, in plain notation except for very small or very large
+// magnitudes, which use an exponent like "1e-7" or "1e+21".
+fn fmt_float(f: f64, formatter: &mut fmt::Formatter) -> fmt::Result {
+    let abs = f.abs();
+    if abs == 0.0 || (1e-6..1e21).contains(&abs) {
+        return fmt::Display::fmt(&f, formatter);
+    }
+
+    let s = format!("{:e}", f);
+    match s.find('e') {
+        Some(i) if !s[i + 1..].starts_with('-') => {
+            formatter.write_str(&s[..=i])?;
+            formatter.write_str("+")?;
+            formatter.write_str(&s[i + 1..])
+        }
+        _ => formatter.write_str(&s),
+    }
+}
+
 impl fmt::Debug for Number {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         let mut debug = formatter.debug_tuple("Number");
@@ -125,6 +279,138 @@ impl fmt::Debug for Number {
     }
 }
 
+// Numbers compare by value whichever way they are stored, as in OPA, so the
+// integer 3, the float 3.0 and the ref "3" are equal and sort together. Each
+// number maps to its nearest f64 and, when it has one, its exact value: an
+// integer, including integral floats, or with the decimal feature a decimal.
+// Numbers sort by the f64 first and then by the exact value, which keeps the
+// order transitive when integers past 2^53 meet floats, with NaN after every
+// other number. Without the decimal feature, refs that aren't plain integers
+// are only as precise as an f64. Refs that aren't numbers at all, which only
+// come from the instance, sort last by their text.
+impl Ord for Number {
+    fn cmp(&self, other: &Number) -> Ordering {
+        match (self.approx(), other.approx()) {
+            (Ok(l), Ok(r)) => l
+                .cmp(&r)
+                .then_with(|| self.exact(l.into_inner()).cmp(&other.exact(r.into_inner()))),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(l), Err(r)) => l.cmp(r),
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Number {}
+
+// Hashes the nearest f64 that numbers sort by first, so the ref "3", the
+// integer 3 and the float 3.0 all hash the same, like they compare.
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.approx() {
+            Ok(f) => f.hash(state),
+            Err(s) => s.hash(state),
+        }
+    }
+}
+
+impl Number {
+    // The nearest f64, or the text of a ref that isn't a number.
+    fn approx(&self) -> Result<OrderedFloat<f64>, &str> {
+        let f = match self.n {
+            N::Int(n) => n as f64,
+            N::Float(f) => f.into_inner(),
+            N::Ref(ref s) => s.parse().map_err(|_| s.as_str())?,
+        };
+        // -0.0 is the same number as 0.0
+        Ok(OrderedFloat(if f == 0.0 { 0.0 } else { f }))
+    }
+
+    // The exact value, given the nearest f64 from approx.
+    fn exact(&self, approx: f64) -> Exact {
+        match self.n {
+            N::Int(n) => Exact::Int(n.into()),
+            N::Float(_) => {
+                let exact = Exact::integral(approx);
+                #[cfg(feature = "decimal")]
+                {
+                    if let (Exact::Inexact, Some(d)) = (&exact, self.as_decimal()) {
+                        return Exact::Dec(d);
+                    }
+                }
+                exact
+            }
+            N::Ref(ref s) => {
+                if let Ok(i) = s.parse() {
+                    return Exact::Int(i);
+                }
+                #[cfg(feature = "decimal")]
+                {
+                    if let Some(d) = parse_decimal(s) {
+                        return Exact::Dec(d);
+                    }
+                }
+                Exact::integral(approx)
+            }
+        }
+    }
+}
+
+enum Exact {
+    Int(i128),
+    #[cfg(feature = "decimal")]
+    Dec(Decimal),
+    Inexact,
+}
+
+impl Exact {
+    fn integral(f: f64) -> Exact {
+        if f.fract() == 0.0 && f >= i128::min_value() as f64 && f < i128::max_value() as f64 {
+            Exact::Int(f as i128)
+        } else {
+            Exact::Inexact
+        }
+    }
+
+    // Exact values compare by value, and sort before inexact ones that share
+    // their nearest f64.
+    fn cmp(&self, other: &Exact) -> Ordering {
+        match (self, other) {
+            (Exact::Int(l), Exact::Int(r)) => l.cmp(r),
+            #[cfg(feature = "decimal")]
+            (Exact::Dec(l), Exact::Dec(r)) => l.cmp(r),
+            #[cfg(feature = "decimal")]
+            (Exact::Int(l), Exact::Dec(r)) => cmp_int_decimal(*l, r),
+            #[cfg(feature = "decimal")]
+            (Exact::Dec(l), Exact::Int(r)) => cmp_int_decimal(*r, l).reverse(),
+            (Exact::Inexact, Exact::Inexact) => Ordering::Equal,
+            (Exact::Inexact, _) => Ordering::Greater,
+            (_, Exact::Inexact) => Ordering::Less,
+        }
+    }
+}
+
+// Integers too large for a decimal are further from zero than any decimal.
+#[cfg(feature = "decimal")]
+fn cmp_int_decimal(i: i128, d: &Decimal) -> Ordering {
+    match i.to_string().parse::<Decimal>() {
+        Ok(i) => i.cmp(d),
+        Err(_) => i.cmp(&0),
+    }
+}
+
 macro_rules! impl_from_int {
     ( $($ty:ty),* ) => {
         $(
@@ -155,8 +441,67 @@ macro_rules! impl_from_float {
     }
 }
 
+// Unlike from_f64 these don't reject NaN or infinities. OrderedFloat still
+// gives them a total order, so sets and maps holding one stay consistent, and
+// the serializer refuses to write them into an instance.
 impl_from_float!(f32, f64);
 
+// Accepts the JSON number grammar: -?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?
+fn is_number(s: &str) -> bool {
+    fn digits(bytes: &[u8], i: &mut usize) -> usize {
+        let start = *i;
+        while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+            *i += 1;
+        }
+        *i - start
+    }
+
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    if bytes.get(i) == Some(&b'-') {
+        i += 1;
+    }
+    match bytes.get(i) {
+        Some(b'0') => i += 1,
+        Some(b'1'..=b'9') => {
+            digits(bytes, &mut i);
+        }
+        _ => return false,
+    }
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        if digits(bytes, &mut i) == 0 {
+            return false;
+        }
+    }
+    if let Some(b'e') | Some(b'E') = bytes.get(i) {
+        i += 1;
+        if let Some(b'+') | Some(b'-') = bytes.get(i) {
+            i += 1;
+        }
+        if digits(bytes, &mut i) == 0 {
+            return false;
+        }
+    }
+    i == bytes.len()
+}
+
+/// Parses a number, keeping its exact textual representation so that
+/// integers and floats outside the range of i64/f64 don't lose precision.
+impl FromStr for Number {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !is_number(s) {
+            return Err(Error::InvalidNumber(s.to_string()));
+        }
+        let n = N::Ref(s.to_string());
+        Ok(Number { n })
+    }
+}
+
+// Unlike from_str, this does not validate the string. It's used for numbers
+// read back from the instance, which are always valid.
 impl From<String> for Number {
     fn from(s: String) -> Self {
         let n = N::Ref(s);
@@ -373,3 +718,304 @@ impl<'de, 'a> Deserializer<'de> for &'a Number {
         ignored_any
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Value;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_from_str() {
+        let big = "123456789012345678901234567890";
+        let n = Number::from_str(big).unwrap();
+        assert_eq!(big, n.to_string());
+        assert!(!n.is_f64());
+
+        let precise = "3.14159265358979323846264338327950288";
+        let n = precise.parse::<Number>().unwrap();
+        assert_eq!(precise, n.to_string());
+        assert!(n.is_f64());
+
+        for valid in &["0", "-0", "-12", "1.5", "1e10", "2.5E-3", "-0.0e+1"] {
+            assert!(Number::from_str(valid).is_ok(), "{}", valid);
+        }
+    }
+
+    #[test]
+    fn test_display_float() {
+        let cases = [
+            (1.0, "1"),
+            (-2.0, "-2"),
+            (0.0, "0"),
+            (-0.0, "-0"),
+            (1.5, "1.5"),
+            (0.1, "0.1"),
+            (0.000001, "0.000001"),
+            (0.0000001, "1e-7"),
+            (-1.25e-10, "-1.25e-10"),
+            (5e-324, "5e-324"),
+            (123456789.125, "123456789.125"),
+            (1e20, "100000000000000000000"),
+            (1e21, "1e+21"),
+            (-1.5e300, "-1.5e+300"),
+        ];
+        for (f, expected) in cases.iter() {
+            assert_eq!(*expected, Number::from(*f).to_string(), "{:?}", f);
+        }
+
+        // Refs print exactly as they were read
+        for s in &["1e-7", "1.0", "1E+21", "0.10"] {
+            assert_eq!(*s, s.parse::<Number>().unwrap().to_string());
+        }
+    }
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_hash_canonical() {
+        let three = hash_of(&Number::from(3));
+        assert_eq!(three, hash_of(&"3".parse::<Number>().unwrap()));
+        assert_eq!(three, hash_of(&"3.0".parse::<Number>().unwrap()));
+        assert_eq!(three, hash_of(&Number::from(3.0)));
+        assert_eq!(three, hash_of(&"3e0".parse::<Number>().unwrap()));
+
+        let half = hash_of(&Number::from(0.5));
+        assert_eq!(half, hash_of(&"0.5".parse::<Number>().unwrap()));
+        assert_eq!(half, hash_of(&"5e-1".parse::<Number>().unwrap()));
+
+        assert_eq!(hash_of(&Number::from(0)), hash_of(&Number::from(-0.0)));
+        assert_eq!(
+            hash_of(&Number::from(f64::NAN)),
+            hash_of(&Number::from(-f64::NAN))
+        );
+
+        let big = "123456789012345678901234567890";
+        assert_eq!(
+            hash_of(&big.parse::<Number>().unwrap()),
+            hash_of(&format!("{}.0", big).parse::<Number>().unwrap())
+        );
+
+        assert_ne!(three, hash_of(&Number::from(4)));
+        assert_ne!(three, half);
+    }
+
+    #[test]
+    fn test_hash_value() {
+        let from_ref = Value::from_set_iter(vec![Value::Number("3".parse().unwrap())]);
+        let from_int = Value::from_set_iter(vec![Value::from(3)]);
+        assert_eq!(hash_of(&from_int), hash_of(&from_ref));
+    }
+
+    #[test]
+    fn test_ord_numeric() {
+        use std::collections::BTreeSet;
+
+        let parse = |s: &str| s.parse::<Number>().unwrap();
+        let mut numbers = vec![
+            parse("3"),
+            Number::from(2.5),
+            Number::from(3),
+            Number::from(3.0),
+            parse("1e0"),
+            Number::from(-1),
+            parse("3.0"),
+            Number::from(1),
+        ];
+        numbers.sort();
+        let values = numbers
+            .iter()
+            .map(|n| n.as_f64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![-1.0, 1.0, 1.0, 2.5, 3.0, 3.0, 3.0, 3.0], values);
+
+        assert_eq!(Number::from(3), parse("3.0"));
+        assert_eq!(Number::from(3.0), parse("3"));
+        assert_eq!(4, numbers.into_iter().collect::<BTreeSet<_>>().len());
+
+        // Integers too large for an i64 or f64 still compare exactly
+        assert!(parse("9223372036854775808") > Number::from(i64::max_value()));
+        assert!(parse("100000000000000000001") > parse("100000000000000000000"));
+        assert!(Number::from(0.5) < parse("9223372036854775808"));
+    }
+
+    #[test]
+    fn test_ord_past_f64_precision() {
+        let parse = |s: &str| s.parse::<Number>().unwrap();
+        let two_53 = 1i64 << 53;
+
+        // 2^53 + 1 rounds to the float 2^53 but isn't equal to it
+        assert!(Number::from(two_53) < Number::from(two_53 + 1));
+        assert_eq!(Number::from(two_53 as f64), Number::from(two_53));
+        assert!(Number::from(two_53 as f64) < Number::from(two_53 + 1));
+        assert!(Number::from(two_53 + 1) < parse("9007199254740993.5"));
+
+        // 2^63 is a float and a ref, but not an i64
+        let two_63 = parse("9223372036854775808");
+        assert_eq!(two_63, Number::from(9_223_372_036_854_775_808.0));
+        assert_eq!(hash_of(&two_63), hash_of(&Number::from(2f64.powi(63))));
+        assert!(Number::from(i64::max_value()) < Number::from(2f64.powi(63)));
+    }
+
+    fn arb_number() -> impl Strategy<Value = Number> {
+        let base = prop_oneof![
+            Just(1i128 << 53),
+            Just(-(1i128 << 53)),
+            Just(1i128 << 63),
+            Just(-(1i128 << 63)),
+        ];
+        (base, -3i128..=3, 0..5u8).prop_map(|(base, offset, repr)| {
+            let i = base + offset;
+            match repr {
+                0 if i >= i64::min_value() as i128 && i <= i64::max_value() as i128 => {
+                    Number::from(i as i64)
+                }
+                0 | 1 => Number::from(i as f64),
+                2 => i.to_string().parse().unwrap(),
+                3 => format!("{}.0", i).parse().unwrap(),
+                _ => format!("{}.5", i).parse().unwrap(),
+            }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_ord_transitive(a in arb_number(), b in arb_number(), c in arb_number()) {
+            if a <= b && b <= c {
+                prop_assert!(a <= c, "{:?} <= {:?} <= {:?}", a, b, c);
+            }
+            if a == b && b == c {
+                prop_assert_eq!(&a, &c);
+            }
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+        }
+
+        #[test]
+        fn test_hash_agrees_with_eq(a in arb_number(), b in arb_number()) {
+            if a == b {
+                prop_assert_eq!(hash_of(&a), hash_of(&b), "{:?} == {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_checked_add() {
+        let sum = Number::from(1).checked_add(&Number::from(2)).unwrap();
+        assert_eq!(Number::from(3), sum);
+
+        // int + int overflows into a ref instead of wrapping
+        let sum = Number::from(i64::max_value())
+            .checked_add(&Number::from(1))
+            .unwrap();
+        assert_eq!("9223372036854775808", sum.to_string());
+        assert!(!sum.is_f64());
+
+        let diff = Number::from(i64::min_value())
+            .checked_sub(&Number::from(1))
+            .unwrap();
+        assert_eq!("-9223372036854775809", diff.to_string());
+
+        let product = Number::from(i64::max_value())
+            .checked_mul(&Number::from(2))
+            .unwrap();
+        assert_eq!("18446744073709551614", product.to_string());
+    }
+
+    #[test]
+    fn test_checked_add_float() {
+        let sum = Number::from(1).checked_add(&Number::from(0.5)).unwrap();
+        assert_eq!(Number::from(1.5), sum);
+
+        let product = Number::from(2.5).checked_mul(&Number::from(2)).unwrap();
+        assert_eq!(Number::from(5.0), product);
+
+        assert!(Number::from(std::f64::MAX)
+            .checked_mul(&Number::from(2))
+            .is_err());
+    }
+
+    #[test]
+    fn test_checked_add_ref() {
+        let big = Number::from_str("9223372036854775808").unwrap();
+        let sum = big.checked_add(&Number::from(1)).unwrap();
+        assert_eq!("9223372036854775809", sum.to_string());
+
+        // a ref that fits in an i64 becomes a plain integer again
+        let diff = big.checked_sub(&Number::from(1)).unwrap();
+        assert_eq!(Number::from(i64::max_value()), diff);
+
+        let sum = Number::from_str("1.25")
+            .unwrap()
+            .checked_add(&Number::from(1))
+            .unwrap();
+        assert_eq!(Number::from(2.25), sum);
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal() {
+        let parse = |s: &str| s.parse::<Number>().unwrap();
+
+        let sum = parse("0.1").checked_add(&parse("0.2")).unwrap();
+        assert_eq!("0.3", sum.to_string());
+        assert_eq!(Some(Decimal::new(3, 1)), sum.as_decimal());
+        assert_eq!(parse("0.3"), sum);
+        assert_ne!(Number::from(0.1 + 0.2), sum);
+
+        // Floats convert from their shortest form
+        let sum = Number::from(0.1).checked_add(&Number::from(0.2)).unwrap();
+        assert_eq!(parse("0.3"), sum);
+
+        let diff = parse("1.10").checked_sub(&parse("0.1")).unwrap();
+        assert_eq!("1", diff.to_string());
+        let product = parse("19.99").checked_mul(&Number::from(3)).unwrap();
+        assert_eq!("59.97", product.to_string());
+
+        // Past f64's precision, refs still compare exactly
+        let long = parse("0.10000000000000000001");
+        assert_eq!(Some(0.1), long.as_f64());
+        assert!(long > parse("0.1"));
+        assert_eq!(parse("2.5e-1"), Number::from(0.25));
+
+        assert_eq!(Some(Decimal::new(15, 0)), parse("1.5e1").as_decimal());
+        assert_eq!(None, Number::from(f64::NAN).as_decimal());
+        assert_eq!(None, parse("1e400").as_decimal());
+    }
+
+    #[test]
+    fn test_from_str_invalid() {
+        for invalid in &[
+            "abc", "", "-", "01", "1.", ".5", "1e", "1e+", "+1", "1 ", "0x10", "NaN",
+        ] {
+            assert!(Number::from_str(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_non_finite_ordering() {
+        use std::collections::BTreeSet;
+
+        assert!(Number::from_f64(f64::NAN).is_none());
+        assert!(Number::from_f64(f64::INFINITY).is_none());
+
+        let nan = Number::from(f64::NAN);
+        assert_eq!(nan, Number::from(f64::NAN));
+
+        let set = vec![
+            Number::from(f64::NAN),
+            Number::from(1.5),
+            Number::from(f64::NAN),
+            Number::from(-1.5),
+        ]
+        .into_iter()
+        .collect::<BTreeSet<_>>();
+        assert_eq!(3, set.len());
+        assert!(set.contains(&nan));
+        assert_eq!(Some(&nan), set.iter().next_back());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::error::Error;
+
+use super::{Map, Value};
+
+pub(crate) fn tokens(pointer: &str) -> Result<Vec<String>, Error> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(Error::InvalidPointer(pointer.to_string()));
+    }
+    let tokens = pointer[1..]
+        .split('/')
+        .map(|t| t.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    Ok(tokens)
+}
+
+// Escapes a token for a pointer, the reverse of the unescaping in tokens().
+pub(crate) fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+// Appends a token to a pointer.
+pub(crate) fn child(pointer: &str, token: &str) -> String {
+    format!("{}/{}", pointer, escape(token))
+}
+
+pub(crate) fn parse_index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.starts_with('0') && token.len() > 1) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+fn step<'v>(value: &'v Value, token: &str) -> Option<&'v Value> {
+    match value {
+        Value::Object(ref map) => map.get(token),
+        Value::Array(ref vec) => parse_index(token).and_then(|i| vec.get(i)),
+        _ => None,
+    }
+}
+
+fn step_mut<'v>(value: &'v mut Value, token: &str) -> Option<&'v mut Value> {
+    match value {
+        Value::Object(ref mut map) => map.get_mut(token),
+        Value::Array(ref mut vec) => parse_index(token).and_then(move |i| vec.get_mut(i)),
+        _ => None,
+    }
+}
+
+fn split_last(pointer: &str) -> Result<(Vec<String>, String), Error> {
+    let mut tokens = tokens(pointer)?;
+    let last = tokens
+        .pop()
+        .ok_or_else(|| Error::InvalidPointer(pointer.to_string()))?;
+    Ok((tokens, last))
+}
+
+fn insert_into(
+    parent: &mut Value,
+    pointer: &str,
+    token: String,
+    value: Value,
+) -> Result<Option<Value>, Error> {
+    match parent {
+        Value::Object(ref mut map) => Ok(map.insert(token, value)),
+        Value::Array(ref mut vec) => {
+            let index = if token == "-" {
+                vec.len()
+            } else {
+                parse_index(&token).ok_or_else(|| Error::InvalidPointer(pointer.to_string()))?
+            };
+            if index > vec.len() {
+                return Err(Error::InvalidPointer(pointer.to_string()));
+            }
+            vec.insert(index, value);
+            Ok(None)
+        }
+        _ => Err(Error::InvalidPointer(pointer.to_string())),
+    }
+}
+
+impl Value {
+    /// Looks up a value by a JSON pointer (RFC 6901), e.g. `/servers/0/name`.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        tokens(pointer)
+            .ok()?
+            .iter()
+            .try_fold(self, |value, token| step(value, token))
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        tokens(pointer)
+            .ok()?
+            .iter()
+            .try_fold(self, |value, token| step_mut(value, token))
+    }
+
+    /// Inserts `value` at `pointer`. The parent of the target must already exist.
+    ///
+    /// Inserting into an object replaces and returns any previous value for the
+    /// key. Inserting into an array shifts later elements; `-` appends.
+    pub fn insert_at(&mut self, pointer: &str, value: Value) -> Result<Option<Value>, Error> {
+        let (path, last) = split_last(pointer)?;
+        let parent = path
+            .iter()
+            .try_fold(self, |value, token| step_mut(value, token))
+            .ok_or_else(|| Error::InvalidPointer(pointer.to_string()))?;
+        insert_into(parent, pointer, last, value)
+    }
+
+    /// Like `insert_at`, but creates any missing intermediate objects.
+    pub fn insert_at_creating(
+        &mut self,
+        pointer: &str,
+        value: Value,
+    ) -> Result<Option<Value>, Error> {
+        let (path, last) = split_last(pointer)?;
+        let mut parent = self;
+        for token in path {
+            parent = match parent {
+                Value::Object(ref mut map) => map
+                    .entry(token)
+                    .or_insert_with(|| Value::Object(Map::new())),
+                other => step_mut(other, &token)
+                    .ok_or_else(|| Error::InvalidPointer(pointer.to_string()))?,
+            };
+        }
+        insert_into(parent, pointer, last, value)
+    }
+
+    /// Replaces the value at each pointer with `replacement`, e.g. to scrub
+    /// secrets before logging. Pointers that don't resolve are ignored.
+    pub fn redact(&mut self, pointers: &[&str], replacement: Value) {
+        for pointer in pointers {
+            if let Some(value) = self.pointer_mut(pointer) {
+                *value = replacement.clone();
+            }
+        }
+    }
+
+    pub fn remove_at(&mut self, pointer: &str) -> Option<Value> {
+        let (path, last) = split_last(pointer).ok()?;
+        let parent = path
+            .iter()
+            .try_fold(self, |value, token| step_mut(value, token))?;
+        match parent {
+            Value::Object(ref mut map) => map.remove(&last),
+            Value::Array(ref mut vec) => {
+                let index = parse_index(&last).filter(|i| *i < vec.len())?;
+                Some(vec.remove(index))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc() -> Value {
+        let mut servers = Map::new();
+        servers.insert("name".to_string(), Value::String("web".to_string()));
+        let mut root = Map::new();
+        root.insert(
+            "servers".to_string(),
+            Value::Array(vec![Value::Object(servers)]),
+        );
+        root.insert("a/b".to_string(), Value::Bool(true));
+        Value::Object(root)
+    }
+
+    #[test]
+    fn test_pointer() {
+        let value = doc();
+        assert_eq!(Some(&value), value.pointer(""));
+        assert_eq!(
+            Some(&Value::String("web".to_string())),
+            value.pointer("/servers/0/name")
+        );
+        assert_eq!(Some(&Value::Bool(true)), value.pointer("/a~1b"));
+        assert_eq!(None, value.pointer("/servers/1"));
+        assert_eq!(None, value.pointer("/servers/01"));
+        assert_eq!(None, value.pointer("servers"));
+    }
+
+    #[test]
+    fn test_child() {
+        let pointer = child(&child("", "a/b"), "~c");
+        assert_eq!("/a~1b/~0c", pointer);
+        assert_eq!(vec!["a/b", "~c"], tokens(&pointer).unwrap());
+    }
+
+    #[test]
+    fn test_pointer_mut() {
+        let mut value = doc();
+        *value.pointer_mut("/servers/0/name").unwrap() = Value::Null;
+        assert_eq!(Some(&Value::Null), value.pointer("/servers/0/name"));
+    }
+
+    #[test]
+    fn test_insert_at() {
+        let mut value = doc();
+        let previous = value
+            .insert_at("/servers/0/name", Value::String("db".to_string()))
+            .unwrap();
+        assert_eq!(Some(Value::String("web".to_string())), previous);
+
+        value.insert_at("/servers/-", Value::Null).unwrap();
+        value.insert_at("/servers/0", Value::Bool(false)).unwrap();
+        assert_eq!(Some(&Value::Bool(false)), value.pointer("/servers/0"));
+        assert_eq!(Some(&Value::Null), value.pointer("/servers/2"));
+
+        assert!(value.insert_at("/servers/9", Value::Null).is_err());
+        assert!(value.insert_at("/missing/key", Value::Null).is_err());
+        assert!(value.insert_at("", Value::Null).is_err());
+    }
+
+    #[test]
+    fn test_insert_at_creating() {
+        let mut value = doc();
+        value
+            .insert_at_creating("/missing/nested/key", Value::Bool(true))
+            .unwrap();
+        assert_eq!(
+            Some(&Value::Bool(true)),
+            value.pointer("/missing/nested/key")
+        );
+        assert!(value.insert_at_creating("/a~1b/key", Value::Null).is_err());
+    }
+
+    #[test]
+    fn test_remove_at() {
+        let mut value = doc();
+        assert_eq!(
+            Some(Value::String("web".to_string())),
+            value.remove_at("/servers/0/name")
+        );
+        assert_eq!(None, value.pointer("/servers/0/name"));
+        assert!(value.remove_at("/servers/0").is_some());
+        assert_eq!(Some(&Value::Array(vec![])), value.pointer("/servers"));
+        assert_eq!(None, value.remove_at("/servers/0"));
+        assert_eq!(None, value.remove_at(""));
+    }
+
+    #[test]
+    fn test_redact() {
+        let mut value = doc();
+        value
+            .insert_at("/password", Value::String("hunter2".to_string()))
+            .unwrap();
+        let stars = Value::String("***".to_string());
+        value.redact(
+            &["/password", "/servers/0/name", "/servers/3", "/missing/key"],
+            stars.clone(),
+        );
+
+        assert_eq!(Some(&stars), value.pointer("/password"));
+        assert_eq!(Some(&stars), value.pointer("/servers/0/name"));
+        assert_eq!(None, value.pointer("/servers/3"));
+        assert_eq!(None, value.pointer("/missing"));
+        assert_eq!(Some(&Value::Bool(true)), value.pointer("/a~1b"));
+    }
+
+    #[test]
+    fn test_redact_array_element() {
+        let mut value = doc();
+        value.redact(&["/servers/0"], Value::Null);
+        assert_eq!(
+            Some(&Value::Array(vec![Value::Null])),
+            value.pointer("/servers")
+        );
+
+        value.redact(&[""], Value::Null);
+        assert_eq!(Value::Null, value);
+    }
+}
@@ -0,0 +1,240 @@
+use super::Value;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Selector {
+    Name(String),
+    Index(i64),
+    Wildcard,
+}
+
+// A selector, applied to the current values or, after `..`, to them and all
+// of their descendants.
+#[derive(Clone, Debug, PartialEq)]
+struct Segment {
+    selector: Selector,
+    recursive: bool,
+}
+
+// Returns what follows `prefix`, if `s` starts with it.
+fn eat<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    match s.get(..prefix.len()) {
+        Some(p) if p == prefix => Some(&s[prefix.len()..]),
+        _ => None,
+    }
+}
+
+fn parse(expr: &str) -> Option<Vec<Segment>> {
+    let mut rest = eat(expr, "$")?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        let mut recursive = false;
+        if let Some(r) = eat(rest, "..") {
+            recursive = true;
+            rest = r;
+        } else if let Some(r) = eat(rest, ".") {
+            rest = r;
+        } else if !rest.starts_with('[') {
+            return None;
+        }
+
+        let selector = if let Some(r) = eat(rest, "[") {
+            let (selector, r) = parse_bracket(r)?;
+            rest = r;
+            selector
+        } else {
+            let end = rest.find(&['.', '['][..]).unwrap_or(rest.len());
+            let (name, r) = rest.split_at(end);
+            rest = r;
+            match name {
+                "" => return None,
+                "*" => Selector::Wildcard,
+                name => Selector::Name(name.to_string()),
+            }
+        };
+        segments.push(Segment {
+            selector,
+            recursive,
+        });
+    }
+    Some(segments)
+}
+
+// Parses the inside of `[...]`: `*`, an index or a quoted name.
+fn parse_bracket(s: &str) -> Option<(Selector, &str)> {
+    if let Some(quote) = s.chars().next().filter(|c| *c == '\'' || *c == '"') {
+        let s = &s[1..];
+        let end = s.find(quote)?;
+        let rest = eat(&s[end + 1..], "]")?;
+        return Some((Selector::Name(s[..end].to_string()), rest));
+    }
+
+    let end = s.find(']')?;
+    let selector = match &s[..end] {
+        "*" => Selector::Wildcard,
+        index => Selector::Index(index.parse().ok()?),
+    };
+    Some((selector, &s[end + 1..]))
+}
+
+fn select<'v>(value: &'v Value, selector: &Selector, matches: &mut Vec<&'v Value>) {
+    match (selector, value) {
+        (Selector::Name(name), Value::Object(map)) => matches.extend(map.get(name)),
+        (Selector::Index(i), Value::Array(_)) => matches.extend(value.get(*i)),
+        (Selector::Wildcard, Value::Array(vec)) => matches.extend(vec),
+        (Selector::Wildcard, Value::Object(map)) => matches.extend(map.values()),
+        (Selector::Wildcard, Value::Set(set)) => matches.extend(set),
+        _ => (),
+    }
+}
+
+// Applies the selector to the value and every value nested in it, parents
+// before their children.
+fn select_recursive<'v>(value: &'v Value, selector: &Selector, matches: &mut Vec<&'v Value>) {
+    select(value, selector, matches);
+    let children: Box<dyn Iterator<Item = &Value>> = match value {
+        Value::Array(vec) => Box::new(vec.iter()),
+        Value::Object(map) => Box::new(map.values()),
+        Value::Set(set) => Box::new(set.iter()),
+        _ => return,
+    };
+    for child in children {
+        select_recursive(child, selector, matches);
+    }
+}
+
+impl Value {
+    /// Returns every value matched by a JSONPath expression, in document
+    /// order, for pulling several values out of a decision at once.
+    ///
+    /// Only a subset of JSONPath is supported:
+    ///
+    /// - `$` is the value itself, and every expression starts with it.
+    /// - `.name` and `['name']` select an object's key.
+    /// - `[0]` selects an array element. Negative indices count from the end.
+    /// - `.*` and `[*]` select every element of an array or set, or every
+    ///   value of an object.
+    /// - `..` before any of the above applies it at every depth, so `$..id`
+    ///   selects every `id` key in the document.
+    ///
+    /// Filters, slices and unions aren't supported. Malformed expressions
+    /// match nothing.
+    ///
+    /// ```
+    /// use opa_wasm::Value;
+    ///
+    /// let value: Value = serde_json::from_str(
+    ///     r#"{"servers": [{"id": "web"}, {"id": "db"}]}"#,
+    /// ).unwrap();
+    /// let ids = value.query("$.servers[*].id");
+    /// assert_eq!(vec![&Value::from("web"), &Value::from("db")], ids);
+    /// ```
+    pub fn query(&self, expr: &str) -> Vec<&Value> {
+        let segments = match parse(expr) {
+            Some(segments) => segments,
+            None => return Vec::new(),
+        };
+
+        let mut current = vec![self];
+        for segment in &segments {
+            let mut matches = Vec::new();
+            for value in current {
+                if segment.recursive {
+                    select_recursive(value, &segment.selector, &mut matches);
+                } else {
+                    select(value, &segment.selector, &mut matches);
+                }
+            }
+            current = matches;
+        }
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn servers() -> Value {
+        serde_json::from_str(
+            r#"{
+                "servers": [
+                    {"id": "app", "ports": [80, 443], "tags": {"id": "public"}},
+                    {"id": "db", "ports": [5432]},
+                    {"id": "cache"}
+                ],
+                "ports": [22]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn strings(values: Vec<&Value>) -> Vec<String> {
+        values.into_iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_query_children() {
+        let value = servers();
+        assert_eq!(
+            vec!["\"app\"", "\"db\"", "\"cache\""],
+            strings(value.query("$.servers[*].id"))
+        );
+        assert_eq!(
+            vec!["\"app\"", "\"db\"", "\"cache\""],
+            strings(value.query("$['servers'].*[\"id\"]"))
+        );
+        assert_eq!(vec!["443"], strings(value.query("$.servers[0].ports[-1]")));
+        assert_eq!(vec![&value], value.query("$"));
+        assert!(value.query("$.servers[3].id").is_empty());
+        assert!(value.query("$.clients[*]").is_empty());
+    }
+
+    #[test]
+    fn test_query_recursive() {
+        let value = servers();
+        assert_eq!(
+            vec!["[22]", "[80,443]", "[5432]"],
+            strings(value.query("$..ports"))
+        );
+        assert_eq!(
+            vec!["22", "80", "443", "5432"],
+            strings(value.query("$..ports[*]"))
+        );
+        assert_eq!(
+            vec!["\"app\"", "\"public\"", "\"db\"", "\"cache\""],
+            strings(value.query("$..id"))
+        );
+        assert_eq!(
+            vec!["22", "80", "5432"],
+            strings(value.query("$..ports[0]"))
+        );
+    }
+
+    #[test]
+    fn test_query_sets() {
+        let value: Value = vec![("roles", Value::from_set_iter(vec!["dev", "admin"]))]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            vec!["\"admin\"", "\"dev\""],
+            strings(value.query("$.roles[*]"))
+        );
+    }
+
+    #[test]
+    fn test_query_malformed() {
+        let value = servers();
+        for expr in &[
+            "",
+            "servers",
+            "$servers",
+            "$.",
+            "$.servers[",
+            "$.servers[x]",
+            "$['servers]",
+            "$.servers[?(@.id)]",
+        ] {
+            assert!(value.query(expr).is_empty(), "{}", expr);
+        }
+    }
+}
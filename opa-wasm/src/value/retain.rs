@@ -0,0 +1,90 @@
+use std::mem;
+
+use super::Value;
+
+impl Value {
+    /// Keeps the elements of an array or set for which `f` returns true, e.g.
+    /// to drop entries from a decision before returning it. Other values are
+    /// left as is.
+    pub fn retain<F: FnMut(&Value) -> bool>(&mut self, mut f: F) {
+        match self {
+            Value::Array(ref mut vec) => vec.retain(|v| f(v)),
+            Value::Set(ref mut set) => {
+                *set = mem::take(set).into_iter().filter(|v| f(v)).collect();
+            }
+            _ => (),
+        }
+    }
+
+    /// Keeps the entries of an object whose key `f` returns true for, e.g. to
+    /// redact fields from a decision. Other values are left as is.
+    pub fn retain_keys<F: FnMut(&str) -> bool>(&mut self, mut f: F) {
+        if let Value::Object(ref mut map) = self {
+            *map = mem::take(map).into_iter().filter(|(k, _)| f(k)).collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Map, Set};
+    use super::*;
+
+    #[test]
+    fn test_retain_array() {
+        let mut value = Value::from(vec![1, 2, 3, 4]);
+        value.retain(|v| v.as_i64().map_or(false, |i| i % 2 == 0));
+        assert_eq!(Value::from(vec![2, 4]), value);
+    }
+
+    #[test]
+    fn test_retain_set() {
+        let mut value = Value::Set(
+            vec!["admin", "dev", "ops"]
+                .into_iter()
+                .map(Value::from)
+                .collect::<Set<Value>>(),
+        );
+        value.retain(|v| v.as_str() != Some("admin"));
+        let expected = vec!["dev", "ops"]
+            .into_iter()
+            .map(Value::from)
+            .collect::<Set<Value>>();
+        assert_eq!(Value::Set(expected), value);
+    }
+
+    #[test]
+    fn test_retain_keys() {
+        let mut map = Map::new();
+        map.insert("user".to_string(), Value::from("alice"));
+        map.insert("password".to_string(), Value::from("secret"));
+        let mut value = Value::Object(map);
+        value.retain_keys(|k| k != "password");
+
+        let mut expected = Map::new();
+        expected.insert("user".to_string(), Value::from("alice"));
+        assert_eq!(Value::Object(expected), value);
+
+        // Objects aren't filtered by value, nor arrays by key
+        value.retain(|_| false);
+        assert_eq!(1, value.as_object().unwrap().len());
+        let mut array = Value::from(vec![1]);
+        array.retain_keys(|_| false);
+        assert_eq!(Value::from(vec![1]), array);
+    }
+
+    #[test]
+    fn test_retain_scalars() {
+        for scalar in vec![
+            Value::Null,
+            Value::from(true),
+            Value::from(1),
+            Value::from("a"),
+        ] {
+            let mut value = scalar.clone();
+            value.retain(|_| false);
+            value.retain_keys(|_| false);
+            assert_eq!(scalar, value);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use super::Value;
+
+impl Value {
+    /// Checks this value against a small subset of JSON Schema.
+    ///
+    /// This is not a full JSON Schema implementation. Only the `type`,
+    /// `required`, `properties` and `items` keywords are understood; any other
+    /// keyword is ignored. `type` may be a single name or an array of names
+    /// and additionally accepts `"set"`. `items` applies to both arrays and
+    /// sets. A boolean schema matches everything (`true`) or nothing
+    /// (`false`).
+    pub fn matches_schema(&self, schema: &Value) -> bool {
+        let schema = match schema {
+            Value::Bool(b) => return *b,
+            Value::Object(ref schema) => schema,
+            _ => return false,
+        };
+
+        if let Some(ty) = schema.get("type") {
+            let matches = match ty {
+                Value::String(ref name) => self.is_type(name),
+                Value::Array(ref names) => names
+                    .iter()
+                    .any(|name| name.as_str().map_or(false, |name| self.is_type(name))),
+                _ => false,
+            };
+            if !matches {
+                return false;
+            }
+        }
+
+        if let Value::Object(ref object) = self {
+            if let Some(Value::Array(ref required)) = schema.get("required") {
+                let missing = required
+                    .iter()
+                    .any(|key| key.as_str().map_or(true, |key| !object.contains_key(key)));
+                if missing {
+                    return false;
+                }
+            }
+
+            if let Some(Value::Object(ref properties)) = schema.get("properties") {
+                let invalid = properties.iter().any(|(key, schema)| {
+                    object
+                        .get(key)
+                        .map_or(false, |value| !value.matches_schema(schema))
+                });
+                if invalid {
+                    return false;
+                }
+            }
+        }
+
+        if let Some(items) = schema.get("items") {
+            let valid = match self {
+                Value::Array(ref v) => v.iter().all(|item| item.matches_schema(items)),
+                Value::Set(ref v) => v.iter().all(|item| item.matches_schema(items)),
+                _ => true,
+            };
+            if !valid {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn is_type(&self, name: &str) -> bool {
+        match name {
+            "null" => self.is_null(),
+            "boolean" => self.is_boolean(),
+            "integer" => self.is_number() && !self.is_f64(),
+            "number" => self.is_number(),
+            "string" => self.is_string(),
+            "array" => self.is_array(),
+            "object" => self.is_object(),
+            "set" => self.is_set(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Map;
+    use super::*;
+
+    fn object(entries: Vec<(&str, Value)>) -> Value {
+        Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect::<Map<String, Value>>(),
+        )
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    fn user_schema() -> Value {
+        object(vec![
+            ("type", string("object")),
+            (
+                "required",
+                Value::Array(vec![string("name"), string("roles")]),
+            ),
+            (
+                "properties",
+                object(vec![
+                    ("name", object(vec![("type", string("string"))])),
+                    ("age", object(vec![("type", string("integer"))])),
+                    (
+                        "roles",
+                        object(vec![
+                            ("type", string("array")),
+                            ("items", object(vec![("type", string("string"))])),
+                        ]),
+                    ),
+                ]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_matches_schema() {
+        let schema = user_schema();
+        let user = object(vec![
+            ("name", string("alice")),
+            ("age", Value::from(30)),
+            ("roles", Value::Array(vec![string("admin")])),
+        ]);
+        assert!(user.matches_schema(&schema));
+
+        let missing = object(vec![("name", string("alice"))]);
+        assert!(!missing.matches_schema(&schema));
+
+        assert!(!Value::Null.matches_schema(&schema));
+    }
+
+    #[test]
+    fn test_matches_schema_type_mismatch() {
+        let schema = user_schema();
+        let wrong_age = object(vec![
+            ("name", string("alice")),
+            ("age", Value::from(30.5)),
+            ("roles", Value::Array(vec![])),
+        ]);
+        assert!(!wrong_age.matches_schema(&schema));
+
+        let wrong_role = object(vec![
+            ("name", string("alice")),
+            ("roles", Value::Array(vec![string("admin"), Value::from(1)])),
+        ]);
+        assert!(!wrong_role.matches_schema(&schema));
+    }
+
+    #[test]
+    fn test_matches_schema_type_list() {
+        let schema = object(vec![(
+            "type",
+            Value::Array(vec![string("string"), string("null")]),
+        )]);
+        assert!(Value::Null.matches_schema(&schema));
+        assert!(string("a").matches_schema(&schema));
+        assert!(!Value::Bool(true).matches_schema(&schema));
+
+        assert!(Value::from(1).matches_schema(&Value::Bool(true)));
+        assert!(!Value::from(1).matches_schema(&Value::Bool(false)));
+    }
+}
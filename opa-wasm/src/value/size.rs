@@ -0,0 +1,75 @@
+use std::mem;
+
+use super::Value;
+
+// Rough per-entry bookkeeping of a BTreeMap or BTreeSet, on top of the
+// entry itself.
+const TREE_ENTRY_OVERHEAD: usize = mem::size_of::<usize>();
+
+impl Value {
+    /// Estimates the memory used by this value, in bytes.
+    ///
+    /// This counts the value itself, the bytes of strings and number refs and
+    /// every nested element, plus a small overhead per object or set entry.
+    /// Spare capacity and allocator overhead are not included, so treat the
+    /// result as a lower bound for comparing values, not an exact figure.
+    pub fn approx_size(&self) -> usize {
+        mem::size_of::<Value>() + self.heap_size()
+    }
+
+    fn heap_size(&self) -> usize {
+        match self {
+            Value::Null | Value::Bool(_) => 0,
+            Value::Number(ref n) => n.heap_size(),
+            Value::String(ref s) => s.len(),
+            Value::Array(ref v) => v.iter().map(Value::approx_size).sum(),
+            Value::Object(ref v) => v
+                .iter()
+                .map(|(k, v)| {
+                    mem::size_of::<String>() + k.len() + v.approx_size() + TREE_ENTRY_OVERHEAD
+                })
+                .sum(),
+            Value::Set(ref v) => v
+                .iter()
+                .map(|v| v.approx_size() + TREE_ENTRY_OVERHEAD)
+                .sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Map, Number};
+    use super::*;
+
+    #[test]
+    fn test_approx_size_scalars() {
+        let base = mem::size_of::<Value>();
+        assert_eq!(base, Value::Null.approx_size());
+        assert_eq!(base, Value::Bool(true).approx_size());
+        assert_eq!(base, Value::from(1).approx_size());
+        assert_eq!(base + 5, Value::from("hello").approx_size());
+
+        let big = "123456789012345678901234567890".parse::<Number>().unwrap();
+        assert_eq!(base + 30, Value::Number(big).approx_size());
+    }
+
+    #[test]
+    fn test_approx_size_nested() {
+        let string = Value::from("a".repeat(100));
+        let array = Value::Array(vec![string.clone(), string.clone()]);
+        assert!(array.approx_size() > 2 * string.approx_size());
+
+        let nested = Value::Array(vec![array.clone()]);
+        assert!(nested.approx_size() > array.approx_size());
+
+        let mut map = Map::new();
+        map.insert("key".to_string(), array.clone());
+        let object = Value::Object(map);
+        assert!(object.approx_size() > nested.approx_size());
+
+        let set = Value::from_set_iter(vec![string.clone(), Value::from("b")]);
+        assert!(set.approx_size() > string.approx_size());
+        assert!(Value::Array(vec![]).approx_size() < set.approx_size());
+    }
+}
@@ -0,0 +1,180 @@
+use std::mem;
+
+use super::{Set, Value};
+
+fn visit_path<F>(value: &Value, path: &mut Vec<Value>, f: &mut F)
+where
+    F: FnMut(&[Value], &Value),
+{
+    f(path, value);
+    match value {
+        Value::Array(ref v) => {
+            for (i, elem) in v.iter().enumerate() {
+                path.push(Value::from(i));
+                visit_path(elem, path, f);
+                path.pop();
+            }
+        }
+        Value::Object(ref m) => {
+            for (k, elem) in m.iter() {
+                path.push(Value::String(k.clone()));
+                visit_path(elem, path, f);
+                path.pop();
+            }
+        }
+        Value::Set(ref s) => {
+            for elem in s.iter() {
+                path.push(elem.clone());
+                visit_path(elem, path, f);
+                path.pop();
+            }
+        }
+        _ => (),
+    }
+}
+
+fn visit_path_mut<F>(value: &mut Value, path: &mut Vec<Value>, f: &mut F)
+where
+    F: FnMut(&[Value], &mut Value),
+{
+    f(path, value);
+    match value {
+        Value::Array(ref mut v) => {
+            for (i, elem) in v.iter_mut().enumerate() {
+                path.push(Value::from(i));
+                visit_path_mut(elem, path, f);
+                path.pop();
+            }
+        }
+        Value::Object(ref mut m) => {
+            for (k, elem) in m.iter_mut() {
+                path.push(Value::String(k.clone()));
+                visit_path_mut(elem, path, f);
+                path.pop();
+            }
+        }
+        Value::Set(ref mut s) => {
+            // Set elements can't be mutated in place, so the set is rebuilt.
+            let elems = mem::replace(s, Set::new());
+            for mut elem in elems {
+                path.push(elem.clone());
+                visit_path_mut(&mut elem, path, f);
+                path.pop();
+                s.insert(elem);
+            }
+        }
+        _ => (),
+    }
+}
+
+impl Value {
+    /// Calls `f` for every node in pre-order with the path of keys leading
+    /// to it, like the `walk` builtin. Set elements are keyed by themselves.
+    pub fn visit<F>(&self, mut f: F)
+    where
+        F: FnMut(&[Value], &Value),
+    {
+        visit_path(self, &mut Vec::new(), &mut f)
+    }
+
+    /// Like `visit`, but allows each node to be modified. Children are visited
+    /// after `f` has been applied to their parent.
+    pub fn visit_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&[Value], &mut Value),
+    {
+        visit_path_mut(self, &mut Vec::new(), &mut f)
+    }
+
+    pub fn fold<B, F>(&self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, &[Value], &Value) -> B,
+    {
+        let mut acc = Some(init);
+        self.visit(|path, value| {
+            let prev = acc.take().expect("accumulator is always set");
+            acc = Some(f(prev, path, value));
+        });
+        acc.expect("accumulator is always set")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Map;
+    use super::*;
+
+    fn doc() -> Value {
+        let mut tags = Set::new();
+        tags.insert(Value::String("prod".to_string()));
+        let mut user = Map::new();
+        user.insert("password".to_string(), Value::String("hunter2".to_string()));
+        user.insert("tags".to_string(), Value::Set(tags));
+        user.insert(
+            "ids".to_string(),
+            Value::Array(vec![Value::from(1), Value::String("x".to_string())]),
+        );
+        let mut root = Map::new();
+        root.insert("user".to_string(), Value::Object(user));
+        Value::Object(root)
+    }
+
+    #[test]
+    fn test_fold_count() {
+        // root, user, ids, ids[0], ids[1], password, tags, tags["prod"]
+        let count = doc().fold(0, |n, _, _| n + 1);
+        assert_eq!(8, count);
+
+        let leaves = doc().fold(0, |n, _, v| match v {
+            Value::Array(_) | Value::Object(_) | Value::Set(_) => n,
+            _ => n + 1,
+        });
+        assert_eq!(4, leaves);
+    }
+
+    #[test]
+    fn test_visit_paths() {
+        let mut paths = Vec::new();
+        doc().visit(|path, value| {
+            if value.is_string() {
+                paths.push(path.to_vec());
+            }
+        });
+        let expected = vec![
+            vec![
+                Value::String("user".to_string()),
+                Value::String("ids".to_string()),
+                Value::from(1),
+            ],
+            vec![
+                Value::String("user".to_string()),
+                Value::String("password".to_string()),
+            ],
+            vec![
+                Value::String("user".to_string()),
+                Value::String("tags".to_string()),
+                Value::String("prod".to_string()),
+            ],
+        ];
+        assert_eq!(expected, paths);
+    }
+
+    #[test]
+    fn test_visit_mut_redact() {
+        let mut value = doc();
+        value.visit_mut(|_, v| {
+            if v.is_string() {
+                *v = Value::String("***".to_string());
+            }
+        });
+
+        let mut strings = Vec::new();
+        value.visit(|_, v| {
+            if let Some(s) = v.as_str() {
+                strings.push(s.to_string());
+            }
+        });
+        assert_eq!(vec!["***", "***", "***"], strings);
+        assert_eq!(Some(&Value::from(1)), value.pointer("/user/ids/0"));
+    }
+}
@@ -0,0 +1,3 @@
+package tests
+
+echo = input
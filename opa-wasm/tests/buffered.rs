@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use opa_wasm::{
+    assert_value_eq, Error, InputTransform, MalformedLine, Module, Policy, PolicyConfig, Value,
+};
+
+fn inputs() -> Vec<Value> {
+    vec![
+        r#"{"user": "alice", "roles": ["admin", "dev"], "age": 42}"#,
+        r#"{"user": "bob"}"#,
+        r#"{"nested": {"list": [1, 2.5, null, true, "snowman ☃"]}}"#,
+        r#"{}"#,
+        r#"{"user": "alice", "roles": ["admin", "dev"], "age": 42}"#,
+    ]
+    .into_iter()
+    .map(|json| serde_json::from_str(json).unwrap())
+    .collect()
+}
+
+#[test]
+fn test_evaluate_buffered() {
+    let module = opa_go::wasm::compile("data.tests.echo", "tests/buffered.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    for input in inputs() {
+        let expected = policy.evaluate(&input).unwrap();
+        let buffered = policy.evaluate_buffered(&input).unwrap();
+        assert_value_eq!(expected, buffered);
+    }
+}
+
+#[test]
+fn test_evaluate_prepared() {
+    let module = opa_go::wasm::compile("data.tests.echo", "tests/buffered.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let prepared = inputs()
+        .iter()
+        .map(|input| policy.prepare_input(input).unwrap())
+        .collect::<Vec<_>>();
+    for _ in 0..2 {
+        for (input, prepared) in inputs().iter().zip(&prepared) {
+            let expected = policy.evaluate(input).unwrap();
+            let result = policy.evaluate_prepared(prepared).unwrap();
+            assert_value_eq!(expected, result);
+        }
+    }
+}
+
+#[test]
+fn test_evaluate_json() {
+    let module = opa_go::wasm::compile("data.tests.echo", "tests/buffered.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    for input in inputs() {
+        let expected = serde_json::Value::from(policy.evaluate(&input).unwrap());
+        let json = policy.evaluate_json(&input).unwrap();
+        let result = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(expected, result);
+    }
+}
+
+#[test]
+fn test_evaluate_ndjson() {
+    let module = opa_go::wasm::compile("data.tests.echo", "tests/buffered.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let ndjson = inputs()
+        .iter()
+        .map(|input| serde_json::to_string(input).unwrap() + "\n")
+        .collect::<String>();
+    let mut output = Vec::new();
+    let written = policy
+        .evaluate_ndjson(ndjson.as_bytes(), &mut output, MalformedLine::Abort)
+        .unwrap();
+    assert_eq!(inputs().len(), written);
+
+    let output = String::from_utf8(output).unwrap();
+    for (input, line) in inputs().iter().zip(output.lines()) {
+        let expected = serde_json::Value::from(policy.evaluate(input).unwrap());
+        let decision = serde_json::from_str::<serde_json::Value>(line).unwrap();
+        assert_eq!(expected, decision);
+    }
+}
+
+#[test]
+fn test_result_deserialize_error() {
+    let module = opa_go::wasm::compile("data.tests.echo", "tests/buffered.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let input = serde_json::json!({"user": "alice"});
+    let err = policy.evaluate_opt::<_, bool>(&input).unwrap_err();
+    assert!(matches!(err, Error::DeserializeResult(_)));
+    assert!(err.to_string().contains("deserializing result"));
+}
+
+#[test]
+fn test_input_transform() {
+    let module = opa_go::wasm::compile("data.tests.echo", "tests/buffered.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    let mut transformed = Policy::from_wasm(&module).unwrap();
+    transformed.set_input_transform(|input| {
+        if let Value::Object(ref mut map) = input {
+            map.insert("timestamp".to_string(), Value::from(1_584_198_566));
+        }
+    });
+
+    let input = serde_json::json!({"user": "alice"});
+    let stamped = serde_json::json!({"user": "alice", "timestamp": 1_584_198_566});
+    let expected = policy.evaluate(&stamped).unwrap();
+    assert_ne!(expected, policy.evaluate(&input).unwrap());
+
+    assert_value_eq!(expected, transformed.evaluate(&input).unwrap());
+    assert_value_eq!(expected, transformed.evaluate_buffered(&input).unwrap());
+    let json = transformed.evaluate_json(&input).unwrap();
+    assert!(json.contains(r#""timestamp":1584198566"#), "{}", json);
+}
+
+#[test]
+fn test_evaluate_with_metrics() {
+    let module = opa_go::wasm::compile("data.tests.echo", "tests/buffered.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    let data_heap_ptr = policy.memory_usage().unwrap().data_heap_ptr;
+
+    for input in inputs() {
+        let expected = policy.evaluate(&input).unwrap();
+        let (result, metrics) = policy.evaluate_with_metrics(&input).unwrap();
+        assert_value_eq!(expected, result);
+        assert_eq!(data_heap_ptr, metrics.heap_ptr_before);
+        assert!(metrics.allocated() > 0);
+        assert_eq!(
+            policy.memory_usage().unwrap().heap_ptr,
+            metrics.heap_ptr_after
+        );
+    }
+}
+
+#[test]
+fn test_evaluate_with_delta() {
+    let module = opa_go::wasm::compile("data.tests.echo", "tests/buffered.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    let base = serde_json::json!({"user": "alice", "headers": {"accept": "json"}});
+    policy
+        .set_base_input(serde_json::from_value(base).unwrap())
+        .unwrap();
+
+    for path in &["/", "/users"] {
+        let delta = serde_json::json!({"path": path, "headers": {"host": "example.com"}});
+        let result = policy
+            .evaluate_with_delta(serde_json::from_value(delta).unwrap())
+            .unwrap();
+        let binding = match result {
+            Value::Set(bindings) => bindings.into_iter().next().unwrap(),
+            result => panic!("unexpected result: {:?}", result),
+        };
+        let input = binding.lookup("result").unwrap();
+        assert_eq!(Some(&Value::from("alice")), input.lookup("user"));
+        assert_eq!(Some(&Value::from(*path)), input.lookup("path"));
+        assert_eq!(Some(&Value::from("json")), input.lookup("headers.accept"));
+        assert_eq!(
+            Some(&Value::from("example.com")),
+            input.lookup("headers.host")
+        );
+    }
+}
+
+#[test]
+fn test_evaluate_buffered_sets() {
+    let module = opa_go::wasm::compile("data.tests.echo", "tests/buffered.rego").unwrap();
+    let config = PolicyConfig {
+        input_transform: Some(InputTransform::new(|input| {
+            if let Value::Object(ref mut map) = input {
+                let tags = Value::from_set_iter(vec![Value::from("b"), Value::from("a")]);
+                map.insert("tags".to_string(), tags);
+            }
+        })),
+        ..PolicyConfig::default()
+    };
+    let module = Arc::new(Module::from_bytes(&module).unwrap());
+    let mut policy = Policy::with_config(module, config).unwrap();
+
+    // Sets are written as arrays, in OPA's order, whether they come from the
+    // input or the transform
+    let mut input = opa_wasm::value::Map::new();
+    input.insert(
+        "roles".to_string(),
+        Value::from_set_iter(vec![Value::from("dev"), Value::from("admin")]),
+    );
+    let input = Value::Object(input);
+    let expected = serde_json::json!([{"result": {
+        "roles": ["admin", "dev"],
+        "tags": ["a", "b"],
+    }}]);
+
+    let result = policy.evaluate_buffered(&input).unwrap();
+    assert_eq!(expected, serde_json::Value::from(result));
+    let json = policy.evaluate_json(&input).unwrap();
+    assert_eq!(
+        expected,
+        serde_json::from_str::<serde_json::Value>(&json).unwrap()
+    );
+}
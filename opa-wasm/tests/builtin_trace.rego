@@ -0,0 +1,5 @@
+package tests
+
+name = trim(input.name, " ")
+
+label = upper(name)
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use opa_wasm::{BuiltinCall, Module, Policy, PolicyConfig, Value};
+
+fn input(name: Value) -> Value {
+    let mut input = opa_wasm::value::Map::new();
+    input.insert("name".to_string(), name);
+    Value::Object(input)
+}
+
+fn call(name: &str, args: Vec<Value>, result: Option<Value>) -> BuiltinCall {
+    BuiltinCall {
+        name: name.to_string(),
+        args,
+        result,
+    }
+}
+
+#[test]
+fn test_take_builtin_trace() {
+    let module = opa_go::wasm::compile("data.tests.label", "tests/builtin_trace.rego").unwrap();
+    // Nothing is traced by default
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    policy.evaluate(&input(" bob ".into())).unwrap();
+    assert!(policy.take_builtin_trace().is_empty());
+
+    let config = PolicyConfig {
+        trace_builtins: true,
+        ..PolicyConfig::default()
+    };
+    let mut policy =
+        Policy::with_config(Arc::new(Module::from_bytes(&module).unwrap()), config).unwrap();
+    policy.evaluate(&input(" bob ".into())).unwrap();
+    let expected = vec![
+        call("trim", vec![" bob ".into(), " ".into()], Some("bob".into())),
+        call("upper", vec!["bob".into()], Some("BOB".into())),
+    ];
+    assert_eq!(expected, policy.take_builtin_trace());
+    assert!(policy.take_builtin_trace().is_empty());
+
+    // A failed call is traced without a result, and the rest don't run
+    policy.evaluate(&input(1.into())).unwrap();
+    let expected = vec![call("trim", vec![1.into(), " ".into()], None)];
+    assert_eq!(expected, policy.take_builtin_trace());
+
+    // Tracing survives a reload
+    policy.reload_wasm(&module).unwrap();
+    policy.evaluate(&input("amy".into())).unwrap();
+    assert_eq!(2, policy.take_builtin_trace().len());
+}
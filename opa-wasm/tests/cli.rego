@@ -0,0 +1,7 @@
+package tests
+
+default allow = false
+
+allow {
+    data.allowed[_] == input.user
+}
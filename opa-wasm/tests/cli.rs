@@ -0,0 +1,37 @@
+#![cfg(feature = "cli")]
+
+use std::path::Path;
+
+#[test]
+fn test_run() {
+    let wasm = opa_go::wasm::compile("data.tests.allow", "tests/cli.rego").unwrap();
+    let input = Path::new("tests/fixtures/input.json");
+
+    let mut out = Vec::new();
+    opa_wasm::run(
+        &wasm,
+        Some(Path::new("tests/fixtures/data.json")),
+        Some(input),
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!("[{\"result\":true}]\n", String::from_utf8(out).unwrap());
+
+    let mut out = Vec::new();
+    opa_wasm::run(&wasm, None, Some(input), &mut out).unwrap();
+    assert_eq!("[{\"result\":false}]\n", String::from_utf8(out).unwrap());
+}
+
+#[test]
+fn test_run_missing_input() {
+    let wasm = opa_go::wasm::compile("data.tests.allow", "tests/cli.rego").unwrap();
+    let mut out = Vec::new();
+    let result = opa_wasm::run(
+        &wasm,
+        None,
+        Some(Path::new("tests/fixtures/missing.json")),
+        &mut out,
+    );
+    assert!(result.is_err());
+    assert!(out.is_empty());
+}
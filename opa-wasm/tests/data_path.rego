@@ -0,0 +1,6 @@
+package tests
+
+config = {"limits": {"max": max, "regions": regions}} {
+    max := data.base * 2
+    regions := [r | r := data.regions[_]; r != "test"]
+}
@@ -0,0 +1,38 @@
+use opa_wasm::{Error, Policy, Value};
+
+#[test]
+fn test_read_data_path() {
+    let module = opa_go::wasm::compile("data", "tests/data_path.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    policy
+        .set_data(&serde_json::json!({"base": 21, "regions": ["eu", "test", "us"]}))
+        .unwrap();
+
+    let max = policy.read_data_path("tests.config.limits.max").unwrap();
+    assert_eq!(Value::from(42), max);
+
+    let regions = policy
+        .read_data_path("tests.config.limits.regions")
+        .unwrap();
+    assert_eq!(Value::from(vec!["eu", "us"]), regions);
+    let second = policy
+        .read_data_path("tests.config.limits.regions.1")
+        .unwrap();
+    assert_eq!(Value::from("us"), second);
+
+    // Plain data is part of the document too
+    assert_eq!(Value::from(21), policy.read_data_path("base").unwrap());
+
+    // The entrypoint has no name to resolve full paths against
+    let err = policy
+        .read_data_path("data.tests.config.limits.max")
+        .unwrap_err();
+    assert!(matches!(err, Error::UnknownEntrypoint(_)));
+    let err = policy.read_data_path("data").unwrap_err();
+    assert!(matches!(err, Error::UnknownEntrypoint(_)));
+
+    let err = policy
+        .read_data_path("tests.config.limits.min")
+        .unwrap_err();
+    assert!(matches!(err, Error::Undefined(_)));
+}
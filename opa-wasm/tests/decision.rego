@@ -0,0 +1,11 @@
+package tests
+
+default decision = {"allow": false, "reason": "user is not an admin"}
+
+decision = {"allow": true, "reason": "user is an admin"} {
+    input.user == "alice"
+}
+
+audited {
+    input.audit == true
+}
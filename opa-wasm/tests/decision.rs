@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use opa_wasm::{Module, Policy, PolicyConfig, Value};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct AllowReason {
+    allow: bool,
+    reason: String,
+}
+
+fn input(user: &str) -> Value {
+    let mut input = opa_wasm::value::Map::new();
+    input.insert("user".to_string(), Value::String(user.to_string()));
+    Value::Object(input)
+}
+
+#[test]
+fn test_evaluate_decision() {
+    let module = opa_go::wasm::compile("data.tests.decision", "tests/decision.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let decision = policy
+        .evaluate_decision::<_, AllowReason>(&input("alice"))
+        .unwrap();
+    assert!(decision.is_defined());
+    let expected = AllowReason {
+        allow: true,
+        reason: "user is an admin".to_string(),
+    };
+    assert_eq!(Some(&expected), decision.result());
+
+    let decision = policy
+        .evaluate_decision::<_, AllowReason>(&input("bob"))
+        .unwrap();
+    let expected = AllowReason {
+        allow: false,
+        reason: "user is not an admin".to_string(),
+    };
+    assert_eq!(Some(expected), decision.into_result());
+}
+
+#[test]
+fn test_evaluate_decision_undefined() {
+    let module = opa_go::wasm::compile("data.tests.audited", "tests/decision.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let decision = policy
+        .evaluate_decision::<_, bool>(&input("alice"))
+        .unwrap();
+    assert!(!decision.is_defined());
+    assert_eq!(None, decision.result());
+}
+
+#[test]
+fn test_evaluate_or() {
+    let module = opa_go::wasm::compile("data.tests.audited", "tests/decision.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let mut audited = opa_wasm::value::Map::new();
+    audited.insert("audit".to_string(), Value::Bool(true));
+    let audited = Value::Object(audited);
+    assert_eq!(true, policy.evaluate_or(&audited, false).unwrap());
+    assert_eq!(false, policy.evaluate_or(&input("alice"), false).unwrap());
+}
+
+#[test]
+fn test_reload_invalidates_cached_decisions() {
+    let admins = opa_go::wasm::compile("data.tests.decision", "tests/decision.rego").unwrap();
+    let audits = opa_go::wasm::compile("data.tests.audited", "tests/decision.rego").unwrap();
+
+    let config = PolicyConfig {
+        decision_cache_size: 16,
+        ..PolicyConfig::default()
+    };
+    let module = Arc::new(Module::from_bytes(&admins).unwrap());
+    let mut policy = Policy::with_config(module, config).unwrap();
+
+    let first = policy.evaluate_cached(&input("alice")).unwrap();
+    assert_eq!(first, policy.evaluate_cached(&input("alice")).unwrap());
+    assert_eq!(first, policy.evaluate(&input("alice")).unwrap());
+
+    policy.reload_wasm(&audits).unwrap();
+    assert_eq!(1, policy.version());
+
+    // The same input is evaluated against the new module, not served from
+    // the cache
+    let reloaded = policy.evaluate_cached(&input("alice")).unwrap();
+    assert_ne!(first, reloaded);
+    assert_eq!(policy.evaluate(&input("alice")).unwrap(), reloaded);
+}
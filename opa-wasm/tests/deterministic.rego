@@ -0,0 +1,5 @@
+package tests
+
+now = time.now_ns()
+
+today = time.date(now)
@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use opa_wasm::{Module, Policy, PolicyConfig, Value};
+
+fn evaluate(policy: &mut Policy) -> Value {
+    let result: Option<Value> = policy.evaluate_opt(&()).unwrap();
+    result.unwrap()
+}
+
+#[test]
+fn test_deterministic() {
+    let module = opa_go::wasm::compile("data.tests", "tests/deterministic.rego").unwrap();
+    let config = PolicyConfig {
+        deterministic: true,
+        ..PolicyConfig::default()
+    };
+    let mut policy =
+        Policy::with_config(Arc::new(Module::from_bytes(&module).unwrap()), config).unwrap();
+
+    let first = evaluate(&mut policy);
+    let second = evaluate(&mut policy);
+    assert_eq!(first, second);
+
+    let expected: Value = serde_json::from_str(r#"{"now": 0, "today": [1970, 1, 1]}"#).unwrap();
+    opa_wasm::assert_value_eq!(expected, first);
+
+    // The mode survives a reload, and is off by default
+    policy.reload_wasm(&module).unwrap();
+    assert_eq!(first, evaluate(&mut policy));
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    assert_ne!(first, evaluate(&mut policy));
+}
@@ -0,0 +1,268 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use opa_wasm::value::Map;
+use opa_wasm::{Error, InputTransform, Module, Policy, PolicyConfig, Value};
+
+// The echo module's entrypoints return the data and the input as their
+// result sets, so each test picks the results by what it passes in.
+fn echo_policy() -> Policy {
+    echo_policy_with_config(PolicyConfig::default())
+}
+
+fn echo_policy_with_config(config: PolicyConfig) -> Policy {
+    let wasm = wat::parse_file("tests/fixtures/echo.wat").unwrap();
+    let module = if config.eval_fuel.is_some() {
+        Module::from_bytes_metered(&wasm).unwrap()
+    } else {
+        Module::from_bytes(&wasm).unwrap()
+    };
+    Policy::with_config(Arc::new(module), config).unwrap()
+}
+
+fn result_set(result: &str) -> Value {
+    let mut binding = Map::new();
+    binding.insert("result".to_string(), Value::String(result.to_string()));
+    Value::from_set_iter(vec![Value::Object(binding)])
+}
+
+#[test]
+fn test_evaluate_entrypoint() {
+    let mut policy = echo_policy();
+    assert_eq!(
+        vec!["example/data", "example/input"],
+        policy.list_entrypoints()
+    );
+    policy.set_data(&result_set("data")).unwrap();
+
+    let data = policy.entrypoint("example/data").unwrap();
+    let input = policy.entrypoint("example/input").unwrap();
+    assert_eq!(0, data.id());
+    assert_eq!(1, input.id());
+    assert_eq!(data, policy.default_entrypoint());
+
+    let result = policy
+        .evaluate_entrypoint(&input, &result_set("input"))
+        .unwrap();
+    assert_eq!(result_set("input"), result);
+    let result = policy
+        .evaluate_entrypoint(&data, &result_set("input"))
+        .unwrap();
+    assert_eq!(result_set("data"), result);
+
+    // evaluate runs the default entrypoint
+    assert_eq!(
+        result_set("data"),
+        policy.evaluate(&result_set("input")).unwrap()
+    );
+}
+
+#[test]
+fn test_evaluate_unknown_entrypoint() {
+    let input = echo_policy().entrypoint("example/input").unwrap();
+
+    let bytes = std::fs::read("tests/empty.wasm").unwrap();
+    let mut policy = Policy::from_wasm(&bytes).unwrap();
+    let err = policy.evaluate_entrypoint(&input, &()).unwrap_err();
+    assert!(matches!(err, Error::UnknownEntrypoint(ref name) if name == "example/input"));
+
+    let default = policy.default_entrypoint();
+    assert!(policy.evaluate_entrypoint(&default, &()).is_ok());
+}
+
+#[test]
+fn test_require_defined_entrypoint() {
+    let config = PolicyConfig {
+        require_defined: vec!["example/input".to_string()],
+        ..PolicyConfig::default()
+    };
+    let mut policy = echo_policy_with_config(config);
+    policy.set_data(&Value::Set(Default::default())).unwrap();
+    let input = policy.entrypoint("example/input").unwrap();
+
+    let empty = Value::Set(Default::default());
+    let err = policy.evaluate_entrypoint(&input, &empty).unwrap_err();
+    assert!(matches!(err, Error::Undefined(ref name) if name == "example/input"));
+    assert!(policy
+        .evaluate_entrypoint(&input, &result_set("input"))
+        .is_ok());
+
+    // Only the required entrypoint is checked
+    assert_eq!(empty, policy.evaluate(&empty).unwrap());
+}
+
+#[test]
+fn test_config_entrypoint() {
+    let config = PolicyConfig {
+        entrypoint: Some("example/input".to_string()),
+        ..PolicyConfig::default()
+    };
+    let mut policy = echo_policy_with_config(config.clone());
+    policy.set_data(&result_set("data")).unwrap();
+    assert_eq!("example/input", policy.default_entrypoint().name());
+    assert_eq!(1, policy.default_entrypoint().id());
+    assert_eq!(
+        result_set("input"),
+        policy.evaluate(&result_set("input")).unwrap()
+    );
+
+    // The config survives a reload
+    let wasm = wat::parse_file("tests/fixtures/echo.wat").unwrap();
+    policy.reload_wasm(&wasm).unwrap();
+    assert_eq!(
+        result_set("input"),
+        policy.evaluate(&result_set("input")).unwrap()
+    );
+
+    let config = PolicyConfig {
+        entrypoint: Some("example/missing".to_string()),
+        ..config
+    };
+    let module = Arc::new(Module::from_bytes(&wasm).unwrap());
+    let err = Policy::with_config(module, config).err().unwrap();
+    assert!(matches!(err, Error::UnknownEntrypoint(ref name) if name == "example/missing"));
+}
+
+#[test]
+fn test_eval_fuel() {
+    let config = PolicyConfig {
+        eval_fuel: Some(1000),
+        ..PolicyConfig::default()
+    };
+    let mut policy = echo_policy_with_config(config.clone());
+    policy.set_data(&result_set("data")).unwrap();
+    assert_eq!(
+        result_set("data"),
+        policy.evaluate(&result_set("input")).unwrap()
+    );
+
+    let config = PolicyConfig {
+        eval_fuel: Some(1),
+        ..config
+    };
+    let mut policy = echo_policy_with_config(config.clone());
+    let err = policy.evaluate(&()).unwrap_err();
+    assert!(matches!(err, Error::FuelExhausted));
+    // Writing data isn't limited, and each evaluation gets the full amount
+    policy.set_data(&result_set("data")).unwrap();
+    assert!(matches!(policy.evaluate(&()), Err(Error::FuelExhausted)));
+
+    let wasm = wat::parse_file("tests/fixtures/echo.wat").unwrap();
+    policy.reload_wasm(&wasm).unwrap();
+    assert!(matches!(policy.evaluate(&()), Err(Error::FuelExhausted)));
+
+    let module = Arc::new(Module::from_bytes(&wasm).unwrap());
+    assert!(!module.is_metered());
+    let err = Policy::with_config(module, config).err().unwrap();
+    assert!(matches!(err, Error::UnmeteredModule));
+}
+
+#[test]
+fn test_evaluate_json_opa_eval() {
+    let config = PolicyConfig {
+        entrypoint: Some("example/input".to_string()),
+        ..PolicyConfig::default()
+    };
+    let mut policy = echo_policy_with_config(config.clone());
+    let input = serde_json::json!([{"result": "input"}]);
+    assert_eq!(
+        r#"[{"result":"input"}]"#,
+        policy.evaluate_json(&input).unwrap()
+    );
+    assert_eq!("[]", echo_policy().evaluate_json(&input).unwrap());
+
+    let err = policy.evaluate_json(&serde_json::json!({"result": "input"}));
+    assert!(matches!(err, Err(Error::MalformedResult(_))));
+    let err = policy.evaluate_json(&serde_json::json!(["input"]));
+    assert!(matches!(err, Err(Error::MalformedResult(_))));
+
+    let config = PolicyConfig {
+        require_defined: vec!["example/input".to_string()],
+        ..config
+    };
+    let mut policy = echo_policy_with_config(config);
+    let err = policy.evaluate_json(&serde_json::json!([])).unwrap_err();
+    assert!(matches!(err, Error::Undefined(ref name) if name == "example/input"));
+    assert!(policy.evaluate_json(&input).is_ok());
+}
+
+#[test]
+fn test_take_prints_println() {
+    let mut policy = echo_policy();
+    policy.set_data(&result_set("data")).unwrap();
+    assert!(policy.take_prints().is_empty());
+
+    policy.evaluate(&()).unwrap();
+    assert_eq!(vec!["eval".to_string()], policy.take_prints());
+    assert!(policy.take_prints().is_empty());
+
+    // Each evaluation starts with an empty buffer
+    policy.evaluate(&()).unwrap();
+    policy.evaluate(&()).unwrap();
+    assert_eq!(vec!["eval".to_string()], policy.take_prints());
+}
+
+#[test]
+fn test_read_data_path_named() {
+    let mut policy = echo_policy();
+    let decision = serde_json::json!({"limits": {"max": 42}});
+    let mut binding = Map::new();
+    binding.insert(
+        "result".to_string(),
+        serde_json::from_value(decision).unwrap(),
+    );
+    policy
+        .set_data(&Value::from_set_iter(vec![Value::Object(binding)]))
+        .unwrap();
+
+    // Full paths are resolved against the entrypoint, example/data
+    let max = policy
+        .read_data_path("data.example.data.limits.max")
+        .unwrap();
+    assert_eq!(Value::from(42), max);
+    assert_eq!(max, policy.read_data_path("limits.max").unwrap());
+
+    let err = policy.read_data_path("data.other.limits.max").unwrap_err();
+    assert!(matches!(err, Error::Undefined(ref path) if path == "data.other.limits.max"));
+}
+
+#[test]
+fn test_evaluate_json_sets() {
+    let config = PolicyConfig {
+        entrypoint: Some("example/input".to_string()),
+        ..PolicyConfig::default()
+    };
+    let mut policy = echo_policy_with_config(config);
+
+    // The raw input comes back, so this shows how sets were written
+    let mut binding = Map::new();
+    let tags = Value::from_set_iter(vec![Value::from("b"), Value::from("a")]);
+    binding.insert("result".to_string(), tags);
+    let input = Value::from_set_iter(vec![Value::Object(binding)]);
+    assert_eq!(
+        r#"[{"result":["a","b"]}]"#,
+        policy.evaluate_json(&input).unwrap()
+    );
+}
+
+#[test]
+fn test_evaluate_cached_input_transform() {
+    // Each transformed input is different, so caching the first decision
+    // would be wrong
+    let count = Arc::new(AtomicUsize::new(0));
+    let counter = count.clone();
+    let config = PolicyConfig {
+        entrypoint: Some("example/input".to_string()),
+        decision_cache_size: 4,
+        input_transform: Some(InputTransform::new(move |input| {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            *input = result_set(&n.to_string());
+        })),
+        ..PolicyConfig::default()
+    };
+    let mut policy = echo_policy_with_config(config);
+
+    assert_eq!(result_set("0"), policy.evaluate_cached(&()).unwrap());
+    assert_eq!(result_set("1"), policy.evaluate_cached(&()).unwrap());
+    assert_eq!(2, count.load(Ordering::SeqCst));
+}
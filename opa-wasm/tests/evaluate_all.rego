@@ -0,0 +1,8 @@
+package tests
+
+users = ["alice", "bob", "carol"]
+
+admins[user] {
+    user := users[_]
+    user != "bob"
+}
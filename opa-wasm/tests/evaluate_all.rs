@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use opa_wasm::{Policy, Value};
+
+#[test]
+fn test_evaluate_all() {
+    let module =
+        opa_go::wasm::compile("x = data.tests.users[_]", "tests/evaluate_all.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let mut results = policy.evaluate_all(&()).unwrap();
+    results.sort();
+    let expected = vec![
+        Value::from("alice"),
+        Value::from("bob"),
+        Value::from("carol"),
+    ];
+    assert_eq!(expected, results);
+}
+
+#[test]
+fn test_evaluate_all_partial_set() {
+    let module =
+        opa_go::wasm::compile("x = data.tests.admins[_]", "tests/evaluate_all.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let mut results = policy.evaluate_all(&()).unwrap();
+    results.sort();
+    assert_eq!(vec![Value::from("alice"), Value::from("carol")], results);
+}
+
+#[test]
+fn test_evaluate_all_empty() {
+    let module = opa_go::wasm::compile(
+        r#"x = data.tests.users[_]; x == "dave""#,
+        "tests/evaluate_all.rego",
+    )
+    .unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    assert!(policy.evaluate_all(&()).unwrap().is_empty());
+}
+
+#[test]
+fn test_evaluate_addr() {
+    let module =
+        opa_go::wasm::compile("x = data.tests.users[_]", "tests/evaluate_all.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let addr = policy.evaluate_addr(&()).unwrap();
+    let value: Value = policy.deserialize_addr(addr).unwrap();
+    let mut bindings: Vec<BTreeMap<String, String>> = policy.deserialize_addr(addr).unwrap();
+    bindings.sort();
+
+    assert_eq!(3, value.as_set().unwrap().len());
+    let users = bindings
+        .iter()
+        .map(|binding| binding["x"].as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["alice", "bob", "carol"], users);
+}
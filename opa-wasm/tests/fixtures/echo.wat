@@ -0,0 +1,106 @@
+;; A stand-in for a compiled policy with two entrypoints, for features the
+;; OPA version used by the other tests can't build. Entrypoint 0,
+;; example/data, returns the data as its result set and entrypoint 1,
+;; example/input, returns the input, so tests choose the result by passing
+;; a set of bindings.
+(module
+  (import "env" "memory" (memory 2))
+  (import "env" "opa_println" (func $println (param i32)))
+
+  (global $heap_ptr (mut i32) (i32.const 1024))
+  (global $heap_top (mut i32) (i32.const 1024))
+
+  ;; {"example/data": 0, "example/input": 1}, laid out as OPA values
+  ;; 64: object, 72 and 84: entries, 96 and 108: key strings,
+  ;; 120 and 136: numbers, 160 and 176: key bytes
+  (data (i32.const 64)
+    "\06\00\00\00" "\48\00\00\00"
+    "\60\00\00\00" "\78\00\00\00" "\54\00\00\00"
+    "\6c\00\00\00" "\88\00\00\00" "\00\00\00\00"
+    "\04\00\00\00" "\0c\00\00\00" "\a0\00\00\00"
+    "\04\00\00\00" "\0d\00\00\00" "\b0\00\00\00"
+    "\03\01\00\00\00\00\00\00" "\00\00\00\00\00\00\00\00"
+    "\03\01\00\00\00\00\00\00" "\01\00\00\00\00\00\00\00")
+  (data (i32.const 160) "example/data")
+  (data (i32.const 176) "example/input")
+
+  ;; Bump allocator, 8 byte aligned, growing the memory as needed
+  (func $malloc (export "opa_malloc") (param $len i32) (result i32)
+    (local $addr i32)
+    (local.set $addr (global.get $heap_ptr))
+    (global.set $heap_ptr
+      (i32.and
+        (i32.add (i32.add (local.get $addr) (local.get $len)) (i32.const 7))
+        (i32.const -8)))
+    (if (i32.gt_u (global.get $heap_ptr) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (drop (memory.grow
+          (i32.add
+            (i32.shr_u
+              (i32.sub (global.get $heap_ptr) (i32.mul (memory.size) (i32.const 65536)))
+              (i32.const 16))
+            (i32.const 1))))))
+    (if (i32.gt_u (global.get $heap_ptr) (global.get $heap_top))
+      (then (global.set $heap_top (global.get $heap_ptr))))
+    (local.get $addr))
+
+  (func (export "opa_json_parse") (param i32 i32) (result i32) (i32.const 0))
+  (func (export "opa_json_dump") (param i32) (result i32) (i32.const 0))
+  (func (export "opa_heap_ptr_get") (result i32) (global.get $heap_ptr))
+  (func (export "opa_heap_ptr_set") (param i32) (global.set $heap_ptr (local.get 0)))
+  (func (export "opa_heap_top_get") (result i32) (global.get $heap_top))
+  (func (export "opa_heap_top_set") (param i32) (global.set $heap_top (local.get 0)))
+
+  ;; A context holds the input, data, result and entrypoint, in that order
+  (func (export "opa_eval_ctx_new") (result i32)
+    (local $ctx i32)
+    (local.set $ctx (call $malloc (i32.const 16)))
+    (i32.store offset=8 (local.get $ctx) (i32.const 0))
+    (i32.store offset=12 (local.get $ctx) (i32.const 0))
+    (local.get $ctx))
+  (func (export "opa_eval_ctx_set_input") (param $ctx i32) (param $input i32)
+    (i32.store (local.get $ctx) (local.get $input)))
+  (func (export "opa_eval_ctx_set_data") (param $ctx i32) (param $data i32)
+    (i32.store offset=4 (local.get $ctx) (local.get $data)))
+  (func (export "opa_eval_ctx_set_entrypoint") (param $ctx i32) (param $entrypoint i32)
+    (i32.store offset=12 (local.get $ctx) (local.get $entrypoint)))
+  (func (export "opa_eval_ctx_get_result") (param $ctx i32) (result i32)
+    (i32.load offset=8 (local.get $ctx)))
+
+  ;; eval prints a message on every call
+  (data (i32.const 208) "eval\00")
+  (func (export "eval") (param $ctx i32) (result i32)
+    (call $println (i32.const 208))
+    (i32.store offset=8 (local.get $ctx)
+      (select
+        (i32.load (local.get $ctx))
+        (i32.load offset=4 (local.get $ctx))
+        (i32.load offset=12 (local.get $ctx))))
+    (i32.const 0))
+
+  (func (export "entrypoints") (result i32) (i32.const 64))
+
+  ;; ABI 1.2's single call evaluation. Values can't be dumped as JSON here,
+  ;; so entrypoint 1 returns the raw input as its result set, and entrypoint
+  ;; 0 an empty one.
+  (data (i32.const 192) "[]\00")
+  (func (export "opa_eval")
+    (param $reserved i32) (param $entrypoint i32) (param $data i32)
+    (param $input i32) (param $len i32) (param $heap i32) (param $format i32)
+    (result i32)
+    (local $out i32)
+    (local $i i32)
+    (if (i32.eqz (local.get $entrypoint))
+      (then (return (i32.const 192))))
+    (global.set $heap_ptr (local.get $heap))
+    (local.set $out (call $malloc (i32.add (local.get $len) (i32.const 1))))
+    (block $done
+      (loop $copy
+        (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+        (i32.store8
+          (i32.add (local.get $out) (local.get $i))
+          (i32.load8_u (i32.add (local.get $input) (local.get $i))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $copy)))
+    (i32.store8 (i32.add (local.get $out) (local.get $len)) (i32.const 0))
+    (local.get $out)))
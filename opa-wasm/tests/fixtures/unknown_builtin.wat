@@ -0,0 +1,57 @@
+;; A module whose builtin table doesn't match the calls it makes: the table
+;; only has upper, with id 0, but eval calls id 1. Evaluation otherwise
+;; returns the input as the result set.
+(module
+  (import "env" "memory" (memory 2))
+  (import "env" "opa_builtin1" (func $builtin1 (param i32 i32 i32) (result i32)))
+
+  (global $heap_ptr (mut i32) (i32.const 1024))
+  (global $heap_top (mut i32) (i32.const 1024))
+
+  ;; {"upper": 0}, laid out as an OPA value
+  ;; 64: object, 72: entry, 84: key string, 96: number, 160: key bytes
+  (data (i32.const 64)
+    "\06\00\00\00" "\48\00\00\00"
+    "\54\00\00\00" "\60\00\00\00" "\00\00\00\00"
+    "\04\00\00\00" "\05\00\00\00" "\a0\00\00\00"
+    "\03\01\00\00\00\00\00\00" "\00\00\00\00\00\00\00\00")
+  (data (i32.const 160) "upper")
+
+  ;; Bump allocator, 8 byte aligned, without growing the memory
+  (func $malloc (export "opa_malloc") (param $len i32) (result i32)
+    (local $addr i32)
+    (local.set $addr (global.get $heap_ptr))
+    (global.set $heap_ptr
+      (i32.and
+        (i32.add (i32.add (local.get $addr) (local.get $len)) (i32.const 7))
+        (i32.const -8)))
+    (if (i32.gt_u (global.get $heap_ptr) (global.get $heap_top))
+      (then (global.set $heap_top (global.get $heap_ptr))))
+    (local.get $addr))
+
+  (func (export "opa_json_parse") (param i32 i32) (result i32) (i32.const 0))
+  (func (export "opa_json_dump") (param i32) (result i32) (i32.const 0))
+  (func (export "opa_heap_ptr_get") (result i32) (global.get $heap_ptr))
+  (func (export "opa_heap_ptr_set") (param i32) (global.set $heap_ptr (local.get 0)))
+  (func (export "opa_heap_top_get") (result i32) (global.get $heap_top))
+  (func (export "opa_heap_top_set") (param i32) (global.set $heap_top (local.get 0)))
+
+  ;; A context holds the input, data and result, in that order
+  (func (export "opa_eval_ctx_new") (result i32)
+    (local $ctx i32)
+    (local.set $ctx (call $malloc (i32.const 12)))
+    (i32.store offset=8 (local.get $ctx) (i32.const 0))
+    (local.get $ctx))
+  (func (export "opa_eval_ctx_set_input") (param $ctx i32) (param $input i32)
+    (i32.store (local.get $ctx) (local.get $input)))
+  (func (export "opa_eval_ctx_set_data") (param $ctx i32) (param $data i32)
+    (i32.store offset=4 (local.get $ctx) (local.get $data)))
+  (func (export "opa_eval_ctx_get_result") (param $ctx i32) (result i32)
+    (i32.load offset=8 (local.get $ctx)))
+
+  (func (export "eval") (param $ctx i32) (result i32)
+    (drop (call $builtin1 (i32.const 1) (local.get $ctx) (i32.load (local.get $ctx))))
+    (i32.store offset=8 (local.get $ctx) (i32.load (local.get $ctx)))
+    (i32.const 0))
+
+  (func (export "builtins") (result i32) (i32.const 64)))
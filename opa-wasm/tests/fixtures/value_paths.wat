@@ -0,0 +1,80 @@
+;; A module exporting opa_value_add_path and opa_value_remove_path, which
+;; don't change the data but keep the last path they were called with.
+;; Evaluation returns that path as the result set, {{"result": path}}, so
+;; tests can check how paths are sent.
+(module
+  (import "env" "memory" (memory 2))
+
+  (global $heap_ptr (mut i32) (i32.const 1024))
+  (global $heap_top (mut i32) (i32.const 1024))
+  ;; The heap below this is never handed out again, to keep the last path
+  (global $pinned (mut i32) (i32.const 0))
+
+  ;; {{"result": path}}, laid out as OPA values
+  ;; 64: set, 72: set element, 80: object, 88: entry, whose value at 92 is
+  ;; the path, 100: key string, 160: key bytes
+  (data (i32.const 64)
+    "\07\00\00\00" "\48\00\00\00"
+    "\50\00\00\00" "\00\00\00\00"
+    "\06\00\00\00" "\58\00\00\00"
+    "\64\00\00\00" "\00\00\00\00" "\00\00\00\00"
+    "\04\00\00\00" "\06\00\00\00" "\a0\00\00\00")
+  (data (i32.const 160) "result")
+
+  ;; Bump allocator, 8 byte aligned, growing the memory as needed
+  (func $malloc (export "opa_malloc") (param $len i32) (result i32)
+    (local $addr i32)
+    (local.set $addr (global.get $heap_ptr))
+    (global.set $heap_ptr
+      (i32.and
+        (i32.add (i32.add (local.get $addr) (local.get $len)) (i32.const 7))
+        (i32.const -8)))
+    (if (i32.gt_u (global.get $heap_ptr) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (drop (memory.grow
+          (i32.add
+            (i32.shr_u
+              (i32.sub (global.get $heap_ptr) (i32.mul (memory.size) (i32.const 65536)))
+              (i32.const 16))
+            (i32.const 1))))))
+    (if (i32.gt_u (global.get $heap_ptr) (global.get $heap_top))
+      (then (global.set $heap_top (global.get $heap_ptr))))
+    (local.get $addr))
+
+  (func (export "opa_json_parse") (param i32 i32) (result i32) (i32.const 0))
+  (func (export "opa_json_dump") (param i32) (result i32) (i32.const 0))
+  (func (export "opa_heap_ptr_get") (result i32) (global.get $heap_ptr))
+  (func (export "opa_heap_ptr_set") (param $ptr i32)
+    (global.set $heap_ptr
+      (select (local.get $ptr) (global.get $pinned)
+        (i32.gt_u (local.get $ptr) (global.get $pinned)))))
+  (func (export "opa_heap_top_get") (result i32) (global.get $heap_top))
+  (func (export "opa_heap_top_set") (param i32) (global.set $heap_top (local.get 0)))
+
+  ;; A context holds the input, data and result, in that order
+  (func (export "opa_eval_ctx_new") (result i32)
+    (local $ctx i32)
+    (local.set $ctx (call $malloc (i32.const 12)))
+    (i32.store offset=8 (local.get $ctx) (i32.const 0))
+    (local.get $ctx))
+  (func (export "opa_eval_ctx_set_input") (param $ctx i32) (param $input i32)
+    (i32.store (local.get $ctx) (local.get $input)))
+  (func (export "opa_eval_ctx_set_data") (param $ctx i32) (param $data i32)
+    (i32.store offset=4 (local.get $ctx) (local.get $data)))
+  (func (export "opa_eval_ctx_get_result") (param $ctx i32) (result i32)
+    (i32.load offset=8 (local.get $ctx)))
+
+  (func (export "eval") (param $ctx i32) (result i32)
+    (i32.store offset=8 (local.get $ctx) (i32.const 64))
+    (i32.const 0))
+
+  (func $keep (param $path i32)
+    (i32.store (i32.const 92) (local.get $path))
+    (global.set $pinned (global.get $heap_ptr)))
+  (func (export "opa_value_add_path") (param $base i32) (param $path i32) (param $value i32)
+    (result i32)
+    (call $keep (local.get $path))
+    (i32.const 0))
+  (func (export "opa_value_remove_path") (param $base i32) (param $path i32) (result i32)
+    (call $keep (local.get $path))
+    (i32.const 0)))
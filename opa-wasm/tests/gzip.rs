@@ -0,0 +1,41 @@
+#![cfg(feature = "gzip")]
+
+use std::fs;
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use opa_wasm::{Error, Policy};
+
+#[test]
+fn test_from_wasm_gz() {
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&bytes).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let mut expected = Policy::from_wasm(&bytes).unwrap();
+    let mut policy = Policy::from_wasm_gz(&compressed).unwrap();
+    assert_eq!(
+        expected.evaluate(&()).unwrap(),
+        policy.evaluate(&()).unwrap()
+    );
+}
+
+#[test]
+fn test_from_wasm_gz_plain() {
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let mut policy = Policy::from_wasm_gz(&bytes).unwrap();
+    policy.evaluate(&()).unwrap();
+}
+
+#[test]
+fn test_from_wasm_gz_truncated() {
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&bytes).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let result = Policy::from_wasm_gz(&compressed[..compressed.len() / 2]);
+    assert!(matches!(result, Err(Error::Decompress(_))));
+}
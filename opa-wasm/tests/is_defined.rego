@@ -0,0 +1,5 @@
+package tests
+
+allow {
+    input.user == "alice"
+}
@@ -0,0 +1,48 @@
+use opa_wasm::{Policy, Value};
+
+#[test]
+fn test_is_defined() {
+    let module = opa_go::wasm::compile("data.tests.allow", "tests/is_defined.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let mut input = opa_wasm::value::Map::new();
+    input.insert("user".to_string(), Value::String("alice".to_string()));
+    assert!(policy.is_defined(&Value::Object(input.clone())).unwrap());
+
+    input.insert("user".to_string(), Value::String("bob".to_string()));
+    assert!(!policy.is_defined(&Value::Object(input)).unwrap());
+}
+
+#[test]
+fn test_evaluate_opt() {
+    let module = opa_go::wasm::compile("data.tests.allow", "tests/is_defined.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let mut input = opa_wasm::value::Map::new();
+    input.insert("user".to_string(), Value::String("alice".to_string()));
+    let result: Option<bool> = policy.evaluate_opt(&Value::Object(input.clone())).unwrap();
+    assert_eq!(Some(true), result);
+
+    input.insert("user".to_string(), Value::String("bob".to_string()));
+    let result: Option<bool> = policy.evaluate_opt(&Value::Object(input)).unwrap();
+    assert_eq!(None, result);
+}
+
+#[test]
+fn test_require_defined() {
+    let module = opa_go::wasm::compile("data.tests.allow", "tests/is_defined.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    let entrypoint = policy.default_entrypoint();
+    policy.require_defined(&entrypoint);
+
+    let mut input = opa_wasm::value::Map::new();
+    input.insert("user".to_string(), Value::String("alice".to_string()));
+    let result: Option<bool> = policy.evaluate_opt(&Value::Object(input.clone())).unwrap();
+    assert_eq!(Some(true), result);
+
+    input.insert("user".to_string(), Value::String("bob".to_string()));
+    let err = policy
+        .evaluate_opt::<_, bool>(&Value::Object(input))
+        .unwrap_err();
+    assert!(matches!(err, opa_wasm::Error::Undefined(_)));
+}
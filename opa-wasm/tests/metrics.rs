@@ -0,0 +1,57 @@
+#![cfg(feature = "metrics")]
+
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+use std::time::Duration;
+
+use opa_wasm::metrics::Recorder;
+use opa_wasm::Policy;
+
+#[derive(Clone, Default)]
+struct MockRecorder {
+    durations: Rc<RefCell<Vec<Duration>>>,
+}
+
+impl Recorder for MockRecorder {
+    fn record(&self, duration: Duration) {
+        self.durations.borrow_mut().push(duration);
+    }
+}
+
+#[test]
+fn test_recorder_called_per_evaluation() {
+    let recorder = MockRecorder::default();
+    let mut policy = Policy::from_wasm(fs::read("tests/empty.wasm").unwrap()).unwrap();
+    policy.set_recorder(recorder.clone());
+
+    policy.evaluate(&()).unwrap();
+    assert_eq!(1, recorder.durations.borrow().len());
+
+    policy.evaluate(&()).unwrap();
+    policy.evaluate(&()).unwrap();
+    assert_eq!(3, recorder.durations.borrow().len());
+}
+
+#[test]
+fn test_recorder_closure() {
+    let count = Rc::new(RefCell::new(0));
+    let mut policy = Policy::from_wasm(fs::read("tests/empty.wasm").unwrap()).unwrap();
+    let counter = count.clone();
+    policy.set_recorder(move |_| *counter.borrow_mut() += 1);
+
+    policy.evaluate(&()).unwrap();
+    assert_eq!(1, *count.borrow());
+}
+
+#[test]
+fn test_recorder_kept_on_reload() {
+    let recorder = MockRecorder::default();
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let mut policy = Policy::from_wasm(&bytes).unwrap();
+    policy.set_recorder(recorder.clone());
+
+    policy.reload_wasm(&bytes).unwrap();
+    policy.evaluate(&()).unwrap();
+    assert_eq!(1, recorder.durations.borrow().len());
+}
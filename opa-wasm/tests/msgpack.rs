@@ -0,0 +1,42 @@
+#![cfg(feature = "msgpack")]
+
+use std::fs;
+use std::sync::Arc;
+
+use opa_wasm::value::Map;
+use opa_wasm::{Module, Policy, Value};
+
+#[test]
+fn test_evaluate_msgpack() {
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let mut policy = Policy::from_wasm(&bytes).unwrap();
+
+    let json = r#"{"user": "alice", "roles": ["admin"], "limits": {"cpu": 1.5}}"#;
+    let input = serde_json::from_str::<Value>(json).unwrap();
+    let expected = serde_json::Value::from(policy.evaluate(&input).unwrap());
+
+    let packed = rmp_serde::to_vec_named(&input).unwrap();
+    let result = policy.evaluate_msgpack(&packed).unwrap();
+    let decoded = rmp_serde::from_slice::<serde_json::Value>(&result).unwrap();
+    assert_eq!(expected, decoded);
+}
+
+#[test]
+fn test_evaluate_msgpack_sets() {
+    // The echo module's example/data entrypoint returns the data as the
+    // result set
+    let wasm = wat::parse_file("tests/fixtures/echo.wat").unwrap();
+    let module = Arc::new(Module::from_bytes(&wasm).unwrap());
+    let mut policy = Policy::with_config(module, Default::default()).unwrap();
+    let mut binding = Map::new();
+    let tags = Value::from_set_iter(vec![Value::from("b"), Value::from("a")]);
+    binding.insert("result".to_string(), tags);
+    policy
+        .set_data(&Value::from_set_iter(vec![Value::Object(binding)]))
+        .unwrap();
+
+    let packed = rmp_serde::to_vec_named(&()).unwrap();
+    let result = policy.evaluate_msgpack(&packed).unwrap();
+    let decoded = rmp_serde::from_slice::<serde_json::Value>(&result).unwrap();
+    assert_eq!(serde_json::json!([{"result": ["a", "b"]}]), decoded);
+}
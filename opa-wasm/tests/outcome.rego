@@ -0,0 +1,5 @@
+package tests
+
+shout = upper(input.message)
+
+highest = max(input.scores)
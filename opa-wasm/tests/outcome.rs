@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use opa_wasm::{Error, EvalOutcome, Module, Policy, PolicyConfig, Value};
+
+fn strict_policy(module: &[u8], config: PolicyConfig) -> Policy {
+    let module = Module::from_bytes(module).unwrap();
+    Policy::with_config(Arc::new(module), config).unwrap()
+}
+
+fn input(message: Value) -> Value {
+    let mut input = opa_wasm::value::Map::new();
+    input.insert("message".to_string(), message);
+    Value::Object(input)
+}
+
+#[test]
+fn test_evaluate_outcome() {
+    let module = opa_go::wasm::compile("data.tests.shout", "tests/outcome.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let outcome = policy.evaluate_outcome(&input("hi".into())).unwrap();
+    assert_eq!(EvalOutcome::Value("HI".into()), outcome);
+
+    let outcome = policy.evaluate_outcome(&Value::Null).unwrap();
+    assert_eq!(EvalOutcome::Undefined, outcome);
+
+    match policy.evaluate_outcome(&input(1.into())).unwrap() {
+        EvalOutcome::BuiltinError(message) => assert!(message.starts_with("upper: ")),
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+
+    // Errors don't carry over to the next evaluation
+    let outcome = policy.evaluate_outcome(&input("hi".into())).unwrap();
+    assert_eq!(EvalOutcome::Value("HI".into()), outcome);
+}
+
+#[test]
+fn test_max_of_empty_is_undefined() {
+    let module = opa_go::wasm::compile("data.tests.highest", "tests/outcome.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let scores = |scores: Vec<Value>| {
+        let mut input = opa_wasm::value::Map::new();
+        input.insert("scores".to_string(), Value::Array(scores));
+        Value::Object(input)
+    };
+
+    let outcome = policy
+        .evaluate_outcome(&scores(vec!["b".into(), "c".into(), "a".into()]))
+        .unwrap();
+    assert_eq!(EvalOutcome::Value("c".into()), outcome);
+
+    // An empty collection has no max, which is not an error
+    let outcome = policy.evaluate_outcome(&scores(vec![])).unwrap();
+    assert_eq!(EvalOutcome::Undefined, outcome);
+}
+
+#[test]
+fn test_lax_builtin_errors() {
+    let module = opa_go::wasm::compile("data.tests.shout", "tests/outcome.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    // A failed builtin leaves the rule undefined
+    let result = policy.evaluate_opt::<_, Value>(&input(1.into())).unwrap();
+    assert_eq!(None, result);
+}
+
+#[test]
+fn test_strict_builtin_errors() {
+    let module = opa_go::wasm::compile("data.tests.shout", "tests/outcome.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    policy.set_strict_builtin_errors(true);
+
+    match policy.evaluate(&input(1.into())) {
+        Err(Error::BuiltinFailed(message)) => assert!(message.starts_with("upper: ")),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    // Strict mode still reports failures as outcomes
+    match policy.evaluate_outcome(&input(1.into())).unwrap() {
+        EvalOutcome::BuiltinError(message) => assert!(message.starts_with("upper: ")),
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+
+    let result = policy
+        .evaluate_opt::<_, Value>(&input("hi".into()))
+        .unwrap();
+    assert_eq!(Some("HI".into()), result);
+
+    // Undefined results from builtins are not failures
+    let module = opa_go::wasm::compile("data.tests.highest", "tests/outcome.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    policy.set_strict_builtin_errors(true);
+    let mut scores = opa_wasm::value::Map::new();
+    scores.insert("scores".to_string(), Value::Array(vec![]));
+    let result = policy
+        .evaluate_opt::<_, Value>(&Value::Object(scores))
+        .unwrap();
+    assert_eq!(None, result);
+}
+
+#[test]
+fn test_strict_builtin_ids() {
+    let module = opa_go::wasm::compile("data.tests.shout", "tests/outcome.rego").unwrap();
+    let config = PolicyConfig {
+        strict_builtin_ids: true,
+        ..PolicyConfig::default()
+    };
+    let mut policy = strict_policy(&module, config);
+
+    let result = policy
+        .evaluate_opt::<_, Value>(&input("hi".into()))
+        .unwrap();
+    assert_eq!(Some("HI".into()), result);
+
+    // A known builtin failing is not an unknown id, so it stays undefined
+    let result = policy.evaluate_opt::<_, Value>(&input(1.into())).unwrap();
+    assert_eq!(None, result);
+}
+
+#[test]
+fn test_strict_builtin_ids_mismatched_table() {
+    // The module calls a builtin id that isn't in its table
+    let module = wat::parse_file("tests/fixtures/unknown_builtin.wat").unwrap();
+    let results = Value::from_set_iter(vec![input("hi".into())]);
+
+    let mut policy = strict_policy(&module, PolicyConfig::default());
+    assert_eq!(results, policy.evaluate(&results).unwrap());
+
+    let config = PolicyConfig {
+        strict_builtin_ids: true,
+        ..PolicyConfig::default()
+    };
+    let mut policy = strict_policy(&module, config);
+    let err = policy.evaluate(&results).unwrap_err();
+    assert!(matches!(err, Error::UnknownBuiltinId(1)));
+}
+
+#[test]
+fn test_builtin_import_arity() {
+    let wat = std::fs::read_to_string("tests/fixtures/unknown_builtin.wat").unwrap();
+    let with_import = |name: &str, params: usize| {
+        let import = format!(
+            "(import \"env\" \"{}\" (func (param{}) (result i32)))",
+            name,
+            " i32".repeat(params)
+        );
+        let wat = wat.replacen("(global", &format!("{}\n  (global", import), 1);
+        let module = Module::from_bytes(wat::parse_str(wat).unwrap()).unwrap();
+        Policy::with_config(Arc::new(module), PolicyConfig::default())
+    };
+
+    // Builtins take the id and context, then up to four arguments
+    assert!(with_import("opa_builtin4", 6).is_ok());
+    assert!(with_import("opa_builtin5", 7).is_err());
+}
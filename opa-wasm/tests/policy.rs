@@ -0,0 +1,815 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use opa_wasm::value::Map;
+use opa_wasm::{
+    DataHandle, Error, EvalOutcome, MalformedLine, Module, Policy, PolicyConfig, Value,
+};
+
+fn empty_policy() -> Policy {
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    Policy::from_wasm(&bytes).unwrap()
+}
+
+fn empty_policy_with_config(config: PolicyConfig) -> Policy {
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let module = Module::from_bytes(&bytes).unwrap();
+    Policy::with_config(Arc::new(module), config).unwrap()
+}
+
+#[test]
+fn test_memory_usage() {
+    let mut policy = empty_policy();
+    let before = policy.memory_usage().unwrap();
+    assert_eq!(before.base_heap_ptr, before.data_heap_ptr);
+    assert!(before.pages > 0);
+
+    let data = (0..1000)
+        .map(|i| (format!("key{}", i), i))
+        .collect::<HashMap<String, i64>>();
+    policy.set_data(&data).unwrap();
+
+    let after = policy.memory_usage().unwrap();
+    assert_eq!(before.base_heap_ptr, after.base_heap_ptr);
+    assert!(after.data_heap_ptr > before.data_heap_ptr);
+    assert!(after.heap_ptr > before.heap_ptr);
+}
+
+#[test]
+fn test_take_prints_empty() {
+    let mut policy = empty_policy();
+    policy.evaluate(&()).unwrap();
+    assert!(policy.take_prints().is_empty());
+}
+
+#[test]
+fn test_evaluate_or_undefined() {
+    let module = opa_go::wasm::compile("data.tests.allow", "tests/is_defined.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    let fallback = Value::String("fallback".to_string());
+
+    let alice = serde_json::json!({"user": "alice"});
+    let result = policy.evaluate_or(&alice, fallback.clone()).unwrap();
+    assert_eq!(Value::Bool(true), result);
+
+    // allow has no default, so it's undefined for anyone else
+    let bob = serde_json::json!({"user": "bob"});
+    let result = policy.evaluate_or(&bob, fallback.clone()).unwrap();
+    assert_eq!(fallback, result);
+}
+
+#[test]
+fn test_last_metrics() {
+    let mut policy = empty_policy();
+    let pages = policy.memory_usage().unwrap().pages;
+
+    let input = vec!["x".repeat(1024); 1024];
+    policy.evaluate(&input).unwrap();
+    let metrics = policy.last_metrics();
+    assert!(metrics.memory_grows > 0);
+    assert!(metrics.peak_pages > pages);
+    assert_eq!(policy.memory_usage().unwrap().pages, metrics.peak_pages);
+
+    policy.evaluate(&"small").unwrap();
+    let metrics = policy.last_metrics();
+    assert_eq!(0, metrics.memory_grows);
+    assert_eq!(policy.memory_usage().unwrap().pages, metrics.peak_pages);
+}
+
+#[test]
+fn test_last_metrics_after_reset() {
+    let mut policy = empty_policy();
+    let pages = policy.memory_usage().unwrap().pages;
+    let input = vec!["x".repeat(1024); 1024];
+
+    // These reset the heap again after evaluating, which must not clear the
+    // metrics of the evaluation
+    policy
+        .evaluate_with_data(&input, &Value::Object(Map::new()))
+        .unwrap();
+    assert!(policy.last_metrics().memory_grows > 0);
+    assert!(policy.last_metrics().peak_pages > pages);
+
+    let mut policy = empty_policy();
+    policy
+        .evaluate_with_overlay(&input, &Value::Object(Map::new()))
+        .unwrap();
+    assert!(policy.last_metrics().memory_grows > 0);
+}
+
+#[test]
+fn test_evaluate_ndjson() {
+    let mut policy = empty_policy();
+    let input = "{\"a\": 1}\n\n[1, 2]\n{\"a\": \n\"last\"";
+
+    let mut output = Vec::new();
+    let written = policy
+        .evaluate_ndjson(input.as_bytes(), &mut output, MalformedLine::Skip)
+        .unwrap();
+    assert_eq!(4, written);
+
+    let lines = String::from_utf8(output).unwrap();
+    let lines = lines.lines().collect::<Vec<_>>();
+    assert_eq!("[]", lines[0]);
+    assert_eq!("[]", lines[1]);
+    assert!(lines[2].starts_with("{\"error\":"));
+    assert_eq!("[]", lines[3]);
+
+    let mut output = Vec::new();
+    let err = policy
+        .evaluate_ndjson(input.as_bytes(), &mut output, MalformedLine::Abort)
+        .unwrap_err();
+    assert!(matches!(err, Error::JsonParse(_)));
+    assert_eq!("[]\n[]\n", String::from_utf8(output).unwrap());
+}
+
+// Raises the flag once the first result has been written.
+struct CancelAfterFirst {
+    output: Vec<u8>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Write for CancelAfterFirst {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.contains(&b'\n') {
+            self.cancel.store(true, Ordering::SeqCst);
+        }
+        self.output.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_evaluate_ndjson_cancelled() {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let config = PolicyConfig {
+        cancel: Some(cancel.clone()),
+        ..PolicyConfig::default()
+    };
+    let mut policy = empty_policy_with_config(config);
+
+    let input = "{}\n{}\n{}\n";
+    let mut writer = CancelAfterFirst {
+        output: Vec::new(),
+        cancel: cancel.clone(),
+    };
+    let err = policy
+        .evaluate_ndjson(input.as_bytes(), &mut writer, MalformedLine::Abort)
+        .unwrap_err();
+    assert!(matches!(err, Error::Cancelled));
+    assert_eq!("[]\n", String::from_utf8(writer.output).unwrap());
+
+    cancel.store(false, Ordering::SeqCst);
+    let mut output = Vec::new();
+    let written = policy
+        .evaluate_ndjson(input.as_bytes(), &mut output, MalformedLine::Abort)
+        .unwrap();
+    assert_eq!(3, written);
+}
+
+#[test]
+fn test_single_query_entrypoints() {
+    let policy = empty_policy();
+    assert!(policy.list_entrypoints().is_empty());
+
+    let err = policy.entrypoint("tests/allow").unwrap_err();
+    assert!(matches!(err, Error::UnknownEntrypoint(ref name) if name == "tests/allow"));
+}
+
+#[test]
+fn test_require_defined() {
+    let mut policy = empty_policy();
+    let entrypoint = policy.default_entrypoint();
+    assert_eq!(0, entrypoint.id());
+    assert_eq!("", entrypoint.name());
+    assert_eq!(
+        Value::Set(Default::default()),
+        policy.evaluate(&()).unwrap()
+    );
+
+    let config = PolicyConfig {
+        require_defined: vec![String::new()],
+        ..PolicyConfig::default()
+    };
+    let mut policy = empty_policy_with_config(config);
+    let err = policy.evaluate(&()).unwrap_err();
+    assert!(matches!(err, Error::Undefined(ref name) if name.is_empty()));
+    assert!(matches!(
+        policy.evaluate_opt::<_, Value>(&()),
+        Err(Error::Undefined(_))
+    ));
+}
+
+#[test]
+fn test_evaluate_outcome_undefined() {
+    let mut policy = empty_policy();
+    assert_eq!(
+        EvalOutcome::Undefined,
+        policy.evaluate_outcome(&()).unwrap()
+    );
+}
+
+#[test]
+fn test_input_serialize_error() {
+    let mut policy = empty_policy();
+
+    let err = policy.evaluate(&f64::NAN).unwrap_err();
+    assert!(matches!(err, Error::SerializeInput(_)));
+    assert!(err.to_string().contains("serializing input"));
+
+    let err = policy.evaluate_opt::<_, Value>(&f64::NAN).unwrap_err();
+    assert!(matches!(err, Error::SerializeInput(_)));
+}
+
+#[test]
+fn test_evaluate_all_undefined() {
+    let module = opa_go::wasm::compile("data.tests.allow", "tests/is_defined.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let alice = serde_json::json!({"user": "alice"});
+    assert_eq!(
+        vec![Value::Bool(true)],
+        policy.evaluate_all(&alice).unwrap()
+    );
+
+    let bob = serde_json::json!({"user": "bob"});
+    assert!(policy.evaluate_all(&bob).unwrap().is_empty());
+}
+
+#[test]
+fn test_evaluate_addr_empty() {
+    let mut policy = empty_policy();
+    let addr = policy.evaluate_addr(&()).unwrap();
+
+    let value: Value = policy.deserialize_addr(addr).unwrap();
+    assert_eq!(Value::Set(Default::default()), value);
+    let bindings: Vec<Value> = policy.deserialize_addr(addr).unwrap();
+    assert!(bindings.is_empty());
+}
+
+#[test]
+fn test_reload_wasm() {
+    let config = PolicyConfig {
+        decision_cache_size: 4,
+        ..PolicyConfig::default()
+    };
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let module = Arc::new(Module::from_bytes(&bytes).unwrap());
+    let mut policy = Policy::with_config(module, config).unwrap();
+    policy.set_data_json(r#"{"users": ["alice"]}"#).unwrap();
+    let data = policy.data().unwrap();
+
+    let expected = policy.evaluate(&()).unwrap();
+    assert_eq!(expected, policy.evaluate_cached(&()).unwrap());
+    assert_eq!(0, policy.version());
+
+    policy.reload_wasm(&bytes).unwrap();
+    assert_eq!(1, policy.version());
+    assert_eq!(data, policy.data().unwrap());
+    assert_eq!(expected, policy.evaluate_cached(&()).unwrap());
+
+    // More inputs than the cache holds
+    for i in 0..10 {
+        assert_eq!(expected, policy.evaluate_cached(&i).unwrap());
+    }
+
+    let err = policy.reload_wasm(b"not wasm").unwrap_err();
+    assert!(!matches!(err, Error::Initialization));
+    assert_eq!(1, policy.version());
+}
+
+#[test]
+fn test_reload() {
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let mut policy = empty_policy();
+    policy.set_data_json(r#"{"users": ["alice"]}"#).unwrap();
+    let old = policy.data().unwrap();
+
+    let mut data = Map::new();
+    data.insert("users".to_string(), Value::from(vec!["bob"]));
+    policy.reload(&bytes, &data).unwrap();
+    assert_eq!(1, policy.version());
+    assert_eq!(Value::Object(data.clone()), policy.data().unwrap());
+
+    // A failed reload keeps the module and data in use
+    policy.set_data_json(r#"{"users": ["alice"]}"#).unwrap();
+    assert!(policy.reload(b"not wasm", &data).is_err());
+    assert!(policy.reload(&bytes, &f64::NAN).is_err());
+    assert_eq!(1, policy.version());
+    assert_eq!(old, policy.data().unwrap());
+    assert_eq!(
+        empty_policy().evaluate(&()).unwrap(),
+        policy.evaluate(&()).unwrap()
+    );
+}
+
+#[test]
+fn test_read_data_path_undefined() {
+    let mut policy = empty_policy();
+    policy.set_data_json(r#"{"users": ["alice"]}"#).unwrap();
+    for path in &["users", "", "database.users"] {
+        match policy.read_data_path(path) {
+            Err(Error::Undefined(p)) => assert_eq!(*path, p),
+            _ => panic!("{} should be undefined", path),
+        }
+    }
+
+    // The module's entrypoint has no name to resolve full paths against
+    let err = policy.read_data_path("data.users").unwrap_err();
+    assert!(matches!(err, Error::UnknownEntrypoint(ref p) if p == "data.users"));
+}
+
+#[test]
+fn test_missing_builtins_export() {
+    // Rename the export, keeping its length so the module stays valid
+    let mut bytes = fs::read("tests/empty.wasm").unwrap();
+    let export = b"\x08builtins\x00";
+    let at = bytes
+        .windows(export.len())
+        .position(|w| w == export)
+        .unwrap();
+    bytes[at + 1..at + 9].copy_from_slice(b"builtinz");
+
+    let mut policy = Policy::from_wasm(&bytes).unwrap();
+    let expected = empty_policy().evaluate(&()).unwrap();
+    assert_eq!(expected, policy.evaluate(&()).unwrap());
+}
+
+#[test]
+fn test_patch_data() {
+    let mut policy = empty_policy();
+    policy
+        .set_data_json(r#"{"users": {"alice": {"role": "dev"}, "bob": {"role": "ops"}}}"#)
+        .unwrap();
+
+    policy
+        .patch_data("/users/alice/role", Value::from("admin"))
+        .unwrap();
+    policy.patch_data("/teams/dev", Value::from(1)).unwrap();
+    policy.evaluate(&()).unwrap();
+
+    let data = policy.data().unwrap();
+    assert_eq!(
+        Some(&Value::from("admin")),
+        data.pointer("/users/alice/role")
+    );
+    assert_eq!(Some(&Value::from("ops")), data.pointer("/users/bob/role"));
+    assert_eq!(Some(&Value::from(1)), data.pointer("/teams/dev"));
+
+    policy.remove_data("/users/bob").unwrap();
+    policy.remove_data("/users/carol").unwrap();
+    let data = policy.data().unwrap();
+    assert_eq!(None, data.pointer("/users/bob"));
+    assert!(data.pointer("/users/alice").is_some());
+
+    let err = policy
+        .patch_data("/users/alice/role/name", Value::Null)
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidPointer(_)));
+}
+
+// The module keeps the last path sent to opa_value_add_path or
+// opa_value_remove_path, and evaluates to it.
+fn value_paths_policy() -> Policy {
+    let wasm = wat::parse_file("tests/fixtures/value_paths.wat").unwrap();
+    let module = Module::from_bytes(&wasm).unwrap();
+    Policy::with_config(Arc::new(module), PolicyConfig::default()).unwrap()
+}
+
+fn last_path(policy: &mut Policy) -> serde_json::Value {
+    let path: Option<Value> = policy.evaluate_opt(&()).unwrap();
+    serde_json::Value::from(path.unwrap())
+}
+
+#[test]
+fn test_value_paths() {
+    let mut policy = value_paths_policy();
+    let data = serde_json::json!({"users": [{"name": "alice"}], "teams": {"0": "dev"}});
+    policy.set_data(&data).unwrap();
+
+    // Array indices are sent as numbers, object keys as strings
+    policy.remove_data("/users/0/name").unwrap();
+    assert_eq!(
+        serde_json::json!(["users", 0, "name"]),
+        last_path(&mut policy)
+    );
+    policy.patch_data("/teams/0", Value::from("ops")).unwrap();
+    assert_eq!(serde_json::json!(["teams", "0"]), last_path(&mut policy));
+    policy.patch_data("/users/1/name", Value::Null).unwrap();
+    assert_eq!(
+        serde_json::json!(["users", 1, "name"]),
+        last_path(&mut policy)
+    );
+    policy.patch_data("/groups/0", Value::Null).unwrap();
+    assert_eq!(serde_json::json!(["groups", "0"]), last_path(&mut policy));
+
+    // Both paths reject the empty pointer and malformed ones
+    for policy in &mut [value_paths_policy(), empty_policy()] {
+        let err = policy.remove_data("").unwrap_err();
+        assert!(matches!(err, Error::InvalidPointer(_)));
+        let err = policy.remove_data("users").unwrap_err();
+        assert!(matches!(err, Error::InvalidPointer(_)));
+    }
+}
+
+// Without pinning the fixture's add_path allocates nothing, like replacing
+// an existing key
+fn unpinned_value_paths_policy() -> Policy {
+    let wat = fs::read_to_string("tests/fixtures/value_paths.wat").unwrap();
+    let wat = wat.replacen("(global.set $pinned (global.get $heap_ptr))", "", 1);
+    let module = Module::from_bytes(wat::parse_str(wat).unwrap()).unwrap();
+    Policy::with_config(Arc::new(module), PolicyConfig::default()).unwrap()
+}
+
+#[test]
+fn test_patch_data_drops_paths() {
+    let mut policy = unpinned_value_paths_policy();
+    policy.set_data(&serde_json::json!({})).unwrap();
+
+    let path = "/a".repeat(32);
+    policy.patch_data(&path, Value::Null).unwrap();
+    let first = policy.memory_usage().unwrap().data_heap_ptr;
+    for _ in 0..100 {
+        policy.patch_data(&path, Value::Null).unwrap();
+    }
+
+    // Only each value is kept, not its 32 segment path
+    let grown = policy.memory_usage().unwrap().data_heap_ptr - first;
+    assert!(grown <= 100 * 16, "grew by {} bytes", grown);
+}
+
+#[test]
+fn test_evaluate_with_delta_sends_delta() {
+    let mut policy = unpinned_value_paths_policy();
+    let base = serde_json::json!({"user": {"name": "alice"}, "body": "x".repeat(4096)});
+    let before = policy.memory_usage().unwrap().data_heap_ptr;
+    policy
+        .set_base_input(serde_json::from_value(base).unwrap())
+        .unwrap();
+    let pinned = policy.memory_usage().unwrap().data_heap_ptr;
+    assert!(pinned - before > 4096);
+
+    for name in &["bob", "carol"] {
+        let delta = serde_json::json!({"user": {"name": name}});
+        let result = policy
+            .evaluate_with_delta(serde_json::from_value(delta).unwrap())
+            .unwrap();
+
+        // The delta's value is added to the base in the instance by path
+        assert_eq!(
+            serde_json::json!([{"result": ["user", "name"]}]),
+            serde_json::Value::from(result)
+        );
+
+        // and only the delta is written, not the 4KiB body again
+        let usage = policy.memory_usage().unwrap();
+        assert_eq!(pinned, usage.data_heap_ptr);
+        let written = usage.heap_ptr - usage.data_heap_ptr;
+        assert!(written < 1024, "wrote {} bytes", written);
+    }
+
+    // Setting the data writes the base input back above it
+    policy.set_data(&serde_json::json!({"a": 1})).unwrap();
+    assert!(policy.memory_usage().unwrap().data_heap_ptr > pinned);
+}
+
+#[test]
+fn test_set_data_json() {
+    let mut policy = empty_policy();
+    policy.set_data_json(r#"{"users": ["alice"]}"#).unwrap();
+    policy.evaluate(&()).unwrap();
+
+    let err = policy
+        .set_data_json(r#"{"users": ["alice", }"#)
+        .unwrap_err();
+    assert!(matches!(err, Error::JsonParse(_)));
+    assert!(err.to_string().contains(r#"{"users": ["alice", }"#));
+}
+
+#[test]
+fn test_set_data_json_error_keeps_data() {
+    let mut policy = empty_policy();
+    policy.set_data_json(r#"{"users": ["alice"]}"#).unwrap();
+    let data = policy.data().unwrap();
+    let before = policy.memory_usage().unwrap();
+
+    assert!(policy.set_data_json(r#"{"users": ["bob", }"#).is_err());
+    assert_eq!(data, policy.data().unwrap());
+    let after = policy.memory_usage().unwrap();
+    assert_eq!(before.data_heap_ptr, after.data_heap_ptr);
+
+    // Replacing the data reuses the heap rather than growing past the old data
+    policy.set_data_json(r#"{"users": ["carol"]}"#).unwrap();
+    assert_eq!(
+        before.data_heap_ptr,
+        policy.memory_usage().unwrap().data_heap_ptr
+    );
+    let data = policy.data().unwrap();
+    assert_eq!(
+        Some(&Value::Array(vec!["carol".into()])),
+        data.pointer("/users")
+    );
+}
+
+#[test]
+fn test_evaluate_with_overlay() {
+    let mut policy = empty_policy();
+    policy
+        .set_data_json(r#"{"users": {"alice": {"admin": false}}, "limit": 10}"#)
+        .unwrap();
+    let data = policy.data().unwrap();
+    let before = policy.memory_usage().unwrap();
+
+    let overlay =
+        serde_json::from_str::<Value>(r#"{"users": {"alice": {"admin": true}}}"#).unwrap();
+    policy.evaluate_with_overlay(&(), &overlay).unwrap();
+
+    assert_eq!(data, policy.data().unwrap());
+    let after = policy.memory_usage().unwrap();
+    assert_eq!(before.heap_ptr, after.heap_ptr);
+    assert_eq!(before.data_heap_ptr, after.data_heap_ptr);
+}
+
+#[test]
+fn test_evaluate_with_data() {
+    let module = opa_go::wasm::compile("data.tests.allow", "tests/reload.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    policy.set_data_json(r#"{"admins": ["alice"]}"#).unwrap();
+    let data = policy.data().unwrap();
+    let before = policy.memory_usage().unwrap();
+
+    let alice = serde_json::json!({"user": "alice"});
+    let bob = serde_json::json!({"user": "bob"});
+    let allowed = policy.evaluate(&alice).unwrap();
+    let denied = policy.evaluate(&bob).unwrap();
+    assert_ne!(allowed, denied);
+
+    // The call's data replaces the policy's, rather than merging with it
+    let call_data = serde_json::from_str::<Value>(r#"{"admins": ["bob"]}"#).unwrap();
+    assert_eq!(
+        allowed,
+        policy.evaluate_with_data(&bob, &call_data).unwrap()
+    );
+    assert_eq!(
+        denied,
+        policy.evaluate_with_data(&alice, &call_data).unwrap()
+    );
+
+    let after = policy.memory_usage().unwrap();
+    assert_eq!(before.heap_ptr, after.heap_ptr);
+    assert_eq!(before.data_heap_ptr, after.data_heap_ptr);
+    assert_eq!(data, policy.data().unwrap());
+    assert_eq!(denied, policy.evaluate(&bob).unwrap());
+}
+
+#[test]
+fn test_is_defined_empty() {
+    let mut policy = empty_policy();
+    assert!(!policy.is_defined(&()).unwrap());
+}
+
+#[test]
+fn test_from_module_shared() {
+    let bytes = opa_go::wasm::compile("data.tests.allow", "tests/reload.rego").unwrap();
+    let module = Arc::new(Module::from_bytes(&bytes).unwrap());
+
+    let mut first = Policy::from_module(module.clone()).unwrap();
+    let mut second = Policy::from_module(module.clone()).unwrap();
+    assert_eq!(3, Arc::strong_count(&module));
+
+    // Each policy has its own data
+    first.set_data_json(r#"{"admins": ["alice"]}"#).unwrap();
+    assert_eq!(Value::Object(Map::new()), second.data().unwrap());
+
+    let input = serde_json::json!({"user": "alice"});
+    assert_eq!(Some(true), first.evaluate_opt(&input).unwrap());
+    assert_eq!(Some(false), second.evaluate_opt(&input).unwrap());
+}
+
+#[test]
+fn test_evaluate_opt_undefined() {
+    let mut policy = empty_policy();
+    let result: Option<bool> = policy.evaluate_opt(&()).unwrap();
+    assert_eq!(None, result);
+}
+
+#[test]
+fn test_evaluate_json() {
+    let mut policy = empty_policy();
+    let expected = serde_json::Value::from(policy.evaluate(&()).unwrap());
+    let json = policy.evaluate_json(&()).unwrap();
+    assert_eq!(
+        expected,
+        serde_json::from_str::<serde_json::Value>(&json).unwrap()
+    );
+}
+
+#[test]
+fn test_prepared_input_invalidated_by_data() {
+    let mut policy = empty_policy();
+    let input = serde_json::from_str::<Value>(r#"{"user": "alice"}"#).unwrap();
+    let prepared = policy.prepare_input(&input).unwrap();
+    let usage = policy.memory_usage().unwrap();
+    assert_eq!(usage.data_heap_ptr, usage.heap_ptr);
+
+    let expected = policy.evaluate(&input).unwrap();
+    assert_eq!(expected, policy.evaluate_prepared(&prepared).unwrap());
+
+    policy.set_data_json(r#"{"limit": 10}"#).unwrap();
+    let err = policy.evaluate_prepared(&prepared).unwrap_err();
+    assert!(matches!(err, Error::PreparedInputMismatch(_, _)));
+
+    let prepared = policy.prepare_input(&input).unwrap();
+    assert_eq!(expected, policy.evaluate_prepared(&prepared).unwrap());
+}
+
+#[test]
+fn test_attach_data() {
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let module = Arc::new(Module::from_bytes(&bytes).unwrap());
+
+    let data = serde_json::from_str::<Value>(
+        r#"{"users": {"alice": {"roles": ["admin", "dev"]}}, "limit": 10.5}"#,
+    )
+    .unwrap();
+    let handle = DataHandle::new(&module, &data).unwrap();
+
+    let mut first = Policy::from_module(module.clone()).unwrap();
+    let mut second = Policy::from_module(module).unwrap();
+    first.attach_data(&handle).unwrap();
+    second.attach_data(&handle).unwrap();
+
+    assert_eq!(data, first.data().unwrap());
+    assert_eq!(data, second.data().unwrap());
+    assert_eq!(first.evaluate(&()).unwrap(), second.evaluate(&()).unwrap());
+
+    let mut expected = empty_policy();
+    expected.set_data(&data).unwrap();
+    assert_eq!(
+        expected.memory_usage().unwrap().data_heap_ptr,
+        first.memory_usage().unwrap().data_heap_ptr
+    );
+}
+
+#[test]
+fn test_evaluate_buffered_empty() {
+    let mut policy = empty_policy();
+    let input = serde_json::from_str::<Value>(r#"{"user": "alice"}"#).unwrap();
+    let expected = policy.evaluate(&input).unwrap();
+    for _ in 0..3 {
+        assert_eq!(expected, policy.evaluate_buffered(&input).unwrap());
+    }
+}
+
+#[test]
+fn test_max_result_depth() {
+    let mut policy = empty_policy();
+    policy.set_max_result_depth(0);
+    let err = policy.evaluate(&()).unwrap_err();
+    assert!(matches!(err, Error::TooDeep(0)));
+
+    policy.set_max_result_depth(1);
+    policy.evaluate(&()).unwrap();
+}
+
+#[test]
+fn test_with_config() {
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let module = Arc::new(Module::from_bytes(&bytes).unwrap());
+
+    let default = Policy::with_config(module.clone(), PolicyConfig::default()).unwrap();
+    let from_module = Policy::from_module(module.clone()).unwrap();
+    assert_eq!(
+        from_module.memory_usage().unwrap(),
+        default.memory_usage().unwrap()
+    );
+
+    let data = serde_json::from_str::<Value>(r#"{"users": ["alice"]}"#).unwrap();
+    let config = PolicyConfig {
+        data: Some(data.clone()),
+        max_result_depth: 0,
+        ..PolicyConfig::default()
+    };
+    let mut policy = Policy::with_config(module, config).unwrap();
+    assert_eq!(data, policy.data().unwrap());
+
+    policy.set_base_input(Value::Object(Map::new())).unwrap();
+    let err = policy.evaluate_with_delta(Value::Null).unwrap_err();
+    assert!(matches!(err, Error::TooDeep(0)));
+}
+
+// large_memory.wasm and small_memory.wasm are empty.wasm with the limits of
+// its memory import changed, from a minimum of 2 pages to a minimum of 10, and
+// to a maximum of 3.
+#[test]
+fn test_memory_import_minimum() {
+    let bytes = fs::read("tests/large_memory.wasm").unwrap();
+    let mut policy = Policy::from_wasm(&bytes).unwrap();
+    assert_eq!(10, policy.memory_usage().unwrap().pages);
+    policy.evaluate(&()).unwrap();
+}
+
+#[test]
+fn test_memory_import_maximum_too_small() {
+    let bytes = fs::read("tests/small_memory.wasm").unwrap();
+    let err = Policy::from_wasm(&bytes).err().unwrap();
+    assert!(matches!(
+        err,
+        Error::MemoryImportTooSmall {
+            max_pages: 3,
+            initial_pages: 5,
+        }
+    ));
+}
+
+#[test]
+fn test_memory_limit_exceeded() {
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let module = Arc::new(Module::from_bytes(&bytes).unwrap());
+    let config = PolicyConfig {
+        max_memory_pages: Some(5),
+        ..PolicyConfig::default()
+    };
+    let mut policy = Policy::with_config(module, config).unwrap();
+    policy.evaluate(&"small").unwrap();
+
+    let input = "x".repeat(6 * 65536);
+    let err = policy.evaluate(&input).unwrap_err();
+    match err {
+        Error::MemoryLimitExceeded {
+            requested_pages,
+            max_pages,
+        } => {
+            assert_eq!(5, max_pages);
+            assert!(requested_pages > 6);
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    // The heap is reset, so smaller inputs still fit
+    policy.evaluate(&"small").unwrap();
+}
+
+// Collects everything the fmt subscriber writes.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn capture_logs<F: FnOnce()>(f: F) -> String {
+    let capture = Capture::default();
+    let writer = capture.clone();
+    let subscriber = tracing_subscriber::fmt::Subscriber::builder()
+        .with_ansi(false)
+        .with_max_level(tracing::Level::DEBUG)
+        .with_writer(move || writer.clone())
+        .finish();
+    tracing::subscriber::with_default(subscriber, f);
+    let logs = capture.0.lock().unwrap();
+    String::from_utf8(logs.clone()).unwrap()
+}
+
+#[test]
+fn test_trace_addresses() {
+    let mut policy = empty_policy();
+    let logs = capture_logs(|| {
+        policy.evaluate(&vec![1, 2, 3]).unwrap();
+    });
+    assert!(!logs.contains("evaluated result"), "{}", logs);
+
+    let mut policy = empty_policy_with_config(PolicyConfig {
+        trace_addresses: true,
+        ..PolicyConfig::default()
+    });
+    let logs = capture_logs(|| {
+        policy.evaluate(&vec![1, 2, 3]).unwrap();
+    });
+    for message in &["wrote input", "created eval context", "evaluated result"] {
+        let line = logs
+            .lines()
+            .find(|line| line.contains(message))
+            .unwrap_or_else(|| panic!("no {:?} in {}", message, logs));
+        assert!(line.contains("addr=") && line.contains("size="), "{}", line);
+    }
+
+    let logs = capture_logs(|| {
+        policy.evaluate_buffered(&vec![1, 2, 3]).unwrap();
+    });
+    assert!(logs.contains("allocated input buffer"), "{}", logs);
+    assert!(logs.contains("parsed input"), "{}", logs);
+}
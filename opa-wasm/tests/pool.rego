@@ -0,0 +1,7 @@
+package tests
+
+default allow = false
+
+allow {
+    input.amount <= data.limits[input.user]
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "parallel")]
+
+use opa_wasm::{Policy, PolicyConfig, PolicyPool, Value};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Input {
+    user: String,
+    amount: i64,
+}
+
+#[test]
+fn test_evaluate_par() {
+    let module = opa_go::wasm::compile("data.tests.allow", "tests/pool.rego").unwrap();
+    let data: Value = serde_json::from_str(r#"{"limits": {"alice": 100, "bob": 10}}"#).unwrap();
+    let config = PolicyConfig {
+        data: Some(data.clone()),
+        ..PolicyConfig::default()
+    };
+    let pool = PolicyPool::with_config(module.clone(), config, 4).unwrap();
+
+    let inputs = (0..500)
+        .map(|i| Input {
+            user: ["alice", "bob", "carol"][i % 3].to_string(),
+            amount: (i * 7 % 150) as i64,
+        })
+        .collect::<Vec<_>>();
+
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    policy.set_data(&data).unwrap();
+    let expected = inputs
+        .iter()
+        .map(|input| policy.evaluate(input).unwrap())
+        .collect::<Vec<_>>();
+
+    let results: Vec<Value> = pool.evaluate_par(&inputs).unwrap();
+    assert_eq!(expected, results);
+
+    // Threads keep their policies between batches
+    let results: Vec<Value> = pool.evaluate_par(&inputs).unwrap();
+    assert_eq!(expected, results);
+}
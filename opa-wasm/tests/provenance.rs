@@ -0,0 +1,50 @@
+use opa_wasm::{Policy, Provenance, Value};
+
+#[test]
+fn test_provenance_from_opa_result() {
+    let results = opa_go::eval_query("x := 1; y := x + 1", &()).unwrap();
+    let result: Value = serde_json::from_value(results[0].clone()).unwrap();
+
+    let (value, provenance) = Provenance::from_result(result);
+    let provenance = provenance.unwrap();
+    assert_eq!(Value::Bool(true), value);
+    assert_eq!("x := 1", provenance.text);
+    assert_eq!(Some((1, 1)), provenance.location);
+}
+
+#[test]
+fn test_provenance_from_binding() {
+    let result = serde_json::from_str::<Value>(r#"{"x": "alice"}"#).unwrap();
+    let (value, provenance) = Provenance::from_result(result);
+    assert_eq!(Value::from("alice"), value);
+    assert_eq!(None, provenance);
+
+    // A location without a column is dropped
+    let result = serde_json::from_str::<Value>(
+        r#"{"expressions": [{"value": 2, "text": "1 + 1", "location": {"row": 3}}]}"#,
+    )
+    .unwrap();
+    let (value, provenance) = Provenance::from_result(result);
+    assert_eq!(Value::from(2), value);
+    let expected = Provenance {
+        text: "1 + 1".to_string(),
+        location: None,
+    };
+    assert_eq!(Some(expected), provenance);
+}
+
+#[test]
+fn test_evaluate_with_provenance_wasm() {
+    let module =
+        opa_go::wasm::compile("x = data.tests.users[_]", "tests/evaluate_all.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let mut results = policy.evaluate_with_provenance(&()).unwrap();
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    let expected = vec![
+        (Value::from("alice"), None),
+        (Value::from("bob"), None),
+        (Value::from("carol"), None),
+    ];
+    assert_eq!(expected, results);
+}
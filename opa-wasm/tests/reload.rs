@@ -0,0 +1,31 @@
+use opa_wasm::Policy;
+
+fn allowed(policy: &mut Policy, user: &str, amount: i64) -> bool {
+    let input = serde_json::json!({"user": user, "amount": amount});
+    let result: Option<bool> = policy.evaluate_opt(&input).unwrap();
+    result.unwrap_or(false)
+}
+
+#[test]
+fn test_reload_module_and_data() {
+    let admins = opa_go::wasm::compile("data.tests.allow", "tests/reload.rego").unwrap();
+    let limits = opa_go::wasm::compile("data.tests.allow", "tests/pool.rego").unwrap();
+
+    let mut policy = Policy::from_wasm(&admins).unwrap();
+    policy
+        .set_data(&serde_json::json!({"admins": ["alice"]}))
+        .unwrap();
+    assert!(allowed(&mut policy, "alice", 500));
+    assert!(!allowed(&mut policy, "bob", 5));
+
+    // The new module needs the new data, which the old module ignores
+    let data = serde_json::json!({"limits": {"bob": 10}});
+    policy.reload(&limits, &data).unwrap();
+    assert!(!allowed(&mut policy, "alice", 500));
+    assert!(allowed(&mut policy, "bob", 5));
+    assert!(!allowed(&mut policy, "bob", 50));
+
+    // A failed reload leaves both in place
+    assert!(policy.reload(b"not wasm", &serde_json::json!({})).is_err());
+    assert!(allowed(&mut policy, "bob", 5));
+}
@@ -0,0 +1,15 @@
+package tests
+
+default allow = false
+
+allow {
+    input.method == "GET"
+    input.path = ["finance", "salary", user]
+    input.user == user
+}
+
+allow {
+    input.method == "GET"
+    input.path = ["finance", "salary", _]
+    input.roles[_] == "manager"
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "request")]
+
+use opa_wasm::request::RequestInput;
+use opa_wasm::Policy;
+
+#[test]
+fn test_request_input() {
+    let module = opa_go::wasm::compile("data.tests.allow", "tests/request.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let allowed = |policy: &mut Policy, input: RequestInput| {
+        let result: Option<bool> = policy.evaluate_opt(&input).unwrap();
+        result.unwrap_or(false)
+    };
+
+    let own = RequestInput::new("GET", "/finance/salary/alice").user("alice");
+    assert!(allowed(&mut policy, own));
+
+    let other = RequestInput::new("GET", "/finance/salary/bob").user("alice");
+    assert!(!allowed(&mut policy, other.clone()));
+    assert!(allowed(&mut policy, other.field("roles", vec!["manager"])));
+
+    let write = RequestInput::new("post", "/finance/salary/alice").user("alice");
+    assert!(!allowed(&mut policy, write));
+}
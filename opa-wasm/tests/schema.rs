@@ -0,0 +1,53 @@
+#![cfg(feature = "schemars")]
+
+use opa_wasm::{Policy, Value};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+#[allow(dead_code)]
+#[derive(Deserialize, JsonSchema)]
+struct AllowReason {
+    allow: bool,
+    reason: String,
+    tags: Option<Vec<String>>,
+}
+
+#[test]
+fn test_result_schema() {
+    let schema = Policy::result_schema::<AllowReason>().unwrap();
+
+    assert_eq!(
+        Some("AllowReason"),
+        schema.at(&["title"]).and_then(Value::as_str)
+    );
+    assert_eq!(
+        Some("boolean"),
+        schema
+            .lookup("properties.allow.type")
+            .and_then(Value::as_str)
+    );
+    assert_eq!(
+        Some("string"),
+        schema
+            .lookup("properties.reason.type")
+            .and_then(Value::as_str)
+    );
+
+    let required = schema.lookup("required").unwrap().as_array().unwrap();
+    assert!(required.contains(&Value::from("allow")));
+    assert!(required.contains(&Value::from("reason")));
+    assert!(!required.contains(&Value::from("tags")));
+}
+
+#[test]
+fn test_result_schema_matches_decision() {
+    let schema = Policy::result_schema::<AllowReason>().unwrap();
+
+    let mut decision = opa_wasm::value::Map::new();
+    decision.insert("allow".to_string(), Value::Bool(true));
+    decision.insert("reason".to_string(), Value::from("user is an admin"));
+    assert!(Value::Object(decision.clone()).matches_schema(&schema));
+
+    decision.remove("reason");
+    assert!(!Value::Object(decision).matches_schema(&schema));
+}
@@ -0,0 +1,7 @@
+package tests
+
+default same_roles = false
+
+same_roles {
+    input.roles == {"admin", "dev"}
+}
@@ -0,0 +1,33 @@
+use std::collections::BTreeSet;
+
+use opa_wasm::set::Set;
+use opa_wasm::Policy;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Input {
+    roles: Set<BTreeSet<&'static str>>,
+}
+
+#[derive(Serialize)]
+struct ArrayInput {
+    roles: BTreeSet<&'static str>,
+}
+
+#[test]
+fn test_set_input() {
+    let module = opa_go::wasm::compile("data.tests.same_roles", "tests/sets.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    let roles = vec!["dev", "admin"].into_iter().collect::<BTreeSet<_>>();
+
+    let input = Input {
+        roles: Set::new(roles.clone()),
+    };
+    let result: Option<bool> = policy.evaluate_opt(&input).unwrap();
+    assert_eq!(Some(true), result);
+
+    // Without the wrapper the roles are an array, which never equals a set
+    let input = ArrayInput { roles };
+    let result: Option<bool> = policy.evaluate_opt(&input).unwrap();
+    assert_eq!(Some(false), result);
+}
@@ -0,0 +1,25 @@
+#![cfg(target_arch = "x86_64")]
+
+use std::fs;
+use std::sync::Arc;
+
+use opa_wasm::wasmtime::{Config, Engine, OptLevel, Store};
+use opa_wasm::{Module, Policy};
+
+#[test]
+fn test_module_with_store() {
+    let mut config = Config::new();
+    config.cranelift_opt_level(OptLevel::None);
+    let store = Store::new(&Engine::new(&config));
+
+    let bytes = fs::read("tests/empty.wasm").unwrap();
+    let module = Module::from_bytes_with_store(&store, &bytes).unwrap();
+    let mut policy = Policy::from_module(Arc::new(module)).unwrap();
+
+    let expected = Policy::from_wasm(&bytes).unwrap().evaluate(&()).unwrap();
+    assert_eq!(expected, policy.evaluate(&()).unwrap());
+
+    let module = Module::from_file_with_store(&store, "tests/empty.wasm").unwrap();
+    let mut other = Policy::from_module(Arc::new(module)).unwrap();
+    assert_eq!(expected, other.evaluate(&()).unwrap());
+}
@@ -0,0 +1,9 @@
+package tests
+
+after_cutoff {
+    input.now > time.parse_rfc3339_ns("2020-01-01T00:00:00Z")
+}
+
+expires_at = input.start + input.ttl
+
+ttl_doubled = input.ttl * 2
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use serde::Serialize;
+
+use opa_wasm::time::{OpaDuration, OpaTime};
+use opa_wasm::Policy;
+
+#[derive(Serialize)]
+struct Input {
+    now: OpaTime,
+}
+
+#[test]
+fn test_opa_time_input() {
+    let module = opa_go::wasm::compile("data.tests.after_cutoff", "tests/time.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+
+    let input = Input {
+        now: OpaTime(Utc.ymd(2020, 6, 1).and_hms(0, 0, 0)),
+    };
+    let result: Option<bool> = policy.evaluate_opt(&input).unwrap();
+    assert_eq!(Some(true), result);
+
+    let input = Input {
+        now: OpaTime(Utc.ymd(2019, 6, 1).and_hms(0, 0, 0)),
+    };
+    let result: Option<bool> = policy.evaluate_opt(&input).unwrap();
+    assert_eq!(None, result);
+}
+
+#[derive(Serialize)]
+struct Session {
+    start: OpaTime,
+    ttl: OpaDuration,
+}
+
+#[test]
+fn test_opa_duration_arithmetic() {
+    let session = Session {
+        start: OpaTime(Utc.ymd(2020, 6, 1).and_hms(0, 0, 0)),
+        ttl: OpaDuration(Duration::from_secs(90 * 60)),
+    };
+
+    let module = opa_go::wasm::compile("data.tests.expires_at", "tests/time.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    let result: Option<OpaTime> = policy.evaluate_opt(&session).unwrap();
+    assert_eq!(Some(OpaTime(Utc.ymd(2020, 6, 1).and_hms(1, 30, 0))), result);
+
+    let module = opa_go::wasm::compile("data.tests.ttl_doubled", "tests/time.rego").unwrap();
+    let mut policy = Policy::from_wasm(&module).unwrap();
+    let result: Option<OpaDuration> = policy.evaluate_opt(&session).unwrap();
+    assert_eq!(Some(OpaDuration(Duration::from_secs(3 * 60 * 60))), result);
+}